@@ -1,7 +1,20 @@
 // SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
 // SPDX-License-Identifier: MIT
 
+use crate::color;
+use crate::content;
+use crate::count;
+use crate::exec;
+use crate::expr::{self, Pred, Spelling, Token};
+use crate::hash;
+use crate::histogram;
+use crate::json;
 use crate::meta;
+use crate::pathstyle;
+use crate::printf;
+use crate::quote;
+use crate::sort;
+use crate::top;
 use anyhow::{anyhow, Error};
 use lexopt::prelude::*;
 use normpath::PathExt;
@@ -50,9 +63,20 @@ pub struct Args {
     /// File name (full path) matching regular expression (`-r`/`--regex`).
     pub regex: Option<Vec<String>>,
 
-    /// Case-insensitive matching (`-i`/`--case-insensitive`).
+    /// `--name` patterns that always match case-insensitively (`-iname`).
+    pub iname: Option<Vec<String>>,
+
+    /// `--regex` patterns that always match case-insensitively (`-iregex`).
+    pub iregex: Option<Vec<String>>,
+
+    /// Case-insensitive matching for every pattern (`-i`/`--case-insensitive`);
+    /// the `-i*` spellings make just their own pattern case-insensitive.
     pub case_insensitive: bool,
 
+    /// Keep entries matching the name globs *or* the regexes (`--any`);
+    /// by default an entry must satisfy both when both are given.
+    pub any: bool,
+
     /// Filter matches by type (`-t`/`--file-type`).
     pub file_type: Vec<FileType>,
 
@@ -63,13 +87,36 @@ pub struct Args {
     /// Glob patterns matched against the **full path** (`-path`/`-wholename`).
     pub path_glob: Option<Vec<String>>,
 
+    /// Case-insensitive full-path globs (`-ipath`/`-iwholename`).
+    pub ipath_glob: Option<Vec<String>>,
+
     /// Glob patterns matched against a symlink's **target** (`-lname`).
     pub lname: Option<Vec<String>>,
 
+    /// Case-insensitive symlink-target globs (`-ilname`).
+    pub ilname: Option<Vec<String>>,
+
     /// `faccessat` mode bits for `-readable`/`-writable`/`-executable`
     /// (see [`meta::access`]); 0 = no access check.
     pub access: u8,
 
+    /// Byte regex a regular file's contents must match (`--contains`),
+    /// tested after every other filter.
+    pub contains: Option<String>,
+
+    /// Files larger than this many bytes are never read for `--contains`
+    /// (`--max-filesize`).
+    pub max_filesize: Option<u64>,
+
+    /// MIME type globs, any of which a matched entry's type (from its first
+    /// bytes) must match (`--mime`).
+    pub mime: Option<Vec<String>>,
+
+    /// Whether a matched regular file must be text, binary, or text in one
+    /// encoding, judged from its first bytes (`--text`/`--binary`/
+    /// `--encoding`).
+    pub class: Option<content::Class>,
+
     /// Glob patterns whose matching entries (by file name) are excluded
     /// (`-E`/`--exclude`); a matched directory is pruned (not descended).
     pub exclude: Option<Vec<String>>,
@@ -78,6 +125,111 @@ pub struct Args {
     /// (`-0`/`--null`/`-print0`); for piping into `xargs -0` and friends.
     pub null: bool,
 
+    /// Output format (`--printf`/`-printf`), parsed from GNU find's `%`
+    /// directives; replaces the plain path + separator when set.
+    pub printf: Option<printf::Format>,
+
+    /// GNU find's `-ls` long listing (`--ls`/`-ls`); conflicts with
+    /// `--printf` and `--json`.
+    pub ls: bool,
+
+    /// Draw matches as a hierarchy once the walk is done (`--tree`).
+    pub tree: bool,
+
+    /// Print per-directory totals of the matches below instead of the
+    /// matches (`--summarize`).
+    pub summarize: bool,
+
+    /// Only directories this deep get a `--summarize` line
+    /// (`--summarize-depth`); implies `--summarize`.
+    pub summarize_depth: Option<usize>,
+
+    /// JSON Lines output (`--json[=FIELDS]`): one object per match with the
+    /// selected fields; conflicts with `--printf`.
+    pub json: Option<json::Format>,
+
+    /// Sort the whole output by this key once the walk is done (`--sort`).
+    pub sort: Option<sort::Key>,
+
+    /// Emit in find's sequential pre-order (`--ordered`); conflicts with
+    /// `--sort`.
+    pub ordered: bool,
+
+    /// Reverse the `--sort`/`--ordered` order, or make `--top` keep the
+    /// smallest keys (`--reverse`).
+    pub reverse: bool,
+
+    /// Print only the matches with the `N` largest `--by` keys (`--top`);
+    /// `None`/`0` = off.
+    pub top: Option<usize>,
+
+    /// What `--top` ranks on (`--by`).
+    pub by: top::By,
+
+    /// Print how many entries match instead of the matches (`-c`,
+    /// `--count[=type]`).
+    pub count: Option<count::By>,
+
+    /// Print match counts and sizes per bucket instead of the matches
+    /// (`--histogram`).
+    pub histogram: Option<histogram::By>,
+
+    /// Print groups of matched regular files with identical contents
+    /// instead of the matches (`--duplicates`).
+    pub duplicates: bool,
+
+    /// Print a `sha256sum`-style digest line per matched regular file
+    /// (`--hash`).
+    pub hash: Option<hash::Algo>,
+
+    /// How plain path output escapes hostile names (`--quote`); `None`
+    /// writes raw bytes, and lets the binary pick `shell` on a terminal.
+    pub quote: Option<quote::Style>,
+
+    /// Paint printed paths by `LS_COLORS` (`--color`); the binary resolves
+    /// `auto`, and the library only paints under `always`.
+    pub color: color::When,
+
+    /// The form printed and matched paths take (`--relative-to`,
+    /// `--absolute-path`, `--canonical`; the last one given wins).
+    pub path_style: pathstyle::Style,
+
+    /// Write matched paths (or `--printf`/`--json` lines). Off when the only
+    /// requested actions are commands or `--delete` (without `-print`), like
+    /// find.
+    pub print: bool,
+
+    /// `-exec` commands run on every match, in command-line order.
+    pub exec: Vec<exec::Command>,
+
+    /// Executor threads running `-exec` commands (`-j`/`--jobs`).
+    pub jobs: usize,
+
+    /// Capture each command's stdout/stderr and write them out together once
+    /// it exits (`--group-output`), so parallel commands do not interleave.
+    pub group_output: bool,
+
+    /// Stay silent about entries that vanish mid-walk (`ENOENT`), as
+    /// `-ignore_readdir_race` does in find; the last of it and
+    /// `-noignore_readdir_race` wins.
+    pub ignore_readdir_race: bool,
+
+    /// Count runtime errors toward the exit status without printing them
+    /// (`--quiet-errors`).
+    pub quiet_errors: bool,
+
+    /// Print a summary of the walk on stderr at exit (`--stats`).
+    pub stats: bool,
+
+    /// Remove every match (`--delete`), directories after their contents;
+    /// refused with `--follow-symlinks`.
+    pub delete: bool,
+
+    /// Boolean expression built when the command line uses an operator
+    /// (`!`, `-a`, `-o`, parentheses); ANDed after the flat filters above.
+    /// `None` for a plain AND chain, whose predicates land in the flat fields.
+    pub expr: Option<expr::Expr>,
+
     /// Paths to traverse (positional; at least one required).
     pub path: Vec<PathBuf>,
 }
@@ -122,12 +274,42 @@ Options:
       --min-depth <N>      Minimum depth to emit (shallower entries are skipped) [alias: -mindepth]
   -s, --max-scan-rate <N>  Max directories scanned per second (0 = unlimited)
       --max-results <N>    Stop after the first N results (0 = unlimited)
-  -n, --name <GLOB>        File-name globbing pattern (repeatable; conflicts with --regex) [aliases: -name; -iname adds -i]
-  -r, --regex <RE>         Full-path regular expression (repeatable; conflicts with --name) [aliases: -regex; -iregex adds -i]
-  -i, --case-insensitive   Case-insensitive glob/regex matching
+  -n, --name <GLOB>        File-name globbing pattern (repeatable, each must match) [aliases: -name; -iname ignores case]
+  -r, --regex <RE>         Full-path regular expression (repeatable, each must match) [aliases: -regex; -iregex ignores case]
+      --any                Keep entries matching --name or --regex (default: both must match)
+  -i, --case-insensitive   Case-insensitive matching for every glob/regex
   -E, --exclude <GLOB>     Exclude entries whose name matches GLOB; matched directories are pruned (repeatable)
   -0, --null               Terminate each path with NUL instead of newline [aliases: -print0, --print0]
-  -t, --file-type <TYPE>   Filter matches by type (repeatable) [default: directory file symlink] [alias: -type]
+      --exec <CMD>... ;    Run CMD for each match, every {} replaced by its path [alias: -exec]
+      --exec <CMD>... {} + Run CMD on as many matches per invocation as ARG_MAX allows
+      --execdir <CMD>... ; Like --exec, run inside each match's directory on ./NAME (also {} +) [alias: -execdir]
+      --okdir <CMD>... ;   Like --execdir ;, asking for confirmation on stdin first [alias: -okdir]
+  -j, --jobs <N>           Parallel --exec commands [default: logical CPU count]
+      --group-output       Print each command's stdout/stderr together once it exits
+      --print              Also print matches when --exec or --delete is given [alias: -print]
+      --delete             Delete matches, directories after their contents (not with -L) [alias: -delete]
+      --json[=FIELDS]      Print one JSON object per match (JSON Lines); FIELDS from path,name,depth,type,
+                           size,mtime,ctime,atime,mode,uid,gid,nlink,ino [default: path,name,depth,type]
+      --ls                 List matches like find -ls (inode, blocks, mode, links, owner, group, size, mtime) [alias: -ls]
+      --hash <ALGO>        Print a sha256, blake3 or xxh3 digest and the path per matched file, as sha256sum does
+      --tree               Draw matches as a tree under their starting points, with the directories leading to them
+      --summarize          Print each directory's total of the matches below (KiB allocated, bytes), like du
+      --summarize-depth <N>  Print --summarize totals only for directories up to N levels deep
+      --sort <KEY>         Sort all output by name, path, size, mtime or depth (ties by path)
+      --ordered            Emit in find's sequential pre-order, reading directories in parallel
+      --top <N>            Print only the N matches with the largest --by key, each after its key
+      --by <KEY>           Rank --top by size, mtime, atime or ctime [default: size]
+  -c, --count[=type]       Print the number of matches per starting point (and in total), optionally per type
+      --histogram <BY>     Print the count and total bytes of the matches per ext, size, age, owner or type bucket
+      --duplicates         Print groups of matched files with identical contents (hard links once), a blank line after each
+      --reverse            Reverse the --sort/--ordered order; --top keeps the smallest keys
+      --relative-to <ROOT|CWD>  Print paths relative to their starting point (which prints as .) or the current directory
+      --absolute-path      Print absolute paths (default)
+      --color <WHEN>       Color paths by LS_COLORS: auto, always or never [default: auto]
+      --quote <STYLE>      Escape printed paths: shell, c, escape or literal [default: shell on a terminal, else literal]
+      --canonical          Print paths with symlinked directories resolved
+      --printf <FORMAT>    Print each match through a find-style format (%p %f %s %M %u %T@ …, \n \0 \t; times in whole seconds) [alias: -printf]
+  -t, --file-type <TYPE>   Filter matches by type; TYPE,TYPE… keeps any of them [default: directory file symlink] [alias: -type]
                            values: empty, block-device, char-device, directory, pipe, file, socket, symlink
                            aliases: e, b, c, d, p, f, s, l
       --empty              Match empty files and directories (= --file-type empty) [alias: -empty]
@@ -141,13 +323,29 @@ Options:
       --inum <[+-]N>       Filter by inode number [alias: -inum]
       --newer, --anewer, --cnewer <FILE>  Entry's m/a/c-time is newer than FILE's mtime [aliases: -newer/-anewer/-cnewer]
       --nouser, --nogroup  Owner uid/gid resolves to no passwd/group entry [aliases: -nouser/-nogroup]
-      --path, --wholename <GLOB>  Glob over the full path (* crosses /) [aliases: -path/-wholename; -ipath/-iwholename add -i]
-      --lname <GLOB>       Glob over a symlink's target [alias: -lname; -ilname adds -i]
+      --path, --wholename <GLOB>  Glob over the full path (* crosses /) [aliases: -path/-wholename; -ipath/-iwholename ignore case]
+      --lname <GLOB>       Glob over a symlink's target [alias: -lname; -ilname ignores case]
       --readable, --writable, --executable  Filter by access (real uid/gid) [aliases: -readable/-writable/-executable]
+      --contains <REGEX>   Match regular files whose contents match REGEX, line by line; binary files are skipped
+      --max-filesize <N[c|k|M|G|T]>  Leave files larger than N unread by --contains
+      --mime <GLOB>        Match by MIME type from the first bytes, e.g. image/* or application/x-elf (repeatable)
+      --text, --binary     Match regular files that look like text / binary from their first 8 KiB
+      --encoding <utf8|utf16|latin1>  Match text files in this encoding (ASCII counts as utf8)
       --quit               Stop after the first match (= --max-results 1) [alias: -quit]
       --idle               Run unobtrusively: idle CPU + I/O scheduling, nice +19, 2 threads (Linux)
+      --ignore-readdir-race  Ignore entries deleted mid-walk instead of reporting them
+                           [alias: -ignore_readdir_race; undone by -noignore_readdir_race]
+      --quiet-errors       Do not print runtime errors; they still make the exit status non-zero
+      --stats              Print counts (directories, entries, stat calls, errors by reason, …) and timing on stderr at exit
   -h, --help               Print help
   -V, --version            Print version
+
+Expression operators (find precedence: ! > -a > -o; predicates are ANDed by default):
+  ( EXPR )                 Group [alias: --open-paren ... --close-paren]
+  ! EXPR                   Negate [aliases: -not, --not]
+  EXPR -a EXPR             Both (implied between adjacent predicates) [aliases: -and, --and]
+  EXPR -o EXPR             Either; -o means OR after a find-spelled operand or inside ( ) [aliases: -or, --or]
+      --true, --false      Always / never match [aliases: -true/-false]
 ";
 
 impl Args {
@@ -182,6 +380,126 @@ fn default_threads() -> usize {
     thread::available_parallelism().map(|n| n.get()).unwrap_or(2)
 }
 
+/// find's single-dash spellings: each word, the long option it stands for,
+/// and whether that takes a value. lexopt would split `-name` into the short
+/// flags `-n -a -m -e`, so [`next_arg`] reads these words itself.
+const FIND_SPELLINGS: &[(&str, &str, bool)] = &[
+    ("-exec", "exec", false),
+    ("-execdir", "execdir", false),
+    ("-okdir", "okdir", false),
+    ("-print0", "null", false),
+    ("-mindepth", "min-depth", true),
+    ("-maxdepth", "max-depth", true),
+    ("-size", "size", true),
+    ("-mtime", "mtime", true),
+    ("-ctime", "ctime", true),
+    ("-atime", "atime", true),
+    ("-mmin", "mmin", true),
+    ("-cmin", "cmin", true),
+    ("-amin", "amin", true),
+    ("-perm", "perm", true),
+    ("-uid", "uid", true),
+    ("-gid", "gid", true),
+    ("-user", "user", true),
+    ("-group", "group", true),
+    ("-name", "name", true),
+    ("-iname", "iname", true),
+    ("-regex", "regex", true),
+    ("-iregex", "iregex", true),
+    ("-type", "file-type", true),
+    ("-xdev", "xdev", false),
+    ("-mount", "xdev", false),
+    ("-follow", "follow-symlinks", false),
+    ("-empty", "empty", false),
+    ("-delete", "delete", false),
+    ("-links", "links", true),
+    ("-inum", "inum", true),
+    ("-newer", "newer", true),
+    ("-anewer", "anewer", true),
+    ("-cnewer", "cnewer", true),
+    ("-path", "path", true),
+    ("-wholename", "wholename", true),
+    ("-ipath", "ipath", true),
+    ("-iwholename", "iwholename", true),
+    ("-lname", "lname", true),
+    ("-ilname", "ilname", true),
+    ("-readable", "readable", false),
+    ("-writable", "writable", false),
+    ("-executable", "executable", false),
+    ("-nouser", "nouser", false),
+    ("-nogroup", "nogroup", false),
+    ("-quit", "quit", false),
+    ("-print", "print", false),
+    ("-printf", "printf", true),
+    ("-ls", "ls", false),
+    ("-not", "not", false),
+    ("-and", "and", false),
+    ("-or", "or", false),
+    ("-true", "true", false),
+    ("-false", "false", false),
+    ("-ignore_readdir_race", "ignore-readdir-race", false),
+    ("-noignore_readdir_race", "noignore-readdir-race", false),
+];
+
+/// The next argument, with a find spelling from [`FIND_SPELLINGS`] read as
+/// its long option and `find` set. Only a word in option position counts,
+/// so a value such as `-n -name` or anything after `--` stays literal.
+fn next_arg<'a>(
+    parser: &'a mut lexopt::Parser,
+    opts_done: &mut bool,
+    find: &mut bool,
+) -> Result<Option<lexopt::Arg<'a>>, Error> {
+    *find = false;
+    if let Some(mut raw) = parser.try_raw_args().filter(|_| !*opts_done) {
+        let word = raw.peek().and_then(|w| w.to_str());
+        if word == Some("--") {
+            *opts_done = true;
+        }
+        let spelled = FIND_SPELLINGS.iter().find(|s| Some(s.0) == word);
+        if let Some(&(spelled, long, takes_value)) = spelled {
+            raw.next();
+            if takes_value && raw.peek().is_none() {
+                return Err(anyhow!(
+                    "missing argument for option '{spelled}'"
+                ));
+            }
+            *find = true;
+            return Ok(Some(Long(long)));
+        }
+    }
+    Ok(parser.next()?)
+}
+
+/// Whether a `-o` here is find's OR: right after an operand written the
+/// find way, or after any operand inside parentheses. Anywhere else it
+/// keeps its historical `--one-filesystem` meaning.
+fn or_follows(tokens: &[Token]) -> bool {
+    let open = tokens.iter().fold(0isize, |depth, t| match t {
+        Token::Open => depth + 1,
+        Token::Close => depth - 1,
+        _ => depth,
+    });
+    tokens.last().is_some_and(|t| {
+        t.ends_find_operand() || open > 0 && matches!(t, Token::Pred(..))
+    })
+}
+
+/// Whether an `-exec` here would run for every match of the expression:
+/// not inside parentheses, not under `!` and not after a top-level `-o`,
+/// where find would make it part of one branch only.
+fn exec_fits(tokens: &[Token]) -> bool {
+    let mut open = 0;
+    for t in tokens {
+        match t {
+            Token::Open => open += 1,
+            Token::Close => open -= 1,
+            Token::Or if open == 0 => return false,
+            _ => {}
+        }
+    }
+    open == 0 && !matches!(tokens.last(), Some(Token::Not))
+}
+
 /// Pure parser over any argv-like iterator (first item is the binary name).
 /// Process-exit-free so it can be unit-tested directly.
 fn parse_inner<I>(args: I) -> Result<Outcome, Error>
@@ -189,62 +507,7 @@ where
     I: IntoIterator,
     I::Item: Into<std::ffi::OsString>,
 {
-    // find spells these as single multi-char short tokens that lexopt would
-    // split into `-p -r -i …`; rewrite each to its `--long` form first.
-    let normalized = args.into_iter().map(|a| {
-        let os: std::ffi::OsString = a.into();
-        match os.to_str() {
-            Some("-print0") => std::ffi::OsString::from("--null"),
-            Some("-mindepth") => std::ffi::OsString::from("--min-depth"),
-            Some("-size") => std::ffi::OsString::from("--size"),
-            Some("-mtime") => std::ffi::OsString::from("--mtime"),
-            Some("-ctime") => std::ffi::OsString::from("--ctime"),
-            Some("-atime") => std::ffi::OsString::from("--atime"),
-            Some("-mmin") => std::ffi::OsString::from("--mmin"),
-            Some("-cmin") => std::ffi::OsString::from("--cmin"),
-            Some("-amin") => std::ffi::OsString::from("--amin"),
-            Some("-perm") => std::ffi::OsString::from("--perm"),
-            Some("-uid") => std::ffi::OsString::from("--uid"),
-            Some("-gid") => std::ffi::OsString::from("--gid"),
-            Some("-user") => std::ffi::OsString::from("--user"),
-            Some("-group") => std::ffi::OsString::from("--group"),
-            Some("-name") => std::ffi::OsString::from("--name"),
-            Some("-iname") => std::ffi::OsString::from("--iname"),
-            Some("-regex") => std::ffi::OsString::from("--regex"),
-            Some("-iregex") => std::ffi::OsString::from("--iregex"),
-            Some("-type") => std::ffi::OsString::from("--file-type"),
-            Some("-maxdepth") => std::ffi::OsString::from("--max-depth"),
-            Some("-xdev" | "-mount") => std::ffi::OsString::from("--xdev"),
-            Some("-follow") => std::ffi::OsString::from("--follow-symlinks"),
-            Some("-empty") => std::ffi::OsString::from("--empty"),
-            Some("-links") => std::ffi::OsString::from("--links"),
-            Some("-inum") => std::ffi::OsString::from("--inum"),
-            Some("-newer") => std::ffi::OsString::from("--newer"),
-            Some("-anewer") => std::ffi::OsString::from("--anewer"),
-            Some("-cnewer") => std::ffi::OsString::from("--cnewer"),
-            Some("-path") => std::ffi::OsString::from("--path"),
-            Some("-wholename") => std::ffi::OsString::from("--wholename"),
-            Some("-ipath") => std::ffi::OsString::from("--ipath"),
-            Some("-iwholename") => std::ffi::OsString::from("--iwholename"),
-            Some("-lname") => std::ffi::OsString::from("--lname"),
-            Some("-ilname") => std::ffi::OsString::from("--ilname"),
-            Some("-readable") => std::ffi::OsString::from("--readable"),
-            Some("-writable") => std::ffi::OsString::from("--writable"),
-            Some("-executable") => std::ffi::OsString::from("--executable"),
-            Some("-nouser") => std::ffi::OsString::from("--nouser"),
-            Some("-nogroup") => std::ffi::OsString::from("--nogroup"),
-            Some("-quit") => std::ffi::OsString::from("--quit"),
-            Some("-print") => std::ffi::OsString::from("--print"),
-            Some("-ignore_readdir_race") => {
-                std::ffi::OsString::from("--ignore-readdir-race")
-            }
-            Some("-noignore_readdir_race") => {
-                std::ffi::OsString::from("--noignore-readdir-race")
-            }
-            _ => os,
-        }
-    });
-    let mut parser = lexopt::Parser::from_iter(normalized);
+    let mut parser = lexopt::Parser::from_iter(args);
 
     let mut follow_symlinks = false;
     let mut one_filesystem = true;
@@ -259,25 +522,78 @@ where
     let mut min_depth = None;
     let mut max_scan_rate = None;
     let mut max_results = None;
-    let mut name: Vec<String> = Vec::new();
-    let mut regex: Vec<String> = Vec::new();
     let mut case_insensitive = false;
-    let mut file_type: Vec<FileType> = Vec::new();
+    let mut contains = None;
+    let mut max_filesize = None;
+    let mut mime = Vec::new();
+    let mut text = false;
+    let mut binary = false;
+    let mut encoding = None;
+    let mut any = false;
     let mut exclude: Vec<String> = Vec::new();
     let mut null = false;
-    let mut meta = meta::Predicates::default();
-    let mut path_glob: Vec<String> = Vec::new();
-    let mut lname: Vec<String> = Vec::new();
-    let mut access: u8 = 0;
+    let mut printf = None;
+    let mut json = None;
+    let mut ls = false;
+    let mut tree = false;
+    let mut summarize = false;
+    let mut summarize_depth = None;
+    let mut print = false;
+    let mut exec: Vec<exec::Command> = Vec::new();
+    let mut jobs: Option<usize> = None;
+    let mut group_output = false;
+    let mut delete = false;
+    let mut sort: Option<sort::Key> = None;
+    let mut ordered = false;
+    let mut reverse = false;
+    let mut top = None;
+    let mut by = None;
+    let mut count = None;
+    let mut histogram = None;
+    let mut duplicates = false;
+    let mut hash = None;
+    let mut path_style = pathstyle::Style::default();
+    let mut quote: Option<quote::Style> = None;
+    let mut color = color::When::default();
+    let mut quiet_errors = false;
+    let mut stats = false;
+    let mut ignore_readdir_race = false;
+    // every predicate and operator, in argv order; lowered onto the flat
+    // fields below unless an operator makes it a real expression
+    let mut tokens: Vec<Token> = Vec::new();
     let mut path: Vec<PathBuf> = Vec::new();
-
-    while let Some(arg) = parser.next()? {
+    // see next_arg
+    let (mut opts_done, mut find) = (false, false);
+
+    while let Some(arg) = next_arg(&mut parser, &mut opts_done, &mut find)? {
+        // the predicates this argument adds carry its spelling
+        let added = tokens.len();
+        // commands run once the whole expression matched, so refuse what
+        // find would evaluate after one, depending on its outcome
+        let after_exec = !exec.is_empty();
+        let after_status = exec.iter().any(|c| !c.is_batch());
+        let is_print = matches!(arg, Long("print"));
+        let is_action = is_print
+            || matches!(
+                arg,
+                Long("exec" | "execdir" | "okdir" | "delete" | "printf")
+                    | Long("ls" | "quit")
+            )
+            || find && matches!(arg, Long("null"));
+        if after_status && is_action {
+            return Err(anyhow!(
+                "an action cannot follow '-exec ... ;': find would run it \
+                 only when the command succeeds, but minifind does not use \
+                 its exit status as a test (move the action before '-exec')"
+            ));
+        }
         match arg {
             Short('h') | Long("help") => return Ok(Outcome::Help),
             Short('V') | Long("version") => return Ok(Outcome::Version),
             Short('f') | Short('L') | Long("follow-symlinks") => {
                 follow_symlinks = true;
             }
+            Short('o') if or_follows(&tokens) => tokens.push(Token::Or),
             Short('o') | Long("one-filesystem") | Long("xdev") => {
                 one_filesystem = true;
             }
@@ -304,29 +620,47 @@ where
                 max_results = Some(parser.value()?.parse()?);
             }
             Short('n') | Long("name") => {
-                name.push(parser.value()?.string()?);
+                tokens.push(pattern(Pred::name, &mut parser, false)?);
             }
             Short('r') | Long("regex") => {
-                regex.push(parser.value()?.string()?);
+                tokens.push(pattern(Pred::regex, &mut parser, false)?);
             }
             Short('i') | Long("case-insensitive") => {
                 case_insensitive = true;
             }
-            // -iname/-iregex = matcher + case-insensitivity; since --name and
-            // --regex are exclusive, the global flag affects only the one used
+            Long("any") => {
+                any = true;
+            }
+            Long("contains") => contains = Some(parser.value()?.string()?),
+            Long("mime") => mime.push(parser.value()?.string()?),
+            Long("text") => text = true,
+            Long("binary") => binary = true,
+            Long("encoding") => {
+                let v = parser.value()?.string()?;
+                encoding = Some(content::Encoding::parse(&v)?);
+            }
+            Long("max-filesize") => {
+                let v = parser.value()?.string()?;
+                max_filesize = Some(content::parse_filesize(&v)?);
+            }
+            // -iname/-iregex: case-insensitive for their own pattern only
             Long("iname") => {
-                case_insensitive = true;
-                name.push(val_str(&mut parser)?);
+                tokens.push(pattern(Pred::name, &mut parser, true)?);
             }
             Long("iregex") => {
-                case_insensitive = true;
-                regex.push(val_str(&mut parser)?);
+                tokens.push(pattern(Pred::regex, &mut parser, true)?);
             }
             Short('t') | Long("file-type") => {
-                file_type.push(parse_file_type(&parser.value()?.string()?)?);
+                let types = parser.value()?.string()?;
+                let types = types.split(',').map(parse_file_type);
+                let types = types.collect::<Result<_, _>>()?;
+                tokens.push(Token::Pred(Pred::Type(types), Spelling::Ours));
             }
             // find's `-empty` predicate; equivalent to `--file-type empty`
-            Long("empty") => file_type.push(FileType::Empty),
+            Long("empty") => tokens.push(Token::Pred(
+                Pred::Type(vec![FileType::Empty]),
+                Spelling::Ours,
+            )),
             Short('E') | Long("exclude") => {
                 exclude.push(parser.value()?.string()?);
             }
@@ -334,124 +668,487 @@ where
             // work everywhere; mode/owner are Unix-only (no mode bits on the
             // fallback leaf), so absent off-Unix → "unexpected option".
             Long("size") => {
-                meta.size =
-                    Some(meta::SizePred::parse(&parser.value()?.string()?)?);
-            }
-            Long("mtime") => meta.times.push(meta::TimePred::mtime(
-                &val_str(&mut parser)?,
-                meta::DAY,
-            )?),
-            Long("ctime") => meta.times.push(meta::TimePred::ctime(
-                &val_str(&mut parser)?,
-                meta::DAY,
-            )?),
-            Long("atime") => meta.times.push(meta::TimePred::atime(
-                &val_str(&mut parser)?,
-                meta::DAY,
-            )?),
-            Long("mmin") => meta.times.push(meta::TimePred::mtime(
-                &val_str(&mut parser)?,
-                meta::MIN,
-            )?),
-            Long("cmin") => meta.times.push(meta::TimePred::ctime(
-                &val_str(&mut parser)?,
-                meta::MIN,
-            )?),
-            Long("amin") => meta.times.push(meta::TimePred::atime(
-                &val_str(&mut parser)?,
-                meta::MIN,
-            )?),
+                let p = meta::SizePred::parse(&parser.value()?.string()?)?;
+                tokens.push(meta_token(|m| m.size = Some(p)));
+            }
+            Long("mtime") => {
+                let p =
+                    meta::TimePred::mtime(&val_str(&mut parser)?, meta::DAY)?;
+                tokens.push(meta_token(|m| m.times.push(p)));
+            }
+            Long("ctime") => {
+                let p =
+                    meta::TimePred::ctime(&val_str(&mut parser)?, meta::DAY)?;
+                tokens.push(meta_token(|m| m.times.push(p)));
+            }
+            Long("atime") => {
+                let p =
+                    meta::TimePred::atime(&val_str(&mut parser)?, meta::DAY)?;
+                tokens.push(meta_token(|m| m.times.push(p)));
+            }
+            Long("mmin") => {
+                let p =
+                    meta::TimePred::mtime(&val_str(&mut parser)?, meta::MIN)?;
+                tokens.push(meta_token(|m| m.times.push(p)));
+            }
+            Long("cmin") => {
+                let p =
+                    meta::TimePred::ctime(&val_str(&mut parser)?, meta::MIN)?;
+                tokens.push(meta_token(|m| m.times.push(p)));
+            }
+            Long("amin") => {
+                let p =
+                    meta::TimePred::atime(&val_str(&mut parser)?, meta::MIN)?;
+                tokens.push(meta_token(|m| m.times.push(p)));
+            }
             #[cfg(unix)]
             Long("perm") => {
-                meta.perm =
-                    Some(meta::PermPred::parse(&parser.value()?.string()?)?);
+                let p = meta::PermPred::parse(&parser.value()?.string()?)?;
+                tokens.push(meta_token(|m| m.perm = Some(p)));
             }
             #[cfg(unix)]
             Long("uid") => {
-                meta.uid =
-                    Some(meta::IdPred::parse(&parser.value()?.string()?)?);
+                let p = meta::IdPred::parse(&parser.value()?.string()?)?;
+                tokens.push(meta_token(|m| m.uid = Some(p)));
             }
             #[cfg(unix)]
             Long("gid") => {
-                meta.gid =
-                    Some(meta::IdPred::parse(&parser.value()?.string()?)?);
+                let p = meta::IdPred::parse(&parser.value()?.string()?)?;
+                tokens.push(meta_token(|m| m.gid = Some(p)));
             }
             #[cfg(unix)]
             Long("user") => {
                 let id = meta::resolve_user(&parser.value()?.string()?)?;
-                meta.uid = Some(meta::IdPred::exact(id));
+                tokens.push(meta_token(|m| {
+                    m.uid = Some(meta::IdPred::exact(id))
+                }));
             }
             #[cfg(unix)]
             Long("group") => {
                 let id = meta::resolve_group(&parser.value()?.string()?)?;
-                meta.gid = Some(meta::IdPred::exact(id));
+                tokens.push(meta_token(|m| {
+                    m.gid = Some(meta::IdPred::exact(id))
+                }));
             }
             // -links/-inum read Unix stat fields (nlink/ino); Unix-only.
             #[cfg(unix)]
             Long("links") => {
-                meta.links =
-                    Some(meta::IdPred::parse(&parser.value()?.string()?)?);
+                let p = meta::IdPred::parse(&parser.value()?.string()?)?;
+                tokens.push(meta_token(|m| m.links = Some(p)));
             }
             #[cfg(unix)]
             Long("inum") => {
-                meta.inum =
-                    Some(meta::IdPred::parse(&parser.value()?.string()?)?);
+                let p = meta::IdPred::parse(&parser.value()?.string()?)?;
+                tokens.push(meta_token(|m| m.inum = Some(p)));
             }
             // -newer family: stat the reference file once, here.
-            Long("newer") => meta.newer.push(meta::NewerPred::newer(
-                meta::file_mtime(Path::new(&parser.value()?))?,
-            )),
-            Long("anewer") => meta.newer.push(meta::NewerPred::anewer(
-                meta::file_mtime(Path::new(&parser.value()?))?,
-            )),
-            Long("cnewer") => meta.newer.push(meta::NewerPred::cnewer(
-                meta::file_mtime(Path::new(&parser.value()?))?,
-            )),
+            Long("newer") => {
+                let t = meta::file_mtime(Path::new(&parser.value()?))?;
+                tokens.push(meta_token(|m| {
+                    m.newer.push(meta::NewerPred::newer(t))
+                }));
+            }
+            Long("anewer") => {
+                let t = meta::file_mtime(Path::new(&parser.value()?))?;
+                tokens.push(meta_token(|m| {
+                    m.newer.push(meta::NewerPred::anewer(t))
+                }));
+            }
+            Long("cnewer") => {
+                let t = meta::file_mtime(Path::new(&parser.value()?))?;
+                tokens.push(meta_token(|m| {
+                    m.newer.push(meta::NewerPred::cnewer(t))
+                }));
+            }
             // -nouser/-nogroup need reverse NSS; Unix-only.
             #[cfg(unix)]
-            Long("nouser") => meta.nouser = true,
+            Long("nouser") => tokens.push(meta_token(|m| m.nouser = true)),
             #[cfg(unix)]
-            Long("nogroup") => meta.nogroup = true,
+            Long("nogroup") => tokens.push(meta_token(|m| m.nogroup = true)),
             // full-path globs; -ipath/-iwholename add case-insensitivity
             Long("path") | Long("wholename") => {
-                path_glob.push(val_str(&mut parser)?);
+                tokens.push(pattern(Pred::path, &mut parser, false)?);
             }
             Long("ipath") | Long("iwholename") => {
-                case_insensitive = true;
-                path_glob.push(val_str(&mut parser)?);
+                tokens.push(pattern(Pred::path, &mut parser, true)?);
             }
             // symlink-target globs; -ilname adds case-insensitivity
-            Long("lname") => lname.push(val_str(&mut parser)?),
+            Long("lname") => {
+                tokens.push(pattern(Pred::lname, &mut parser, false)?);
+            }
             Long("ilname") => {
-                case_insensitive = true;
-                lname.push(val_str(&mut parser)?);
+                tokens.push(pattern(Pred::lname, &mut parser, true)?);
             }
             // access checks via faccessat (real uid/gid); Unix-only.
             #[cfg(unix)]
-            Long("readable") => access |= meta::access::READ,
+            Long("readable") => {
+                tokens.push(Token::Pred(
+                    Pred::Access(meta::access::READ),
+                    Spelling::Ours,
+                ));
+            }
             #[cfg(unix)]
-            Long("writable") => access |= meta::access::WRITE,
+            Long("writable") => {
+                tokens.push(Token::Pred(
+                    Pred::Access(meta::access::WRITE),
+                    Spelling::Ours,
+                ));
+            }
             #[cfg(unix)]
-            Long("executable") => access |= meta::access::EXEC,
+            Long("executable") => {
+                tokens.push(Token::Pred(
+                    Pred::Access(meta::access::EXEC),
+                    Spelling::Ours,
+                ));
+            }
             // -quit: stop after the first match (= --max-results 1).
             Long("quit") => max_results = Some(1),
-            // no-ops: minifind always prints and already skips readdir races.
-            Long("print")
-            | Long("ignore-readdir-race")
-            | Long("noignore-readdir-race") => {}
+            // -print keeps printing alongside -exec; as an operand it is
+            // always true
+            Long("print") => {
+                print = true;
+                tokens.push(Token::Pred(Pred::True, Spelling::Ours));
+            }
+            Long("true") => {
+                tokens.push(Token::Pred(Pred::True, Spelling::Ours));
+            }
+            // the command words run up to `;` or `{} +`, read raw so options
+            // inside the command are not parsed as ours
+            Long(flag @ ("exec" | "execdir" | "okdir")) => {
+                let mode = match flag {
+                    "exec" => exec::Mode::Exec,
+                    "execdir" => exec::Mode::ExecDir,
+                    _ => exec::Mode::OkDir,
+                };
+                if !exec_fits(&tokens) {
+                    return Err(anyhow!(
+                        "'-{flag}' cannot be used inside ( ), after '!' or \
+                         after '-o': minifind runs it on every match of the \
+                         whole expression"
+                    ));
+                }
+                exec.push(exec::Command::parse(mode, parser.raw_args()?)?);
+            }
+            Short('j') | Long("jobs") => {
+                jobs = Some(parse_jobs(&parser.value()?.string()?)?);
+            }
+            Long("group-output") => {
+                group_output = true;
+            }
+            Long("delete") => {
+                delete = true;
+            }
+            Long("sort") => {
+                sort = Some(sort::Key::parse(&parser.value()?.string()?)?);
+            }
+            Long("ordered") => {
+                ordered = true;
+            }
+            Long("reverse") => {
+                reverse = true;
+            }
+            Long("top") => top = Some(parser.value()?.parse()?),
+            // the breakdown is optional and must be attached: --count=type
+            Long("duplicates") => duplicates = true,
+            Long("hash") => {
+                hash = Some(hash::Algo::parse(&parser.value()?.string()?)?);
+            }
+            Long("histogram") => {
+                histogram =
+                    Some(histogram::By::parse(&parser.value()?.string()?)?);
+            }
+            Short('c') | Long("count") => {
+                count = Some(match parser.optional_value() {
+                    Some(v) => count::By::parse(&v.string()?)?,
+                    None => count::By::Root,
+                });
+            }
+            Long("by") => {
+                by = Some(top::By::parse(&parser.value()?.string()?)?);
+            }
+            Long("relative-to") => {
+                path_style = pathstyle::Style::parse_relative(
+                    &parser.value()?.string()?,
+                )?;
+            }
+            Long("color" | "colour") => {
+                color = color::When::parse(&parser.value()?.string()?)?;
+            }
+            Long("quote") => {
+                quote = Some(quote::Style::parse(&parser.value()?.string()?)?);
+            }
+            Long("absolute-path") => {
+                path_style = pathstyle::Style::Absolute;
+            }
+            Long("canonical") => {
+                path_style = pathstyle::Style::Canonical;
+            }
+            Long("quiet-errors") => {
+                quiet_errors = true;
+            }
+            Long("stats") => stats = true,
+            Long("false") => {
+                tokens.push(Token::Pred(Pred::False, Spelling::Ours));
+            }
+            // expression operators; `(`, `)` and `!` arrive as values below
+            Long("not") => tokens.push(Token::Not),
+            Short('a') | Long("and") => tokens.push(Token::And),
+            Long("or") => tokens.push(Token::Or),
+            Long("open-paren") => tokens.push(Token::Open),
+            Long("close-paren") => tokens.push(Token::Close),
+            Long("ignore-readdir-race") => ignore_readdir_race = true,
+            Long("noignore-readdir-race") => ignore_readdir_race = false,
             // `-print0` is rewritten to `--null` above; `--print0` (fd-style)
             // and `-0` (xargs/grep-style) are accepted directly.
             Short('0') | Long("null") | Long("print0") => {
                 null = true;
             }
-            Value(val) => path.push(parse_paths(&val.string()?)?),
+            // parsed now so a bad directive errors before the walk
+            Long("printf") => {
+                printf = Some(printf::Format::parse(&val_str(&mut parser)?)?);
+            }
+            Long("ls") => {
+                ls = true;
+            }
+            Long("tree") => {
+                tree = true;
+            }
+            Long("summarize") => summarize = true,
+            Long("summarize-depth") => {
+                summarize = true;
+                summarize_depth = Some(parser.value()?.parse()?);
+            }
+            // the field list is optional and must be attached: --json=a,b
+            Long("json") => {
+                let list = match parser.optional_value() {
+                    Some(v) => v.string()?,
+                    None => json::DEFAULT_FIELDS.to_string(),
+                };
+                json = Some(json::Format::parse(&list)?);
+            }
+            Value(val) => match val.to_str() {
+                Some("!") => tokens.push(Token::Not),
+                Some("(") => tokens.push(Token::Open),
+                Some(")") => tokens.push(Token::Close),
+                _ => path.push(parse_paths(&val.string()?)?),
+            },
             _ => return Err(arg.unexpected().into()),
         }
+        if after_exec && tokens.len() > added && !is_print {
+            return Err(anyhow!(
+                "a test or operator cannot follow '-exec': minifind runs \
+                 commands only on entries the whole expression matched \
+                 (move it before '-exec')"
+            ));
+        }
+        if find {
+            for t in &mut tokens[added..] {
+                if let Token::Pred(_, spelling) = t {
+                    *spelling = Spelling::Find;
+                }
+            }
+        }
+    }
+
+    // lower the AND chain onto the flat fields the hot path filters on
+    // directly; an operator, or a leaf they cannot AND, makes it a real
+    // expression instead
+    let mut flat = Flat::default();
+    let lowered = !tokens.iter().any(Token::needs_tree)
+        && tokens.iter().all(|t| match t {
+            Token::Pred(p, _) => flat.lower(p.clone()),
+            _ => true,
+        });
+    let expr = if lowered {
+        None
+    } else {
+        flat = Flat::default();
+        Some(expr::parse(tokens)?)
+    };
+    let Flat {
+        name,
+        regex,
+        iname,
+        iregex,
+        mut file_type,
+        meta,
+        path_glob,
+        ipath_glob,
+        lname,
+        ilname,
+        access,
+        ..
+    } = flat;
+
+    if printf.is_some() && json.is_some() {
+        return Err(anyhow!(
+            "the argument '--json' cannot be used with '--printf'"
+        ));
+    }
+    if ls && (printf.is_some() || json.is_some()) {
+        let other = if json.is_some() { "--json" } else { "--printf" };
+        return Err(anyhow!(
+            "the argument '--ls' cannot be used with '{other}'"
+        ));
+    }
+    if hash.is_some() {
+        let other = [
+            (printf.is_some(), "--printf"),
+            (json.is_some(), "--json"),
+            (ls, "--ls"),
+            // the lines keep sha256sum's own escaping, so -c reads them back
+            (quote.is_some(), "--quote"),
+        ]
+        .into_iter()
+        .find_map(|(set, flag)| set.then_some(flag));
+        if let Some(flag) = other {
+            return Err(anyhow!(
+                "the argument '--hash' cannot be used with '{flag}'"
+            ));
+        }
+    }
+
+    // a tree is one drawing with its own layout and order
+    if tree {
+        let other = [
+            (printf.is_some(), "--printf"),
+            (json.is_some(), "--json"),
+            (ls, "--ls"),
+            (hash.is_some(), "--hash"),
+            (null, "--null"),
+            (sort.is_some(), "--sort"),
+            (ordered, "--ordered"),
+        ]
+        .into_iter()
+        .find_map(|(set, flag)| set.then_some(flag));
+        if let Some(flag) = other {
+            return Err(anyhow!(
+                "the argument '--tree' cannot be used with '{flag}'"
+            ));
+        }
+    }
+
+    // totals replace the per-match output, and need the walk to run through
+    if summarize {
+        let other = [
+            (printf.is_some(), "--printf"),
+            (json.is_some(), "--json"),
+            (ls, "--ls"),
+            (hash.is_some(), "--hash"),
+            (tree, "--tree"),
+            (!exec.is_empty(), "--exec"),
+            (delete, "--delete"),
+        ]
+        .into_iter()
+        .find_map(|(set, flag)| set.then_some(flag));
+        if let Some(flag) = other {
+            return Err(anyhow!(
+                "the argument '--summarize' cannot be used with '{flag}'"
+            ));
+        }
+    }
+
+    if sort.is_some() && ordered {
+        return Err(anyhow!(
+            "the argument '--ordered' cannot be used with '--sort'"
+        ));
+    }
+    // counting prints no matches, so nothing that shapes them applies
+    let reports = [
+        (count.is_some(), "--count"),
+        (histogram.is_some(), "--histogram"),
+        (duplicates, "--duplicates"),
+    ];
+    let mut set = reports.iter().filter(|r| r.0).map(|r| r.1);
+    let report = set.next();
+    if let (Some(report), Some(flag)) = (report, set.next()) {
+        return Err(anyhow!(
+            "the argument '{flag}' cannot be used with '{report}'"
+        ));
+    }
+    if let Some(report) = report {
+        let other = [
+            (printf.is_some(), "--printf"),
+            (json.is_some(), "--json"),
+            (ls, "--ls"),
+            (hash.is_some(), "--hash"),
+            (tree, "--tree"),
+            (summarize, "--summarize"),
+            (top.is_some(), "--top"),
+            (sort.is_some(), "--sort"),
+            (ordered, "--ordered"),
+            (!exec.is_empty(), "--exec"),
+            (delete, "--delete"),
+            (max_results.is_some_and(|n| n != 0), "--max-results"),
+        ]
+        .into_iter()
+        .find_map(|(set, flag)| set.then_some(flag));
+        if let Some(flag) = other {
+            return Err(anyhow!(
+                "the argument '{report}' cannot be used with '{flag}'"
+            ));
+        }
     }
 
-    if !name.is_empty() && !regex.is_empty() {
+    // the N winners come out in their own order
+    if top.is_some() {
+        let other = [
+            (sort.is_some(), "--sort"),
+            (ordered, "--ordered"),
+            (tree, "--tree"),
+            (summarize, "--summarize"),
+            (delete, "--delete"),
+        ]
+        .into_iter()
+        .find_map(|(set, flag)| set.then_some(flag));
+        if let Some(flag) = other {
+            return Err(anyhow!(
+                "the argument '--top' cannot be used with '{flag}'"
+            ));
+        }
+    } else if by.is_some() {
+        return Err(anyhow!("the argument '--by' requires '--top'"));
+    }
+    if reverse && sort.is_none() && !ordered && top.is_none() {
         return Err(anyhow!(
-            "the argument '--name' cannot be used with '--regex'"
+            "the argument '--reverse' requires '--sort', '--ordered' or \
+             '--top'"
+        ));
+    }
+
+    // a root-relative {} means nothing from the directory -exec runs in
+    if path_style == pathstyle::Style::Root && exec.iter().any(|c| !c.in_dir())
+    {
+        return Err(anyhow!(
+            "the argument '--relative-to ROOT' cannot be used with '--exec' \
+             (use '--execdir')"
+        ));
+    }
+
+    if max_filesize.is_some() && contains.is_none() {
+        return Err(anyhow!(
+            "the argument '--max-filesize' requires '--contains'"
+        ));
+    }
+
+    // --encoding narrows --text; neither goes with --binary
+    if binary && (text || encoding.is_some()) {
+        let other = if text { "--text" } else { "--encoding" };
+        return Err(anyhow!(
+            "the argument '--binary' cannot be used with '{other}'"
+        ));
+    }
+    let class = match (text, binary, encoding) {
+        (_, _, Some(e)) => Some(content::Class::Encoding(e)),
+        (true, _, None) => Some(content::Class::Text),
+        (_, true, None) => Some(content::Class::Binary),
+        _ => None,
+    };
+
+    // a followed link would delete outside the tree being cleaned
+    if delete && follow_symlinks {
+        return Err(anyhow!(
+            "the argument '--delete' cannot be used with '--follow-symlinks'"
         ));
     }
 
@@ -461,11 +1158,31 @@ where
         ));
     }
 
+    // an expression testing types decides them itself, so admit every type
     if file_type.is_empty() {
-        file_type =
-            vec![FileType::Directory, FileType::File, FileType::Symlink];
+        file_type = if expr.as_ref().is_some_and(expr::Expr::has_type) {
+            vec![
+                FileType::BlockDevice,
+                FileType::CharDevice,
+                FileType::Directory,
+                FileType::Pipe,
+                FileType::File,
+                FileType::Symlink,
+                FileType::Socket,
+            ]
+        } else {
+            vec![FileType::Directory, FileType::File, FileType::Symlink]
+        };
     }
 
+    // an -exec replaces the implicit print unless -print asked for it too
+    let print = print
+        || exec.is_empty() && !delete
+        || printf.is_some()
+        || json.is_some()
+        || ls;
+    let jobs = jobs.unwrap_or_else(default_threads);
+
     // --idle defaults to 2 threads; an explicit --threads always wins.
     // unwrap_or_else so default_threads() (an OS query) is skipped when set.
     let threads =
@@ -482,18 +1199,157 @@ where
         max_results,
         name: (!name.is_empty()).then_some(name),
         regex: (!regex.is_empty()).then_some(regex),
+        iname: (!iname.is_empty()).then_some(iname),
+        iregex: (!iregex.is_empty()).then_some(iregex),
         case_insensitive,
+        any,
         file_type,
         meta,
         path_glob: (!path_glob.is_empty()).then_some(path_glob),
+        ipath_glob: (!ipath_glob.is_empty()).then_some(ipath_glob),
         lname: (!lname.is_empty()).then_some(lname),
+        ilname: (!ilname.is_empty()).then_some(ilname),
         access,
+        contains,
+        max_filesize,
+        mime: (!mime.is_empty()).then_some(mime),
+        class,
         exclude: (!exclude.is_empty()).then_some(exclude),
         null,
+        printf,
+        json,
+        ls,
+        tree,
+        summarize,
+        summarize_depth,
+        print,
+        exec,
+        jobs,
+        group_output,
+        ignore_readdir_race,
+        quiet_errors,
+        stats,
+        delete,
+        sort,
+        ordered,
+        reverse,
+        top,
+        by: by.unwrap_or_default(),
+        count,
+        histogram,
+        duplicates,
+        hash,
+        path_style,
+        quote,
+        color,
+        expr,
         path,
     })))
 }
 
+/// The flat-field view of an operator-free command line, whose adjacent
+/// predicates all AND. Each field holds one test, so a repeated
+/// `-n`/`-r`/`-t`, `--path`/`--lname` or scalar metadata test cannot be
+/// ANDed here and sends the command line to the expression tree. Each
+/// pattern kind keeps its `-i*` spelling apart, so case-insensitivity stays
+/// per pattern.
+#[derive(Default)]
+struct Flat {
+    name: Vec<String>,
+    regex: Vec<String>,
+    iname: Vec<String>,
+    iregex: Vec<String>,
+    file_type: Vec<FileType>,
+    meta: meta::Predicates,
+    path_glob: Vec<String>,
+    ipath_glob: Vec<String>,
+    lname: Vec<String>,
+    ilname: Vec<String>,
+    access: u8,
+}
+
+impl Flat {
+    /// Lowers one leaf; false when it cannot AND with the fields lowered so
+    /// far.
+    fn lower(&mut self, p: Pred) -> bool {
+        let seen = match p {
+            Pred::Name { .. } => self.name.len() + self.iname.len(),
+            Pred::Regex { .. } => self.regex.len() + self.iregex.len(),
+            Pred::Path { .. } | Pred::Lname { .. } => {
+                self.path_glob.len()
+                    + self.ipath_glob.len()
+                    + self.lname.len()
+                    + self.ilname.len()
+            }
+            Pred::Type(_) => self.file_type.len(),
+            _ => 0,
+        };
+        if seen > 0 {
+            return false;
+        }
+        let (set, pattern) = match p {
+            Pred::Name { pattern, case_insensitive: false } => {
+                (&mut self.name, pattern)
+            }
+            Pred::Name { pattern, case_insensitive: true } => {
+                (&mut self.iname, pattern)
+            }
+            Pred::Regex { pattern, case_insensitive: false } => {
+                (&mut self.regex, pattern)
+            }
+            Pred::Regex { pattern, case_insensitive: true } => {
+                (&mut self.iregex, pattern)
+            }
+            Pred::Path { pattern, case_insensitive: false } => {
+                (&mut self.path_glob, pattern)
+            }
+            Pred::Path { pattern, case_insensitive: true } => {
+                (&mut self.ipath_glob, pattern)
+            }
+            Pred::Lname { pattern, case_insensitive: false } => {
+                (&mut self.lname, pattern)
+            }
+            Pred::Lname { pattern, case_insensitive: true } => {
+                (&mut self.ilname, pattern)
+            }
+            Pred::Type(t) => {
+                self.file_type = t;
+                return true;
+            }
+            Pred::Meta(m) if self.meta.overlaps(&m) => return false,
+            Pred::Meta(m) => {
+                self.meta.merge(*m);
+                return true;
+            }
+            Pred::Access(a) => {
+                self.access |= a;
+                return true;
+            }
+            // -false always builds a tree (see Token::needs_tree)
+            Pred::True => return true,
+            Pred::False => return false,
+        };
+        set.push(pattern);
+        true
+    }
+}
+
+/// A `-name`-style pattern predicate built from the next option value.
+fn pattern(
+    ctor: fn(String, bool) -> Pred,
+    parser: &mut lexopt::Parser,
+    case_insensitive: bool,
+) -> Result<Token, Error> {
+    Ok(Token::Pred(ctor(val_str(parser)?, case_insensitive), Spelling::Ours))
+}
+
+/// A one-predicate metadata token, filled in by `set`.
+fn meta_token(set: impl FnOnce(&mut meta::Predicates)) -> Token {
+    let mut m = meta::Predicates::default();
+    set(&mut m);
+    Token::Pred(Pred::Meta(Box::new(m)), Spelling::Ours)
+}
+
 /// Next option value as a `String`; keeps the metadata parse arms terse.
 fn val_str(parser: &mut lexopt::Parser) -> Result<String, Error> {
     Ok(parser.value()?.string()?)
@@ -550,6 +1406,17 @@ fn parse_threads(x: &str) -> Result<usize, Error> {
     }
 }
 
+/// Validates `-j/--jobs`; unlike walker threads a single executor is fine.
+fn parse_jobs(x: &str) -> Result<usize, Error> {
+    let v = x.parse::<usize>()?;
+
+    if (1..=65535).contains(&v) {
+        Ok(v)
+    } else {
+        Err(anyhow!("jobs should be in [1..=65535] range"))
+    }
+}
+
 /// Parses a path argument, requiring an existing directory and normalizing it.
 fn parse_paths(x: &str) -> Result<PathBuf, Error> {
     let p = Path::new(x);
@@ -692,7 +1559,8 @@ mod tests {
         assert!(run(&["-path", "*/x", &dir]).path_glob.is_some());
         assert!(run(&["-wholename", "*/x", &dir]).path_glob.is_some());
         let a = run(&["-ipath", "*/X", &dir]);
-        assert!(a.path_glob.is_some() && a.case_insensitive);
+        assert!(a.ipath_glob.is_some() && a.path_glob.is_none());
+        assert!(!a.case_insensitive);
     }
 
     #[test]
@@ -700,7 +1568,8 @@ mod tests {
         let dir = tmp_dir();
         assert!(run(&["-lname", "*.so", &dir]).lname.is_some());
         let a = run(&["-ilname", "*.SO", &dir]);
-        assert!(a.lname.is_some() && a.case_insensitive);
+        assert!(a.ilname.is_some() && a.lname.is_none());
+        assert!(!a.case_insensitive);
     }
 
     #[test]
@@ -749,6 +1618,139 @@ mod tests {
         assert_eq!(a.access, meta::access::WRITE | meta::access::EXEC);
     }
 
+    #[test]
+    fn test_parse_inner_plain_and_chain_builds_no_expression() {
+        let dir = tmp_dir();
+        let a = run(&["-name", "*.rs", "-size", "+1k", &dir]);
+        assert!(a.expr.is_none());
+        assert_eq!(a.name, Some(vec!["*.rs".to_string()]));
+        assert!(a.meta.is_active());
+    }
+
+    #[test]
+    fn test_parse_inner_operators_build_expression() {
+        let dir = tmp_dir();
+        let a = run(&[
+            &dir,
+            "(",
+            "-name",
+            "*.rs",
+            "-o",
+            "-name",
+            "*.toml",
+            ")",
+            "!",
+            "-path",
+            "*/target/*",
+        ]);
+        assert!(a.expr.is_some());
+        // predicates inside the tree are not also lowered onto flat fields
+        assert_eq!(a.name, None);
+        assert_eq!(a.path_glob, None);
+        assert!(parse_argv(&[
+            "-name", "a", "-or", "-not", "-name", "b", &dir
+        ])
+        .is_ok());
+        assert!(
+            parse_argv(&["-name", "a", "-and", "-name", "b", &dir]).is_ok()
+        );
+    }
+
+    #[test]
+    fn test_parse_inner_dash_o_is_or_only_after_an_operand() {
+        let dir = tmp_dir();
+        // leading -o keeps its --one-filesystem meaning
+        let a = run(&["--cross-filesystem", "-o", &dir]);
+        assert!(a.one_filesystem && a.expr.is_none());
+        // so does -o after minifind's own spelling
+        let a = run(&["--cross-filesystem", "-n", "*.rs", "-o", &dir]);
+        assert!(a.one_filesystem && a.expr.is_none());
+        // after a find-spelled operand, or inside parentheses, it is OR
+        let a = run(&["-name", "a", "-o", "-name", "b", &dir]);
+        assert!(matches!(a.expr, Some(expr::Expr::Or(_))));
+        let a = run(&["(", "-n", "a", "-o", "-n", "b", ")", &dir]);
+        assert!(matches!(a.expr, Some(expr::Expr::Or(_))));
+    }
+
+    #[test]
+    fn test_parse_inner_find_spelling_only_in_option_position() {
+        let dir = tmp_dir();
+        // a value that looks like a find spelling stays literal
+        let a = run(&["-E", "-name", "-n", "-size", &dir]);
+        assert_eq!(a.exclude, Some(vec!["-name".to_string()]));
+        assert_eq!(a.name, Some(vec!["-size".to_string()]));
+        let e = parse_argv(&[&dir, "-name"]).unwrap_err();
+        assert!(e.to_string().contains("'-name'"));
+    }
+
+    #[test]
+    fn test_parse_inner_malformed_expression_errors() {
+        let dir = tmp_dir();
+        assert!(parse_argv(&[&dir, "(", "-name", "a"]).is_err());
+        assert!(parse_argv(&[&dir, "-name", "a", ")"]).is_err());
+        assert!(parse_argv(&[&dir, "-name", "a", "-or"]).is_err());
+        assert!(parse_argv(&[&dir, "!"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_inner_adjacent_predicates_and_like_dash_a() {
+        let dir = tmp_dir();
+        let tree = |argv: &[&str]| format!("{:?}", run(argv).expr);
+        for (adjacent, explicit) in [
+            (
+                &[&dir, "-name", "*.rs", "-name", "a*"][..],
+                &[&dir, "-name", "*.rs", "-a", "-name", "a*"][..],
+            ),
+            (
+                &[&dir, "-type", "f", "-empty"],
+                &[&dir, "-type", "f", "-a", "-empty"],
+            ),
+            (
+                &[&dir, "-n", "*.rs", "-name", "a*"],
+                &[&dir, "-n", "*.rs", "-a", "-name", "a*"],
+            ),
+            (
+                &[&dir, "-size", "+1k", "-size", "-1M"],
+                &[&dir, "-size", "+1k", "-a", "-size", "-1M"],
+            ),
+            (
+                &[&dir, "--path", "*/a/*", "--path", "*.rs"],
+                &[&dir, "--path", "*/a/*", "-a", "--path", "*.rs"],
+            ),
+            (
+                &[&dir, "-n", "*.rs", "--name", "a*"],
+                &[&dir, "-n", "*.rs", "-a", "--name", "a*"],
+            ),
+            (
+                &[&dir, "-t", "f", "-t", "d"],
+                &[&dir, "-t", "f", "-a", "-t", "d"],
+            ),
+        ] {
+            let got = run(adjacent);
+            assert!(got.expr.is_some() && got.name.is_none());
+            assert_eq!(tree(adjacent), tree(explicit));
+        }
+        // a type list is one test, lowered flat
+        let a = run(&["-n", "*.rs", "-t", "f,d", &dir]);
+        assert!(a.expr.is_none());
+        assert_eq!(a.name, Some(vec!["*.rs".to_string()]));
+        assert_eq!(a.file_type, vec![FileType::File, FileType::Directory]);
+        // different scalars still lower flat, ANDed
+        let a = run(&["-size", "+1k", "-mtime", "-1", "-mmin", "-5", &dir]);
+        assert!(a.expr.is_none());
+    }
+
+    #[test]
+    fn test_parse_inner_expression_type_widens_default_types() {
+        let dir = tmp_dir();
+        let a = run(&[&dir, "-type", "f", "-o", "-type", "p"]);
+        assert!(a.file_type.contains(&FileType::Pipe));
+        assert!(a.file_type.contains(&FileType::Socket));
+        // without a type leaf the usual d/f/l default stays
+        let a = run(&[&dir, "-name", "a", "-o", "-name", "b"]);
+        assert_eq!(a.file_type.len(), 3);
+    }
+
     #[test]
     fn test_parse_inner_find_empty_alias() {
         let dir = tmp_dir();
@@ -756,19 +1758,21 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_inner_iname_sets_name_and_case_insensitive() {
+    fn test_parse_inner_iname_is_case_insensitive_per_pattern() {
         let dir = tmp_dir();
         let a = run(&["-iname", "*.RS", &dir]);
-        assert_eq!(a.name, Some(vec!["*.RS".to_string()]));
-        assert!(a.case_insensitive);
+        assert_eq!(a.iname, Some(vec!["*.RS".to_string()]));
+        assert_eq!(a.name, None);
+        assert!(!a.case_insensitive);
     }
 
     #[test]
-    fn test_parse_inner_iregex_sets_regex_and_case_insensitive() {
+    fn test_parse_inner_iregex_is_case_insensitive_per_pattern() {
         let dir = tmp_dir();
         let a = run(&["-iregex", ".*", &dir]);
-        assert_eq!(a.regex, Some(vec![".*".to_string()]));
-        assert!(a.case_insensitive);
+        assert_eq!(a.iregex, Some(vec![".*".to_string()]));
+        assert_eq!(a.regex, None);
+        assert!(!a.case_insensitive);
     }
 
     #[test]
@@ -925,24 +1929,328 @@ mod tests {
         assert!(parse_argv(&["-x", "1", &dir]).is_err());
     }
 
+    #[test]
+    fn test_parse_inner_printf_alias_and_bad_directive() {
+        let dir = tmp_dir();
+        assert!(run(&["-printf", "%p\\n", &dir]).printf.is_some());
+        assert!(run(&[&dir]).printf.is_none());
+        assert!(parse_argv(&["--printf", "%z", &dir]).is_err());
+    }
+
+    #[test]
+    fn test_parse_inner_json_optional_field_list() {
+        let dir = tmp_dir();
+        let a = run(&["--json", &dir]);
+        assert_eq!(a.json.unwrap().mask(), 0);
+        let a = run(&["--json=path,size", &dir]);
+        assert_eq!(a.json.unwrap().mask(), meta::mask::SIZE);
+        assert!(parse_argv(&["--json=bogus", &dir]).is_err());
+        assert!(parse_argv(&["--json", "--printf", "%p", &dir]).is_err());
+    }
+
+    #[test]
+    fn test_parse_inner_ls_prints_and_excludes_formats() {
+        let dir = tmp_dir();
+        let a = run(&["-ls", &dir]);
+        assert!(a.ls && a.print);
+        assert!(run(&["-ls", "-exec", "true", ";", &dir]).print);
+        assert!(parse_argv(&["--ls", "--printf", "%p", &dir]).is_err());
+        assert!(parse_argv(&["--ls", "--json", &dir]).is_err());
+    }
+
+    #[test]
+    fn test_parse_inner_summarize() {
+        let dir = tmp_dir();
+        assert!(!run(&[&dir]).summarize);
+        assert!(run(&["--summarize", &dir]).summarize);
+        let a = run(&["--summarize-depth", "1", &dir]);
+        assert!(a.summarize);
+        assert_eq!(a.summarize_depth, Some(1));
+        assert!(parse_argv(&["--summarize", "--ls", &dir]).is_err());
+        assert!(parse_argv(&["--summarize", "--delete", &dir]).is_err());
+    }
+
+    #[test]
+    fn test_parse_inner_top_by() {
+        let dir = tmp_dir();
+        let a = run(&["--top", "5", &dir]);
+        assert_eq!((a.top, a.by), (Some(5), top::By::Size));
+        let a = run(&["--top", "3", "--by", "mtime", "--reverse", &dir]);
+        assert_eq!((a.top, a.by, a.reverse), (Some(3), top::By::Mtime, true));
+        assert!(parse_argv(&["--by", "size", &dir]).is_err());
+        assert!(parse_argv(&["--top", "2", "--sort", "name", &dir]).is_err());
+    }
+
+    #[test]
+    fn test_parse_inner_count() {
+        let dir = tmp_dir();
+        assert_eq!(run(&[&dir]).count, None);
+        assert_eq!(run(&["-c", &dir]).count, Some(count::By::Root));
+        assert_eq!(run(&["--count=type", &dir]).count, Some(count::By::Type));
+        assert!(parse_argv(&["--count=size", &dir]).is_err());
+        assert!(parse_argv(&["--count", "--ls", &dir]).is_err());
+    }
+
+    #[test]
+    fn test_parse_inner_contains() {
+        let dir = tmp_dir();
+        let a = run(&["--contains", "TODO", "--max-filesize", "1M", &dir]);
+        assert_eq!(a.contains.as_deref(), Some("TODO"));
+        assert_eq!(a.max_filesize, Some(1 << 20));
+        assert!(parse_argv(&["--max-filesize", "1M", &dir]).is_err());
+        let bad = ["--contains", "x", "--max-filesize", "big", &dir];
+        assert!(parse_argv(&bad).is_err());
+    }
+
+    #[test]
+    fn test_parse_inner_mime_repeats() {
+        let dir = tmp_dir();
+        assert_eq!(run(&[&dir]).mime, None);
+        let a = run(&["--mime", "image/*", "--mime", "application/pdf", &dir]);
+        let want = vec!["image/*".to_string(), "application/pdf".to_string()];
+        assert_eq!(a.mime, Some(want));
+    }
+
+    #[test]
+    fn test_parse_inner_text_binary_encoding() {
+        let dir = tmp_dir();
+        assert_eq!(run(&[&dir]).class, None);
+        assert_eq!(run(&["--text", &dir]).class, Some(content::Class::Text));
+        assert_eq!(
+            run(&["--binary", &dir]).class,
+            Some(content::Class::Binary)
+        );
+        let utf16 = Some(content::Class::Encoding(content::Encoding::Utf16));
+        assert_eq!(run(&["--encoding", "utf16", &dir]).class, utf16);
+        assert_eq!(run(&["--text", "--encoding", "utf16", &dir]).class, utf16);
+        assert!(parse_argv(&["--text", "--binary", &dir]).is_err());
+        assert!(parse_argv(&["--binary", "--encoding", "utf8", &dir]).is_err());
+        assert!(parse_argv(&["--encoding", "ebcdic", &dir]).is_err());
+    }
+
+    #[test]
+    fn test_parse_inner_hash() {
+        let dir = tmp_dir();
+        assert_eq!(run(&[&dir]).hash, None);
+        assert_eq!(
+            run(&["--hash", "xxh3", &dir]).hash,
+            Some(hash::Algo::Xxh3)
+        );
+        assert!(parse_argv(&["--hash", "md5", &dir]).is_err());
+        assert!(parse_argv(&["--hash", "sha256", "--ls", &dir]).is_err());
+        assert!(
+            parse_argv(&["--hash", "sha256", "--quote", "c", &dir]).is_err()
+        );
+        assert!(parse_argv(&["--hash", "sha256", "-c", &dir]).is_err());
+    }
+
+    #[test]
+    fn test_parse_inner_duplicates() {
+        let dir = tmp_dir();
+        assert!(!run(&[&dir]).duplicates);
+        assert!(run(&["--duplicates", "-t", "f", &dir]).duplicates);
+        assert!(parse_argv(&["--duplicates", "-c", &dir]).is_err());
+        assert!(parse_argv(&["--duplicates", "--json", &dir]).is_err());
+        let root = ["--duplicates", "--relative-to", "root", &dir];
+        assert!(run(&root).duplicates);
+    }
+
+    #[test]
+    fn test_parse_inner_histogram() {
+        let dir = tmp_dir();
+        assert_eq!(run(&[&dir]).histogram, None);
+        let a = run(&["--histogram", "ext", &dir]);
+        assert_eq!(a.histogram, Some(histogram::By::Ext));
+        assert!(parse_argv(&["--histogram", "name", &dir]).is_err());
+        assert!(parse_argv(&["--histogram", "age", "-c", &dir]).is_err());
+        assert!(
+            parse_argv(&["--histogram", "size", "--top", "3", &dir]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_inner_tree_excludes_other_layouts() {
+        let dir = tmp_dir();
+        assert!(run(&["--tree", &dir]).tree);
+        assert!(parse_argv(&["--tree", "--ls", &dir]).is_err());
+        assert!(parse_argv(&["--tree", "-0", &dir]).is_err());
+        assert!(parse_argv(&["--tree", "--sort", "name", &dir]).is_err());
+    }
+
+    #[test]
+    fn test_parse_inner_exec_reads_raw_words() {
+        let dir = tmp_dir();
+        let a =
+            run(&["-name", "x", "-exec", "grep", "-name", "{}", ";", &dir]);
+        assert_eq!(a.exec.len(), 1);
+        assert!(!a.exec[0].is_batch());
+        assert_eq!(a.name, Some(vec!["x".to_string()]));
+        assert!(!a.print);
+        let a = run(&[&dir, "-exec", "echo", "{}", "+", "-print"]);
+        assert!(a.exec[0].is_batch() && a.print);
+        assert!(parse_argv(&[&dir, "-exec", "echo", "{}"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_inner_exec_rejects_what_its_status_would_decide() {
+        let dir = tmp_dir();
+        let bad: &[&[&str]] = &[
+            &["-exec", "test", "-s", "{}", ";", "-print"],
+            &["-exec", "true", ";", "-delete"],
+            &["-exec", "true", ";", "-exec", "echo", "{}", ";"],
+            &["-exec", "true", ";", "-print0"],
+            &["-exec", "echo", "{}", "+", "-name", "x"],
+            &["-exec", "echo", "{}", "+", "-o", "-true"],
+            &["-name", "a", "-o", "-exec", "rm", "{}", ";"],
+            &["(", "-name", "a", "-exec", "rm", "{}", ";", ")"],
+            &["!", "-exec", "true", ";"],
+        ];
+        for argv in bad {
+            let mut argv = argv.to_vec();
+            argv.push(&dir);
+            assert!(parse_argv(&argv).is_err(), "{argv:?}");
+        }
+        // a parenthesized -o ANDs with the -exec after it
+        let a = run(&[
+            "(", "-name", "a", "-o", "-name", "b", ")", "-exec", "true", ";",
+            "-j", "2", &dir,
+        ]);
+        assert!(a.expr.is_some() && !a.print);
+    }
+
+    #[test]
+    fn test_parse_inner_readdir_race_last_wins() {
+        let dir = tmp_dir();
+        assert!(!run(&[&dir]).ignore_readdir_race);
+        assert!(run(&["-ignore_readdir_race", &dir]).ignore_readdir_race);
+        let a = run(&["-ignore_readdir_race", "-noignore_readdir_race", &dir]);
+        assert!(!a.ignore_readdir_race);
+    }
+
+    #[test]
+    fn test_parse_inner_sort_ordered_reverse() {
+        let dir = tmp_dir();
+        let a = run(&["--sort", "size", "--reverse", &dir]);
+        assert_eq!(a.sort, Some(sort::Key::Size));
+        assert!(a.reverse && !a.ordered);
+        assert!(run(&["--ordered", &dir]).ordered);
+        assert!(parse_argv(&["--sort", "bogus", &dir]).is_err());
+        assert!(parse_argv(&["--sort", "name", "--ordered", &dir]).is_err());
+        assert!(parse_argv(&["--reverse", &dir]).is_err());
+    }
+
+    #[test]
+    fn test_parse_inner_path_style_last_wins() {
+        use pathstyle::Style;
+        let dir = tmp_dir();
+        assert_eq!(run(&[&dir]).path_style, Style::Absolute);
+        let a = run(&["--relative-to", "root", &dir]);
+        assert_eq!(a.path_style, Style::Root);
+        let a = run(&["--relative-to", "CWD", "--canonical", &dir]);
+        assert_eq!(a.path_style, Style::Canonical);
+        let a = run(&["--canonical", "--absolute-path", &dir]);
+        assert_eq!(a.path_style, Style::Absolute);
+        assert!(parse_argv(&["--relative-to", "home", &dir]).is_err());
+        let root_exec = ["--relative-to", "ROOT", "-exec", "ls", "{}", ";"];
+        assert!(parse_argv(&[&root_exec[..], &[&dir]].concat()).is_err());
+        let root_execdir = ["--relative-to", "ROOT", "-execdir", "ls", ";"];
+        run(&[&root_execdir[..], &[&dir]].concat());
+    }
+
+    #[test]
+    fn test_parse_inner_quote_style() {
+        let dir = tmp_dir();
+        assert_eq!(run(&[&dir]).quote, None);
+        assert_eq!(run(&["--quote=c", &dir]).quote, Some(quote::Style::C));
+        let a = run(&["--quote", "shell", &dir]);
+        assert_eq!(a.quote, Some(quote::Style::Shell));
+        assert!(parse_argv(&["--quote=locale", &dir]).is_err());
+    }
+
+    #[test]
+    fn test_parse_inner_color_when() {
+        let dir = tmp_dir();
+        assert_eq!(run(&[&dir]).color, color::When::Auto);
+        assert_eq!(run(&["--color=never", &dir]).color, color::When::Never);
+        let a = run(&["--color", "always", &dir]);
+        assert_eq!(a.color, color::When::Always);
+        assert!(parse_argv(&["--color=sometimes", &dir]).is_err());
+    }
+
+    #[test]
+    fn test_parse_inner_quiet_errors() {
+        let dir = tmp_dir();
+        assert!(!run(&[&dir]).quiet_errors);
+        assert!(run(&["--quiet-errors", &dir]).quiet_errors);
+    }
+
+    #[test]
+    fn test_parse_inner_stats() {
+        let dir = tmp_dir();
+        assert!(!run(&[&dir]).stats);
+        assert!(run(&["--stats", &dir]).stats);
+    }
+
+    #[test]
+    fn test_parse_inner_delete() {
+        let dir = tmp_dir();
+        let a = run(&["-delete", &dir]);
+        assert!(a.delete && !a.print);
+        assert!(run(&["--delete", "-print", &dir]).print);
+        assert!(parse_argv(&["-L", "--delete", &dir]).is_err());
+    }
+
+    #[test]
+    fn test_parse_inner_execdir_and_okdir() {
+        let dir = tmp_dir();
+        let a = run(&[
+            &dir, "-execdir", "rm", "{}", "+", "-okdir", "cat", "{}", ";",
+        ]);
+        assert_eq!(a.exec.len(), 2);
+        assert!(a.exec.iter().all(exec::Command::in_dir));
+        assert!(a.exec[0].is_batch() && !a.exec[1].is_batch());
+        assert!(parse_argv(&[&dir, "-okdir", "rm", "{}", "+"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_inner_jobs_and_group_output() {
+        let dir = tmp_dir();
+        let a = run(&["-j", "3", "--group-output", &dir]);
+        assert_eq!(a.jobs, 3);
+        assert!(a.group_output && a.print);
+        assert!(parse_argv(&["--jobs", "0", &dir]).is_err());
+    }
+
     #[test]
     fn test_parse_inner_name_repeatable() {
         let dir = tmp_dir();
+        // both must match, like find's -name a -name b
         let a = run(&["-n", "a", "--name", "b", &dir]);
-        assert_eq!(a.name, Some(vec!["a".to_string(), "b".to_string()]));
+        assert!(a.name.is_none());
+        assert!(matches!(a.expr, Some(expr::Expr::And(v)) if v.len() == 2));
+    }
+
+    #[test]
+    fn test_parse_inner_name_and_regex_together() {
+        let dir = tmp_dir();
+        let a = run(&["-iname", "*.JPG", "-regex", ".*/2024/.*", &dir]);
+        assert_eq!(a.iname, Some(vec!["*.JPG".to_string()]));
+        assert_eq!(a.regex, Some(vec![".*/2024/.*".to_string()]));
+        assert!(!a.any && !a.case_insensitive);
     }
 
     #[test]
-    fn test_parse_inner_name_regex_conflict() {
+    fn test_parse_inner_any_flag() {
         let dir = tmp_dir();
-        assert!(parse_argv(&["-n", "*.rs", "-r", ".*", &dir]).is_err());
+        assert!(run(&["--any", "-n", "*.rs", "-r", "x", &dir]).any);
     }
 
     #[test]
     fn test_parse_inner_file_type_names_and_aliases() {
         let dir = tmp_dir();
-        let a = run(&["-t", "f", "--file-type", "directory", &dir]);
+        let a = run(&["-t", "f,directory", &dir]);
         assert_eq!(a.file_type, vec![FileType::File, FileType::Directory]);
+        assert_eq!(run(&["--file-type", "l", &dir]).file_type.len(), 1);
     }
 
     #[test]
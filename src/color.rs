@@ -0,0 +1,356 @@
+// SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
+// SPDX-License-Identifier: MIT
+
+//! `--color` — paths painted by `LS_COLORS`, like `ls --color` and fd.
+//!
+//! The spec is parsed once into a [`Palette`]. A worker picks each match's
+//! [`Paint`] from its [`EntryType`], its name's suffix and — only when the
+//! spec has a style that needs it — its mode bits, link count or, for
+//! orphaned symlinks, its target; the output thread then wraps the path in
+//! that style's SGR sequence.
+
+use crate::filetype::EntryType;
+use crate::meta::{self, Meta};
+use crate::walk::{Entry, StatAt};
+use anyhow::{anyhow, Error};
+#[cfg(unix)]
+use std::os::unix::ffi::OsStrExt;
+
+/// Used when `LS_COLORS` is unset or empty: the common `dircolors` classes.
+const DEFAULT_SPEC: &str = "di=01;34:ln=01;36:so=01;35:pi=40;33:ex=01;32:\
+                            bd=40;33;01:cd=40;33;01:or=40;31;01:su=37;41:\
+                            sg=30;43:tw=30;42:ow=34;42:st=37;44";
+
+/// When to color (`--color`).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum When {
+    /// Only when stdout is a terminal and `NO_COLOR` is unset (decided by the
+    /// binary; the library treats it as `Never`).
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl When {
+    /// Parses a `--color` value.
+    ///
+    /// # Errors
+    ///
+    /// Anything but `auto`, `always` or `never`.
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        match s {
+            "auto" => Ok(When::Auto),
+            "always" => Ok(When::Always),
+            "never" => Ok(When::Never),
+            _ => Err(anyhow!(
+                "invalid --color '{s}' (expected auto, always or never)"
+            )),
+        }
+    }
+}
+
+/// An `LS_COLORS` class: a two-letter key.
+#[derive(Debug, Clone, Copy)]
+enum Class {
+    Normal,
+    File,
+    Dir,
+    Link,
+    Fifo,
+    Socket,
+    Block,
+    Char,
+    Orphan,
+    Exec,
+    Setuid,
+    Setgid,
+    Sticky,
+    OtherWritable,
+    StickyOtherWritable,
+    MultiLink,
+}
+
+impl Class {
+    const COUNT: usize = 16;
+
+    fn from_key(key: &str) -> Option<Self> {
+        Some(match key {
+            "no" => Class::Normal,
+            "fi" => Class::File,
+            "di" => Class::Dir,
+            "ln" => Class::Link,
+            "pi" => Class::Fifo,
+            "so" => Class::Socket,
+            "bd" => Class::Block,
+            "cd" => Class::Char,
+            "or" => Class::Orphan,
+            "ex" => Class::Exec,
+            "su" => Class::Setuid,
+            "sg" => Class::Setgid,
+            "st" => Class::Sticky,
+            "ow" => Class::OtherWritable,
+            "tw" => Class::StickyOtherWritable,
+            "mh" => Class::MultiLink,
+            _ => return None,
+        })
+    }
+}
+
+/// A style picked for one match: an index into its [`Palette`], 0 = none.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Paint(u16);
+
+/// A parsed `LS_COLORS`.
+#[derive(Debug, Default)]
+pub struct Palette {
+    // SGR parameter strings; [0] is the empty "no style"
+    styles: Vec<String>,
+    classes: [Paint; Class::COUNT],
+    // `*SUFFIX=…` entries, matched ASCII-case-insensitively, longest first
+    suffixes: Vec<(Vec<u8>, Paint)>,
+}
+
+impl Palette {
+    /// The palette from `LS_COLORS`, or the built-in one.
+    pub fn from_env() -> Self {
+        match std::env::var("LS_COLORS") {
+            Ok(spec) if !spec.is_empty() => Self::parse(&spec),
+            _ => Self::parse(DEFAULT_SPEC),
+        }
+    }
+
+    /// Parses an `LS_COLORS` value; unknown keys (`lc`, `ca`, …) and
+    /// malformed entries are ignored, as `ls` does.
+    pub fn parse(spec: &str) -> Self {
+        let mut p = Palette { styles: vec![String::new()], ..Self::default() };
+        for item in spec.split(':') {
+            let Some((key, sgr)) = item.split_once('=') else {
+                continue;
+            };
+            // `ln=target` (paint a link as what it points to) is not supported
+            if sgr.is_empty() || sgr == "target" {
+                continue;
+            }
+            let paint = Paint(u16::try_from(p.styles.len()).unwrap_or(0));
+            if let Some(suffix) = key.strip_prefix('*') {
+                p.suffixes
+                    .push((suffix.to_ascii_lowercase().into_bytes(), paint));
+            } else if let Some(c) = Class::from_key(key) {
+                p.classes[c as usize] = paint;
+            } else {
+                continue;
+            }
+            p.styles.push(sgr.to_string());
+        }
+        p.suffixes.sort_by_key(|s| std::cmp::Reverse(s.0.len()));
+        p
+    }
+
+    fn class(&self, c: Class) -> Paint {
+        self.classes[c as usize]
+    }
+
+    fn has(&self, c: Class) -> bool {
+        self.class(c) != Paint::default()
+    }
+
+    /// The `stat` fields choosing a style for a `ty` entry may need.
+    fn mask(&self, ty: EntryType) -> u32 {
+        use Class::*;
+        let any = |cs: &[Class]| cs.iter().any(|&c| self.has(c));
+        match ty {
+            EntryType::Dir
+                if any(&[StickyOtherWritable, OtherWritable, Sticky]) =>
+            {
+                meta::mask::MODE
+            }
+            EntryType::File => {
+                let mut m = 0;
+                if any(&[Setuid, Setgid, Exec]) {
+                    m |= meta::mask::MODE;
+                }
+                if self.has(MultiLink) {
+                    m |= meta::mask::NLINK;
+                }
+                m
+            }
+            _ => 0,
+        }
+    }
+
+    /// Picks the style for `entry`, `stat`-ing it only as far as this
+    /// palette needs.
+    pub fn pick(&self, entry: &Entry, stat: &StatAt) -> Paint {
+        let mask = self.mask(entry.file_type);
+        let m = if mask == 0 { None } else { stat.fetch(mask).ok() };
+        let fallback = |c: Class| match self.class(c) {
+            p if p == Paint::default() => self.class(Class::Normal),
+            p => p,
+        };
+        match entry.file_type {
+            EntryType::Dir => match m.map_or(0, |m| m.mode) {
+                mode if mode & 0o1002 == 0o1002
+                    && self.has(Class::StickyOtherWritable) =>
+                {
+                    self.class(Class::StickyOtherWritable)
+                }
+                mode if mode & 0o002 != 0
+                    && self.has(Class::OtherWritable) =>
+                {
+                    self.class(Class::OtherWritable)
+                }
+                mode if mode & 0o1000 != 0 && self.has(Class::Sticky) => {
+                    self.class(Class::Sticky)
+                }
+                _ => fallback(Class::Dir),
+            },
+            EntryType::Symlink => {
+                if self.has(Class::Orphan) && stat.followed().fetch(0).is_err()
+                {
+                    self.class(Class::Orphan)
+                } else {
+                    fallback(Class::Link)
+                }
+            }
+            EntryType::File => self.file(entry, m.as_ref()),
+            EntryType::Fifo => fallback(Class::Fifo),
+            EntryType::Socket => fallback(Class::Socket),
+            EntryType::BlockDevice => fallback(Class::Block),
+            EntryType::CharDevice => fallback(Class::Char),
+        }
+    }
+
+    /// Regular files: special modes first, then the name's suffix, then `fi`.
+    fn file(&self, entry: &Entry, m: Option<&Meta>) -> Paint {
+        let (mode, nlink) = m.map_or((0, 1), |m| (m.mode, m.nlink));
+        let special = [
+            (mode & 0o4000 != 0, Class::Setuid),
+            (mode & 0o2000 != 0, Class::Setgid),
+            (mode & 0o111 != 0, Class::Exec),
+            (nlink > 1, Class::MultiLink),
+        ];
+        if let Some(&(_, c)) =
+            special.iter().find(|&&(hit, c)| hit && self.has(c))
+        {
+            return self.class(c);
+        }
+        #[cfg(unix)]
+        let name = entry.file_name().as_bytes();
+        #[cfg(not(unix))]
+        let lossy = entry.file_name().to_string_lossy();
+        #[cfg(not(unix))]
+        let name = lossy.as_bytes();
+        let suffix = self.suffixes.iter().find(|(s, _)| {
+            name.len() >= s.len()
+                && name[name.len() - s.len()..].eq_ignore_ascii_case(s)
+        });
+        match suffix {
+            Some(&(_, p)) => p,
+            None if self.has(Class::File) => self.class(Class::File),
+            None => self.class(Class::Normal),
+        }
+    }
+
+    /// Appends `text` to `out` wrapped in `paint`'s SGR sequence.
+    pub fn write(&self, paint: Paint, text: &[u8], out: &mut Vec<u8>) {
+        match self.styles.get(usize::from(paint.0)) {
+            Some(sgr) if !sgr.is_empty() => {
+                out.extend_from_slice(b"\x1b[");
+                out.extend_from_slice(sgr.as_bytes());
+                out.push(b'm');
+                out.extend_from_slice(text);
+                out.extend_from_slice(b"\x1b[0m");
+            }
+            _ => out.extend_from_slice(text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn painted(p: &Palette, paint: Paint) -> String {
+        let mut out = Vec::new();
+        p.write(paint, b"x", &mut out);
+        String::from_utf8(out).unwrap()
+    }
+
+    fn entry(name: &str, file_type: EntryType) -> Entry {
+        Entry {
+            path: PathBuf::from(name),
+            file_type,
+            depth: 1,
+            order: None,
+            subtree: None,
+            root: 0,
+        }
+    }
+
+    #[test]
+    fn parse_keeps_known_classes_and_suffixes() {
+        let p = Palette::parse("di=01;34:lc=x:*.tar.gz=31:*.gz=32:bogus:fi=");
+        assert_eq!(painted(&p, p.class(Class::Dir)), "\x1b[01;34mx\x1b[0m");
+        assert_eq!(p.suffixes[0].0, b".tar.gz");
+        assert_eq!(painted(&p, p.class(Class::File)), "x");
+    }
+
+    #[test]
+    fn suffix_match_is_case_insensitive_and_longest_first() {
+        let p = Palette::parse("*.gz=32:*.TAR.GZ=31");
+        let file = |n| p.file(&entry(n, EntryType::File), None);
+        assert_eq!(painted(&p, file("a.tar.gz")), "\x1b[31mx\x1b[0m");
+        assert_eq!(painted(&p, file("b.GZ")), "\x1b[32mx\x1b[0m");
+        assert_eq!(file("c.txt"), Paint::default());
+    }
+
+    #[test]
+    fn special_modes_win_over_the_suffix() {
+        let p = Palette::parse("ex=01;32:su=37;41:*.sh=33");
+        let e = entry("run.sh", EntryType::File);
+        let m = |mode| Meta {
+            size: 0,
+            mtime: 0,
+            ctime: 0,
+            atime: 0,
+            mode,
+            uid: 0,
+            gid: 0,
+            nlink: 1,
+            ino: 0,
+            blocks: 0,
+            rdev: (0, 0),
+            dev: 0,
+        };
+        assert_eq!(
+            painted(&p, p.file(&e, Some(&m(0o755)))),
+            "\x1b[01;32mx\x1b[0m"
+        );
+        assert_eq!(
+            painted(&p, p.file(&e, Some(&m(0o4755)))),
+            "\x1b[37;41mx\x1b[0m"
+        );
+        assert_eq!(
+            painted(&p, p.file(&e, Some(&m(0o644)))),
+            "\x1b[33mx\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn stat_is_needed_only_for_mode_classes() {
+        let p = Palette::parse("di=34:*.rs=33");
+        assert_eq!(p.mask(EntryType::File), 0);
+        assert_eq!(p.mask(EntryType::Dir), 0);
+        let p = Palette::parse("tw=30;42:mh=44");
+        assert_eq!(p.mask(EntryType::Dir), meta::mask::MODE);
+        assert_eq!(p.mask(EntryType::File), meta::mask::NLINK);
+    }
+
+    #[test]
+    fn when_parses() {
+        assert_eq!(When::parse("always").unwrap(), When::Always);
+        assert!(When::parse("sometimes").is_err());
+    }
+}
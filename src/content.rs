@@ -0,0 +1,348 @@
+// SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
+// SPDX-License-Identifier: MIT
+
+//! `--contains PATTERN` — matches regular files whose bytes match a regex;
+//! `--text`, `--binary` and `--encoding` — classifies them by their head.
+//!
+//! The last and costliest tests in the visitor chain: a file is opened (with
+//! `openat` on the parent fd) only once every cheaper filter has kept it.
+//! Files larger than `--max-filesize` are left unread, and a file with a NUL
+//! byte in its first [`PROBE`] bytes is taken for binary and skipped, as
+//! `grep -I` does. Contents are searched a chunk of whole lines at a time,
+//! so like grep's, a match never spans a line break; a line longer than
+//! [`MAX_LINE`] is searched in overlapping windows instead, where a match
+//! longer than the overlap can be missed.
+//!
+//! A [`Class`] reads only those first [`PROBE`] bytes: a byte order mark
+//! settles the encoding, otherwise control bytes (NUL among them) make the
+//! file binary, and the rest is UTF-8 when it decodes as such and Latin-1
+//! when it does not.
+
+use crate::meta;
+use crate::walk::StatAt;
+use anyhow::{anyhow, Context, Error};
+use regex::bytes::{Regex, RegexBuilder};
+use std::io::{self, Read};
+
+/// How much of a file the binary check looks at.
+pub const PROBE: usize = 8 * 1024;
+
+// bytes read per chunk (a longer line grows the buffer)
+const CHUNK: usize = 256 * 1024;
+
+// a line growing past this is searched in windows, each keeping the last
+// OVERLAP bytes of the one before, so the buffer stays bounded
+const MAX_LINE: usize = 4 * CHUNK;
+const OVERLAP: usize = CHUNK;
+
+/// A compiled `--contains` test.
+#[derive(Debug, Clone)]
+pub struct Contains {
+    regex: Regex,
+    max_filesize: Option<u64>,
+}
+
+impl Contains {
+    /// Compiles `pattern`; files over `max_filesize` bytes never match.
+    ///
+    /// # Errors
+    ///
+    /// An invalid pattern.
+    pub fn new(
+        pattern: &str,
+        case_insensitive: bool,
+        max_filesize: Option<u64>,
+    ) -> Result<Self, Error> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(case_insensitive)
+            .multi_line(true)
+            .build()
+            .context("Unable to parse and build the --contains expression")?;
+        Ok(Contains { regex, max_filesize })
+    }
+
+    /// Whether the regular file behind `stat` is text and holds a match;
+    /// `buf` is the worker's scratch buffer, reused across files.
+    ///
+    /// # Errors
+    ///
+    /// The `stat`, open or read failure.
+    pub fn matches(
+        &self,
+        stat: &StatAt,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<bool> {
+        let limit = match self.max_filesize {
+            Some(max) => {
+                if stat.fetch(meta::mask::SIZE)?.size > max {
+                    return Ok(false);
+                }
+                max
+            }
+            None => u64::MAX,
+        };
+        self.search(stat.open()?.take(limit), buf)
+    }
+
+    fn search(
+        &self,
+        mut file: impl Read,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<bool> {
+        buf.clear();
+        let mut first = true;
+        // 1 when buf starts with the byte before a window, kept as context
+        // for `^` and `\b`
+        let mut skip = 0;
+        loop {
+            // top up to a full chunk past what is carried over
+            let start = buf.len();
+            buf.resize(start + CHUNK, 0);
+            let n = read_full(&mut file, &mut buf[start..])?;
+            buf.truncate(start + n);
+            if first {
+                if is_binary(&buf[..buf.len().min(PROBE)]) {
+                    return Ok(false);
+                }
+                first = false;
+            }
+            if n == 0 {
+                return Ok(self.regex.is_match_at(buf, skip));
+            }
+            // search the whole lines, carry the partial last one over
+            let Some(end) = buf.iter().rposition(|&b| b == b'\n') else {
+                if buf.len() >= MAX_LINE {
+                    // a match running into the window's end may go on past
+                    // it; the next window tries it again
+                    let hit = self.regex.find_at(buf, skip);
+                    if hit.is_some_and(|m| m.end() < buf.len()) {
+                        return Ok(true);
+                    }
+                    buf.drain(..buf.len() - OVERLAP - 1);
+                    skip = 1;
+                }
+                continue;
+            };
+            if self.regex.is_match_at(&buf[..end], skip) {
+                return Ok(true);
+            }
+            buf.drain(..=end);
+            skip = 0;
+        }
+    }
+}
+
+/// A text encoding `--encoding` can ask for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// UTF-8, plain ASCII included.
+    Utf8,
+    /// UTF-16 of either byte order, known by its byte order mark.
+    Utf16,
+    /// Text that is not UTF-8: ISO 8859-1 or a Windows code page.
+    Latin1,
+}
+
+impl Encoding {
+    /// Parses an `--encoding` value.
+    ///
+    /// # Errors
+    ///
+    /// An unknown encoding.
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        match s {
+            "utf8" => Ok(Encoding::Utf8),
+            "utf16" => Ok(Encoding::Utf16),
+            "latin1" => Ok(Encoding::Latin1),
+            _ => Err(anyhow!(
+                "invalid --encoding '{s}' (expected utf8, utf16 or latin1)"
+            )),
+        }
+    }
+}
+
+/// Which regular files `--text`, `--binary` or `--encoding` keep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    /// Text in any encoding.
+    Text,
+    /// Anything that is not text.
+    Binary,
+    /// Text in this encoding.
+    Encoding(Encoding),
+}
+
+impl Class {
+    /// Whether the regular file behind `stat` is of this class.
+    ///
+    /// # Errors
+    ///
+    /// The open or read failure.
+    pub fn matches(self, stat: &StatAt) -> io::Result<bool> {
+        let mut head = [0; PROBE];
+        let n = read_head(stat, &mut head)?;
+        let found = encoding(&head[..n]);
+        Ok(match self {
+            Class::Text => found.is_some(),
+            Class::Binary => found.is_none(),
+            Class::Encoding(e) => found == Some(e),
+        })
+    }
+}
+
+/// The encoding of a file starting with `head` (its first [`PROBE`] bytes),
+/// or `None` when it is binary. An empty file is UTF-8 text.
+pub fn encoding(head: &[u8]) -> Option<Encoding> {
+    if head.starts_with(b"\xef\xbb\xbf") {
+        return Some(Encoding::Utf8);
+    }
+    if head.starts_with(b"\xff\xfe") || head.starts_with(b"\xfe\xff") {
+        return Some(Encoding::Utf16);
+    }
+    if head.iter().any(|&b| is_control(b)) {
+        return None;
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => Some(Encoding::Utf8),
+        // only a character cut off where the probe ends
+        Err(e) if e.error_len().is_none() => Some(Encoding::Utf8),
+        Err(_) => Some(Encoding::Latin1),
+    }
+}
+
+// control bytes text never holds; tab, line breaks, form feed, bell,
+// backspace and escape it may, as file(1) has it
+fn is_control(b: u8) -> bool {
+    matches!(b, 0x00..=0x06 | 0x0e..=0x1a | 0x1c..=0x1f | 0x7f)
+}
+
+/// Whether a file starting with `head` (its first [`PROBE`] bytes) is
+/// binary to `--contains`: it holds a NUL byte.
+pub fn is_binary(head: &[u8]) -> bool {
+    head.contains(&0)
+}
+
+/// Reads the start of the regular file behind `stat` into `buf`, as much as
+/// fits; the count read.
+///
+/// # Errors
+///
+/// The open or read failure.
+pub fn read_head(stat: &StatAt, buf: &mut [u8]) -> io::Result<usize> {
+    read_full(&mut stat.open()?, buf)
+}
+
+// reads until `buf` is full or the file ends; the count read
+fn read_full(file: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        match file.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(m) => n += m,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(n)
+}
+
+/// Parses a `--max-filesize` value: bytes, or a number with one of
+/// `--size`'s `c`/`k`/`M`/`G`/`T` suffixes.
+///
+/// # Errors
+///
+/// Anything else, or a size past `u64`.
+pub fn parse_filesize(s: &str) -> Result<u64, Error> {
+    let (num, unit) = match s.char_indices().last() {
+        Some((i, 'c')) => (&s[..i], 1),
+        Some((i, 'k')) => (&s[..i], 1 << 10),
+        Some((i, 'M')) => (&s[..i], 1 << 20),
+        Some((i, 'G')) => (&s[..i], 1 << 30),
+        Some((i, 'T')) => (&s[..i], 1 << 40),
+        _ => (s, 1),
+    };
+    num.parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit))
+        .ok_or_else(|| anyhow!("invalid --max-filesize '{s}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(pattern: &str, data: &[u8]) -> bool {
+        let c = Contains::new(pattern, false, None).unwrap();
+        c.search(data, &mut Vec::new()).unwrap()
+    }
+
+    #[test]
+    fn finds_matches_across_chunks_line_by_line() {
+        assert!(found("needle", b"hay\nsome needle here\nhay"));
+        assert!(!found("needle", b"hay\nhay\n"));
+        assert!(found("^end$", b"start\nend"));
+        let mut long = vec![b'x'; 3 * CHUNK];
+        long.extend_from_slice(b"needle\n");
+        assert!(found("x+needle$", &long));
+        let mut spread = b"a\n".repeat(CHUNK);
+        spread.extend_from_slice(b"tail needle");
+        assert!(found("needle", &spread));
+    }
+
+    #[test]
+    fn endless_lines_are_searched_in_bounded_windows() {
+        let c = Contains::new("needle$", false, None).unwrap();
+        let mut line = vec![b'x'; 3 * MAX_LINE];
+        line.extend_from_slice(b"needle");
+        let mut buf = Vec::new();
+        assert!(c.search(&line[..], &mut buf).unwrap());
+        assert!(buf.capacity() <= 2 * MAX_LINE, "{}", buf.capacity());
+        // the window's end is not the line's, nor its start the line's
+        let mut cut = vec![b'x'; MAX_LINE - 6];
+        cut.extend_from_slice(b"needle and more");
+        cut.resize(3 * MAX_LINE, b'y');
+        assert!(!c.search(&cut[..], &mut buf).unwrap());
+        let c = Contains::new("^y", false, None).unwrap();
+        assert!(!c.search(&cut[..], &mut buf).unwrap());
+    }
+
+    #[test]
+    fn binary_files_are_skipped() {
+        assert!(!found("needle", b"needle\0"));
+        // a NUL past the probe does not make it binary
+        let mut late = b"needle\n".to_vec();
+        late.resize(PROBE + 1, b'.');
+        late.push(0);
+        assert!(found("needle", &late));
+    }
+
+    #[test]
+    fn encodings_by_bom_controls_and_utf8_validity() {
+        assert_eq!(encoding(b""), Some(Encoding::Utf8));
+        assert_eq!(encoding(b"plain\tascii\r\n\x1b[0m"), Some(Encoding::Utf8));
+        assert_eq!(encoding("čćž\n".as_bytes()), Some(Encoding::Utf8));
+        // a multi-byte character cut off by the probe
+        assert_eq!(encoding(&"ž".as_bytes()[..1]), Some(Encoding::Utf8));
+        assert_eq!(encoding(b"caf\xe9\n"), Some(Encoding::Latin1));
+        assert_eq!(encoding(b"\xff\xfeh\0i\0"), Some(Encoding::Utf16));
+        assert_eq!(encoding(b"\xef\xbb\xbfhi"), Some(Encoding::Utf8));
+        assert_eq!(encoding(b"text\0"), None);
+        assert_eq!(encoding(b"\x7fELF\x02\x01"), None);
+        assert_eq!(encoding(b"caf\xe9\x01"), None);
+    }
+
+    #[test]
+    fn encoding_parses_and_rejects_unknown() {
+        assert_eq!(Encoding::parse("utf16").unwrap(), Encoding::Utf16);
+        assert!(Encoding::parse("ascii").is_err());
+    }
+
+    #[test]
+    fn filesize_parses_with_units() {
+        assert_eq!(parse_filesize("100").unwrap(), 100);
+        assert_eq!(parse_filesize("2k").unwrap(), 2048);
+        assert_eq!(parse_filesize("1M").unwrap(), 1 << 20);
+        assert!(parse_filesize("1x").is_err());
+        assert!(parse_filesize("k").is_err());
+    }
+}
@@ -0,0 +1,202 @@
+// SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
+// SPDX-License-Identifier: MIT
+
+//! `-c`/`--count[=type]` — how many entries match, per starting point.
+//!
+//! Nothing goes through the output channel: each worker adds its matches to
+//! its own [`Local`] tally, merged into the shared [`Counts`] when the worker
+//! ends, and the lines are written once the walk is done. When no filter
+//! reads a name or path, the walker does not even build the paths of the
+//! entries it does not descend into (see `walk`).
+
+use crate::filetype::EntryType;
+use crate::quote;
+use crate::regex::path_to_bytes;
+use anyhow::{anyhow, Error};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// What `--count` breaks the counts down by.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum By {
+    /// One count per starting point (`-c`, `--count`).
+    #[default]
+    Root,
+    /// Per starting point and entry type (`--count=type`).
+    Type,
+}
+
+impl By {
+    /// Parses a `--count=` value.
+    ///
+    /// # Errors
+    ///
+    /// Anything but `type`.
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        match s {
+            "type" => Ok(By::Type),
+            _ => {
+                Err(anyhow!("invalid --count breakdown '{s}' (expected type)"))
+            }
+        }
+    }
+}
+
+// the order --count=type lists them in
+pub(crate) const TYPES: [EntryType; 7] = [
+    EntryType::File,
+    EntryType::Dir,
+    EntryType::Symlink,
+    EntryType::BlockDevice,
+    EntryType::CharDevice,
+    EntryType::Fifo,
+    EntryType::Socket,
+];
+
+pub(crate) fn slot(ty: EntryType) -> usize {
+    TYPES.iter().position(|&t| t == ty).unwrap_or(0)
+}
+
+/// Matches per starting point and type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counts {
+    roots: Vec<[u64; TYPES.len()]>,
+}
+
+impl Counts {
+    /// All zero, for `roots` starting points.
+    pub fn new(roots: usize) -> Self {
+        Counts { roots: vec![[0; TYPES.len()]; roots] }
+    }
+
+    /// Counts one match below starting point `root`.
+    #[inline]
+    pub fn add(&mut self, root: usize, ty: EntryType) {
+        if let Some(r) = self.roots.get_mut(root) {
+            r[slot(ty)] += 1;
+        }
+    }
+
+    fn merge(&mut self, other: &Counts) {
+        for (mine, theirs) in self.roots.iter_mut().zip(&other.roots) {
+            for (m, t) in mine.iter_mut().zip(theirs) {
+                *m += t;
+            }
+        }
+    }
+
+    /// Appends the report to `out`: `COUNT<TAB>ROOT` per starting point (or
+    /// `COUNT<TAB>TYPE<TAB>ROOT` for each type found), then the same for
+    /// `total` when there are several.
+    pub fn render(
+        &self,
+        by: By,
+        roots: &[&Path],
+        style: quote::Style,
+        separator: u8,
+        out: &mut Vec<u8>,
+    ) {
+        let mut total = [0; TYPES.len()];
+        for (counts, root) in self.roots.iter().zip(roots) {
+            let mut name = Vec::new();
+            quote::write(style, &path_to_bytes(root), &mut name);
+            line(by, counts, &name, separator, out);
+            for (t, n) in total.iter_mut().zip(counts) {
+                *t += n;
+            }
+        }
+        if roots.len() > 1 {
+            line(by, &total, b"total", separator, out);
+        }
+    }
+}
+
+fn line(
+    by: By,
+    counts: &[u64; TYPES.len()],
+    name: &[u8],
+    separator: u8,
+    out: &mut Vec<u8>,
+) {
+    match by {
+        By::Root => {
+            let _ = write!(out, "{}\t", counts.iter().sum::<u64>());
+            out.extend_from_slice(name);
+            out.push(separator);
+        }
+        By::Type => {
+            for (ty, &n) in TYPES.iter().zip(counts).filter(|(_, &n)| n > 0) {
+                let _ = write!(out, "{n}\t{}\t", char::from(ty.type_char()));
+                out.extend_from_slice(name);
+                out.push(separator);
+            }
+        }
+    }
+}
+
+/// One worker's tally, added to `into` when the worker ends.
+pub struct Local<'a> {
+    counts: Counts,
+    into: &'a Mutex<Counts>,
+}
+
+impl<'a> Local<'a> {
+    /// An empty tally shaped like `into`.
+    pub fn new(into: &'a Mutex<Counts>) -> Self {
+        let roots = into.lock().map_or(0, |c| c.roots.len());
+        Local { counts: Counts::new(roots), into }
+    }
+
+    /// Counts one match below starting point `root`.
+    #[inline]
+    pub fn add(&mut self, root: usize, ty: EntryType) {
+        self.counts.add(root, ty);
+    }
+}
+
+impl Drop for Local<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut shared) = self.into.lock() {
+            shared.merge(&self.counts);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shared() -> Mutex<Counts> {
+        let shared = Mutex::new(Counts::new(2));
+        let (mut a, mut b) = (Local::new(&shared), Local::new(&shared));
+        a.add(0, EntryType::File);
+        a.add(0, EntryType::Dir);
+        b.add(0, EntryType::File);
+        b.add(1, EntryType::Symlink);
+        drop((a, b));
+        shared
+    }
+
+    fn rendered(by: By) -> String {
+        let counts = shared().into_inner().unwrap();
+        let mut out = Vec::new();
+        let roots = [Path::new("/a"), Path::new("/b")];
+        counts.render(by, &roots, quote::Style::Literal, b'\n', &mut out);
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn workers_merge_into_per_root_totals() {
+        assert_eq!(rendered(By::Root), "3\t/a\n1\t/b\n4\ttotal\n");
+    }
+
+    #[test]
+    fn type_breakdown_lists_only_types_found() {
+        assert_eq!(
+            rendered(By::Type),
+            "2\tf\t/a\n1\td\t/a\n1\tl\t/b\n2\tf\ttotal\n1\td\ttotal\n\
+             1\tl\ttotal\n"
+        );
+    }
+}
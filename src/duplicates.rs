@@ -0,0 +1,330 @@
+// SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
+// SPDX-License-Identifier: MIT
+
+//! `--duplicates` — groups of matched regular files with identical contents.
+//!
+//! The walk only collects each matched file's size (one `statx` for
+//! `SIZE`) and an [`Anchored`] handle on it, per worker into a [`Local`]
+//! list merged when the worker ends. Once it is done, files whose size is
+//! unique are dropped, the rest get an XXH3-128 hash of their first
+//! [`PARTIAL`] bytes, and only those still sharing size and partial hash
+//! are confirmed by a BLAKE3 hash of their whole contents (a file no longer
+//! than [`PARTIAL`] gets it in the first round). Both rounds run on as many
+//! threads as the walk did, each spending a `--max-scan-rate` token per file
+//! opened through the directory fd the walk held, and several links to one
+//! inode count once. Empty files are never reported: they are all alike.
+
+use crate::hash::feed;
+use crate::quote;
+use crate::ratelimit::Limiter;
+use crate::regex::path_to_bytes;
+use crate::report::Errors;
+use crate::walk::Anchored;
+use itertools::Itertools;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use xxhash_rust::xxh3::Xxh3;
+
+/// How much of each file the first round hashes.
+pub const PARTIAL: u64 = 4096;
+
+/// A matched regular file, its size and how to open it again.
+#[derive(Clone)]
+pub struct Candidate {
+    pub size: u64,
+    pub path: PathBuf,
+    pub file: Anchored,
+}
+
+/// One worker's files, appended to `into` when the worker ends.
+pub struct Local<'a> {
+    files: Vec<Candidate>,
+    into: &'a Mutex<Vec<Candidate>>,
+}
+
+impl<'a> Local<'a> {
+    /// An empty list.
+    pub fn new(into: &'a Mutex<Vec<Candidate>>) -> Self {
+        Local { files: Vec::new(), into }
+    }
+
+    /// Collects one matched regular file.
+    #[inline]
+    pub fn add(&mut self, size: u64, path: PathBuf, file: Anchored) {
+        self.files.push(Candidate { size, path, file });
+    }
+}
+
+impl Drop for Local<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut shared) = self.into.lock() {
+            shared.append(&mut self.files);
+        }
+    }
+}
+
+/// Where and how the hashing rounds run.
+pub struct Pool<'a> {
+    /// Hashing threads.
+    pub workers: usize,
+    /// `--max-scan-rate`, one token per file opened.
+    pub limiter: Option<&'a Limiter>,
+    /// Set on interrupt; hashing stops and reports nothing further.
+    pub quit: &'a AtomicBool,
+    /// Where unreadable files are reported (and then left out).
+    pub errors: &'a Errors,
+    /// Run first in each thread (`--idle` priorities).
+    pub enter: &'a (dyn Fn() + Sync),
+}
+
+// a hashed file: its inode, when known, tells hard links apart
+struct Hashed {
+    id: Option<(u64, u64)>,
+    // XXH3-128 of a prefix, or BLAKE3 of the whole file
+    hash: [u8; 32],
+    file: Candidate,
+}
+
+/// The groups of identical files among `files`, largest files first, each
+/// sorted by path.
+pub fn groups(mut files: Vec<Candidate>, pool: &Pool) -> Vec<Vec<PathBuf>> {
+    // round 0: a size shared by another file
+    files.retain(|f| f.size > 0);
+    files.sort_unstable_by_key(|f| f.size);
+    let files: Vec<Candidate> = files
+        .into_iter()
+        .chunk_by(|f| f.size)
+        .into_iter()
+        .map(|(_, same)| same.collect::<Vec<_>>())
+        .filter(|same| same.len() > 1)
+        .flatten()
+        .collect();
+
+    // round 1: the first PARTIAL bytes (all of a small file, with the
+    // strong hash), then one path per inode
+    let mut hashed = pool.map(&files, |f| pool.hash(f, f.size <= PARTIAL));
+    hashed.sort_unstable_by(|a, b| {
+        (a.id, &a.file.path).cmp(&(b.id, &b.file.path))
+    });
+    hashed.dedup_by(|b, a| a.id.is_some() && a.id == b.id);
+    let mut out = Vec::new();
+    let mut unsure = Vec::new();
+    for group in alike(hashed) {
+        if group[0].file.size <= PARTIAL {
+            // the first round already read the whole file
+            out.push(group);
+        } else {
+            unsure.extend(group.into_iter().map(|h| h.file));
+        }
+    }
+
+    // round 2: the whole contents of the rest
+    let full = pool.map(&unsure, |f| pool.hash(f, true));
+    out.extend(alike(full));
+
+    let mut out: Vec<(u64, Vec<PathBuf>)> = out
+        .into_iter()
+        .map(|g| {
+            let size = g[0].file.size;
+            let mut paths: Vec<PathBuf> =
+                g.into_iter().map(|h| h.file.path).collect();
+            paths.sort_unstable();
+            (size, paths)
+        })
+        .collect();
+    out.sort_unstable_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    out.into_iter().map(|(_, paths)| paths).collect()
+}
+
+// the runs of two or more files sharing size and hash
+fn alike(mut hashed: Vec<Hashed>) -> Vec<Vec<Hashed>> {
+    hashed.sort_unstable_by_key(|h| (h.file.size, h.hash));
+    hashed
+        .into_iter()
+        .chunk_by(|h| (h.file.size, h.hash))
+        .into_iter()
+        .map(|(_, same)| same.collect::<Vec<_>>())
+        .filter(|same| same.len() > 1)
+        .collect()
+}
+
+impl Pool<'_> {
+    // `f` over `items` on the pool's threads, in no particular order
+    fn map<T: Sync, R: Send>(
+        &self,
+        items: &[T],
+        f: impl Fn(&T) -> Option<R> + Sync,
+    ) -> Vec<R> {
+        let next = AtomicUsize::new(0);
+        let out = Mutex::new(Vec::with_capacity(items.len()));
+        thread::scope(|scope| {
+            for _ in 0..self.workers.min(items.len()) {
+                scope.spawn(|| {
+                    (self.enter)();
+                    let mut mine = Vec::new();
+                    while let Some(item) =
+                        items.get(next.fetch_add(1, Ordering::Relaxed))
+                    {
+                        if let Some(r) = f(item) {
+                            mine.push(r);
+                        }
+                    }
+                    if let Ok(mut shared) = out.lock() {
+                        shared.append(&mut mine);
+                    }
+                });
+            }
+        });
+        out.into_inner().unwrap_or_default()
+    }
+
+    // one file's BLAKE3 hash if `whole`, else the XXH3-128 of its first
+    // PARTIAL bytes; None once interrupted or when it cannot be read
+    // (reported)
+    fn hash(&self, f: &Candidate, whole: bool) -> Option<Hashed> {
+        if self.quit.load(Ordering::Relaxed)
+            || self.limiter.is_some_and(|l| !l.acquire(self.quit))
+        {
+            return None;
+        }
+        let hashed = f.file.open().and_then(|file| {
+            let id = identity(&file)?;
+            Ok(Hashed { id, hash: digest(file, whole)?, file: f.clone() })
+        });
+        match hashed {
+            Ok(h) => Some(h),
+            Err(e) => {
+                self.errors.io(&f.path, &e);
+                None
+            }
+        }
+    }
+}
+
+fn digest(file: File, whole: bool) -> io::Result<[u8; 32]> {
+    let mut buf = vec![0; 64 * 1024];
+    let mut hash = [0; 32];
+    if whole {
+        let mut hasher = blake3::Hasher::new();
+        feed(file, &mut buf, |b| {
+            hasher.update(b);
+        })?;
+        hash = *hasher.finalize().as_bytes();
+    } else {
+        let mut hasher = Xxh3::new();
+        feed(file.take(PARTIAL), &mut buf, |b| hasher.update(b))?;
+        hash[..16].copy_from_slice(&hasher.digest128().to_be_bytes());
+    }
+    Ok(hash)
+}
+
+#[cfg(unix)]
+fn identity(file: &File) -> io::Result<Option<(u64, u64)>> {
+    use std::os::unix::fs::MetadataExt;
+    let m = file.metadata()?;
+    Ok(Some((m.dev(), m.ino())))
+}
+
+// no inode to compare: every link is its own file
+#[cfg(not(unix))]
+fn identity(_file: &File) -> io::Result<Option<(u64, u64)>> {
+    Ok(None)
+}
+
+/// Appends the groups to `out`: each path ended by `separator`, and each
+/// group by one more.
+pub fn render(
+    groups: &[Vec<PathBuf>],
+    style: quote::Style,
+    separator: u8,
+    out: &mut Vec<u8>,
+) {
+    for group in groups {
+        for path in group {
+            quote::write(style, &path_to_bytes(path), out);
+            out.push(separator);
+        }
+        out.push(separator);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    fn find(dir: &Path, names: &[&str]) -> Vec<Vec<String>> {
+        let files = names
+            .iter()
+            .map(|n| {
+                let path = dir.join(n);
+                let size = std::fs::metadata(&path).unwrap().len();
+                let file = Anchored::at(&path);
+                Candidate { size, path, file }
+            })
+            .collect();
+        let quit = AtomicBool::new(false);
+        let errors = Errors::new(true, false);
+        let pool = Pool {
+            workers: 3,
+            limiter: None,
+            quit: &quit,
+            errors: &errors,
+            enter: &|| {},
+        };
+        groups(files, &pool)
+            .into_iter()
+            .map(|g| {
+                g.iter()
+                    .map(|p| p.file_name().unwrap().to_string_lossy().into())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn groups_identical_contents_largest_first() {
+        let tmp = TempDir::new().unwrap();
+        let big = vec![7_u8; 3 * PARTIAL as usize];
+        let mut differs_late = big.clone();
+        *differs_late.last_mut().unwrap() = 8;
+        let write = |n: &str, data: &[u8]| {
+            std::fs::write(tmp.path().join(n), data).unwrap();
+        };
+        write("a", b"same");
+        write("b", b"same");
+        write("c", b"diff");
+        write("d", &big);
+        write("e", &big);
+        write("f", &differs_late);
+        write("g", b"");
+        write("h", b"");
+        let got = find(tmp.path(), &["a", "b", "c", "d", "e", "f", "g", "h"]);
+        assert_eq!(got, [vec!["d", "e"], vec!["a", "b"]]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_links_count_once() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("a"), b"same").unwrap();
+        std::fs::hard_link(tmp.path().join("a"), tmp.path().join("b"))
+            .unwrap();
+        assert!(find(tmp.path(), &["a", "b"]).is_empty());
+        std::fs::write(tmp.path().join("c"), b"same").unwrap();
+        assert_eq!(find(tmp.path(), &["a", "b", "c"]), [vec!["a", "c"]]);
+    }
+
+    #[test]
+    fn render_ends_each_group_with_an_empty_line() {
+        let groups = vec![vec![PathBuf::from("/a"), PathBuf::from("/b c")]];
+        let mut out = Vec::new();
+        render(&groups, quote::Style::Shell, b'\n', &mut out);
+        assert_eq!(out, b"/a\n'/b c'\n\n");
+    }
+}
@@ -0,0 +1,475 @@
+// SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
+// SPDX-License-Identifier: MIT
+
+//! `-exec CMD {} ;` and `-exec CMD {} +` — running commands on matches —
+//! plus `-execdir`/`-okdir`, which run in the entry's parent directory.
+//!
+//! Commands are parsed at arg-parse time into [`Command`]s. At run time the
+//! output thread owns a [`Dispatcher`] that turns matched paths into jobs —
+//! one per path for `;`, `ARG_MAX`-bounded batches for `+` — and feeds them
+//! over a bounded channel to a [`Pool`] of executor threads. Commands are
+//! actions, not tests: they run after the whole expression has matched, so
+//! the parser refuses anything find would make depend on a `;` command's
+//! exit status (a later action, test or `-o`, or a command inside `( )`).
+//! A failing `;` command is otherwise ignored, while a failing `+` batch (or
+//! a command that cannot be started) makes the whole run exit non-zero.
+//!
+//! `-execdir` commands get the parent directory as a [`ExecDir`]: the
+//! child enters it by `fchdir` on the walker's held fd and sees `{}` as
+//! `./leaf`, so no path component is re-resolved between match and run.
+
+use crate::walk::ExecDir;
+use anyhow::{anyhow, Error};
+use crossbeam_channel::{bounded, Receiver, Sender};
+use std::ffi::{OsStr, OsString};
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+#[cfg(unix)]
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+/// The placeholder replaced by the matched path.
+const BRACES: &str = "{}";
+
+/// Bytes of `ARG_MAX` left unused for the environment's growth and the
+/// kernel's own bookkeeping, as find does.
+const ARG_HEADROOM: usize = 2048;
+
+/// Serializes `-okdir` prompts so parallel jobs ask one at a time.
+static PROMPT: Mutex<()> = Mutex::new(());
+
+/// Which action a [`Command`] came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// `-exec`: run from minifind's working directory on the full path.
+    Exec,
+    /// `-execdir`: run in the entry's parent directory on `./leaf`.
+    ExecDir,
+    /// `-okdir`: like `-execdir`, after a yes on stdin.
+    OkDir,
+}
+
+impl Mode {
+    fn flag(self) -> &'static str {
+        match self {
+            Mode::Exec => "-exec",
+            Mode::ExecDir => "-execdir",
+            Mode::OkDir => "-okdir",
+        }
+    }
+}
+
+/// One `-exec` action: the command words and whether it batches (`+`).
+#[derive(Debug, Clone)]
+pub struct Command {
+    argv: Vec<OsString>,
+    batch: bool,
+    mode: Mode,
+}
+
+impl Command {
+    /// Reads the words of one `mode` action from `raw` up to its terminator:
+    /// a `;`, or a `+` directly after a lone `{}` (any other `+` is an
+    /// argument).
+    ///
+    /// # Errors
+    ///
+    /// A missing terminator, an empty command, a `+` command with more than
+    /// one `{}`, or `-okdir … +`.
+    pub fn parse(
+        mode: Mode,
+        raw: impl Iterator<Item = OsString>,
+    ) -> Result<Self, Error> {
+        let flag = mode.flag();
+        let mut argv: Vec<OsString> = Vec::new();
+        let mut batch = None;
+        for word in raw {
+            if word == ";" {
+                batch = Some(false);
+                break;
+            }
+            if word == "+" && argv.last().is_some_and(|w| w == BRACES) {
+                batch = Some(true);
+                break;
+            }
+            argv.push(word);
+        }
+        let Some(batch) = batch else {
+            return Err(anyhow!(
+                "missing argument to '{flag}' (terminate it with ';' or '{{}} +')"
+            ));
+        };
+        if argv.is_empty() || batch && argv.len() == 1 {
+            return Err(anyhow!("'{flag}' needs a command"));
+        }
+        // find asks once per entry, so there is nothing to batch
+        if batch && mode == Mode::OkDir {
+            return Err(anyhow!("'-okdir' only supports ';'"));
+        }
+        // `+` appends the paths where its trailing `{}` stands; find allows
+        // no other placeholder there
+        if batch && argv[..argv.len() - 1].iter().any(|w| has_braces(w)) {
+            return Err(anyhow!(
+                "only one instance of '{{}}' is supported with '{flag} … +'"
+            ));
+        }
+        if batch {
+            argv.pop();
+        }
+        Ok(Command { argv, batch, mode })
+    }
+
+    /// Whether this is a batching `-exec … {} +`.
+    pub fn is_batch(&self) -> bool {
+        self.batch
+    }
+
+    /// Whether this runs in the entry's parent directory (`-execdir`,
+    /// `-okdir`), and so needs a [`ExecDir`] per match.
+    pub fn in_dir(&self) -> bool {
+        self.mode != Mode::Exec
+    }
+
+    /// The argv for one `;` run on `path`: every `{}` substituted.
+    fn argv_for(&self, path: &OsStr) -> Vec<OsString> {
+        self.argv.iter().map(|w| replace_braces(w, path)).collect()
+    }
+
+    /// `ARG_MAX` cost of the fixed words (`+` commands).
+    fn base_cost(&self) -> usize {
+        self.argv.iter().map(|w| arg_cost(w)).sum()
+    }
+}
+
+/// A unit of work for the pool: command `cmd` over `paths` (exactly one for
+/// `;` commands), run in `dir` for `-execdir`/`-okdir`.
+struct Job {
+    cmd: usize,
+    paths: Vec<PathBuf>,
+    dir: Option<ExecDir>,
+}
+
+/// A `+` batch being filled: its paths, their `ARG_MAX` cost, and for
+/// `-execdir` the directory they all share.
+struct Pending {
+    paths: Vec<PathBuf>,
+    used: usize,
+    dir: Option<ExecDir>,
+}
+
+/// Turns matched paths into jobs; lives on the output thread, so `+` batches
+/// are assembled without locking.
+pub struct Dispatcher {
+    commands: Arc<[Command]>,
+    tx: Sender<Job>,
+    // one pending `+` batch per command
+    pending: Vec<Pending>,
+    limit: usize,
+}
+
+impl Dispatcher {
+    /// Queues `path` for every command, sending a `+` batch once the next
+    /// path would push it past `ARG_MAX` (or, for `-execdir`, lives in
+    /// another directory). `dir` must be given when any command
+    /// [`in_dir`](Command::in_dir).
+    pub fn dispatch(&mut self, path: &std::path::Path, dir: Option<&ExecDir>) {
+        for (cmd, c) in self.commands.iter().enumerate() {
+            let (path, dir) = if c.in_dir() {
+                let dir = dir.expect("-execdir match without its directory");
+                (dir.leaf(), Some(dir))
+            } else {
+                (path, None)
+            };
+            if !c.batch {
+                let paths = vec![path.to_path_buf()];
+                let _ = self.tx.send(Job { cmd, paths, dir: dir.cloned() });
+                continue;
+            }
+            let cost = arg_cost(path.as_os_str());
+            let p = &mut self.pending[cmd];
+            let moved = match (&p.dir, dir) {
+                (Some(a), Some(b)) => !a.same_dir(b),
+                _ => false,
+            };
+            if !p.paths.is_empty() && (moved || p.used + cost > self.limit) {
+                let job = Job {
+                    cmd,
+                    paths: std::mem::take(&mut p.paths),
+                    dir: p.dir.take(),
+                };
+                p.used = c.base_cost();
+                let _ = self.tx.send(job);
+            }
+            if p.paths.is_empty() {
+                p.dir = dir.cloned();
+            }
+            p.paths.push(path.to_path_buf());
+            p.used += cost;
+        }
+    }
+
+    /// Sends the partial `+` batches; the pool drains once this is dropped.
+    pub fn finish(mut self) {
+        for (cmd, p) in self.pending.iter_mut().enumerate() {
+            if !p.paths.is_empty() {
+                let paths = std::mem::take(&mut p.paths);
+                let _ = self.tx.send(Job { cmd, paths, dir: p.dir.take() });
+            }
+        }
+    }
+}
+
+/// The executor threads. [`join`](Pool::join) waits for every job and
+/// reports whether the run should exit non-zero.
+pub struct Pool {
+    workers: Vec<JoinHandle<()>>,
+    failed: Arc<AtomicBool>,
+}
+
+impl Pool {
+    /// Spawns `jobs` executor threads for `commands` and returns them with
+    /// the [`Dispatcher`] feeding them. With `group`, each command's stdout
+    /// and stderr are captured and written out together once it exits.
+    pub fn spawn(
+        commands: &[Command],
+        jobs: usize,
+        group: bool,
+    ) -> (Pool, Dispatcher) {
+        let commands: Arc<[Command]> = commands.into();
+        let jobs = jobs.max(1);
+        let (tx, rx) = bounded::<Job>(jobs * 2);
+        let failed = Arc::new(AtomicBool::new(false));
+        let workers = (0..jobs)
+            .map(|_| {
+                let rx = rx.clone();
+                let commands = Arc::clone(&commands);
+                let failed = Arc::clone(&failed);
+                thread::spawn(move || worker(&rx, &commands, group, &failed))
+            })
+            .collect();
+        let limit = arg_limit();
+        let pending = commands
+            .iter()
+            .map(|c| Pending {
+                paths: Vec::new(),
+                used: c.base_cost(),
+                dir: None,
+            })
+            .collect();
+        let dispatcher = Dispatcher { commands, tx, pending, limit };
+        (Pool { workers, failed }, dispatcher)
+    }
+
+    /// Waits for the queued jobs to finish. Returns `true` when a `+` batch
+    /// failed or a command could not be started.
+    pub fn join(self) -> bool {
+        for w in self.workers {
+            let _ = w.join();
+        }
+        self.failed.load(Ordering::Relaxed)
+    }
+}
+
+fn worker(
+    rx: &Receiver<Job>,
+    commands: &[Command],
+    group: bool,
+    failed: &AtomicBool,
+) {
+    for job in rx {
+        let c = &commands[job.cmd];
+        let argv = if c.batch {
+            let mut argv = c.argv.clone();
+            argv.extend(job.paths.into_iter().map(PathBuf::into_os_string));
+            argv
+        } else {
+            c.argv_for(job.paths[0].as_os_str())
+        };
+        if c.mode == Mode::OkDir && !confirm(&argv) {
+            continue;
+        }
+        let mut cmd = process::Command::new(&argv[0]);
+        cmd.args(&argv[1..]);
+        if let Some(dir) = &job.dir {
+            dir.enter(&mut cmd);
+        }
+        // stdin carried the answer; like find, the command gets none
+        if c.mode == Mode::OkDir {
+            cmd.stdin(process::Stdio::null());
+        }
+        let status = if group {
+            cmd.output().map(|out| {
+                // one lock each, so the command's lines stay together
+                let _ = io::stdout().lock().write_all(&out.stdout);
+                let _ = io::stderr().lock().write_all(&out.stderr);
+                out.status
+            })
+        } else {
+            cmd.status()
+        };
+        match status {
+            Ok(s) if !s.success() && c.batch => {
+                failed.store(true, Ordering::Relaxed);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("minifind: {}: {e}", argv[0].to_string_lossy());
+                failed.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Asks on stderr whether to run `argv`, find-style (`< cmd ./leaf > ? `),
+/// and reads the answer from stdin; only `y`/`Y` runs it.
+fn confirm(argv: &[OsString]) -> bool {
+    let _guard = PROMPT.lock().unwrap_or_else(|e| e.into_inner());
+    let words: Vec<_> = argv.iter().map(|w| w.to_string_lossy()).collect();
+    let mut err = io::stderr().lock();
+    let _ = write!(err, "< {} > ? ", words.join(" "));
+    let _ = err.flush();
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    answer.trim_start().starts_with(['y', 'Y'])
+}
+
+fn has_braces(word: &OsStr) -> bool {
+    bytes(word).windows(2).any(|w| w == BRACES.as_bytes())
+}
+
+/// `word` with every `{}` replaced by `path` (find does this anywhere in an
+/// argument for `;` commands).
+fn replace_braces(word: &OsStr, path: &OsStr) -> OsString {
+    if !has_braces(word) {
+        return word.to_owned();
+    }
+    let (w, p) = (bytes(word), bytes(path));
+    let mut out = Vec::with_capacity(w.len() + p.len());
+    let mut i = 0;
+    while i < w.len() {
+        if w[i..].starts_with(BRACES.as_bytes()) {
+            out.extend_from_slice(&p);
+            i += BRACES.len();
+        } else {
+            out.push(w[i]);
+            i += 1;
+        }
+    }
+    from_bytes(out)
+}
+
+#[cfg(unix)]
+fn bytes(s: &OsStr) -> std::borrow::Cow<'_, [u8]> {
+    std::borrow::Cow::Borrowed(s.as_bytes())
+}
+
+#[cfg(not(unix))]
+fn bytes(s: &OsStr) -> std::borrow::Cow<'_, [u8]> {
+    std::borrow::Cow::Owned(s.to_string_lossy().into_owned().into_bytes())
+}
+
+#[cfg(unix)]
+fn from_bytes(b: Vec<u8>) -> OsString {
+    OsString::from_vec(b)
+}
+
+#[cfg(not(unix))]
+fn from_bytes(b: Vec<u8>) -> OsString {
+    String::from_utf8_lossy(&b).into_owned().into()
+}
+
+/// What one argument costs against `ARG_MAX`: its bytes, the NUL and the
+/// argv pointer.
+fn arg_cost(word: &OsStr) -> usize {
+    word.len() + 1 + std::mem::size_of::<usize>()
+}
+
+/// The argv budget for one `+` invocation: `ARG_MAX` less the environment
+/// and find's headroom.
+fn arg_limit() -> usize {
+    #[cfg(unix)]
+    // SAFETY: sysconf has no preconditions.
+    let max = match unsafe { libc::sysconf(libc::_SC_ARG_MAX) } {
+        n if n > 0 => n as usize,
+        _ => 128 * 1024,
+    };
+    // Windows caps a command line at 32767 UTF-16 units
+    #[cfg(not(unix))]
+    let max = 32 * 1024;
+    let env: usize =
+        std::env::vars_os().map(|(k, v)| arg_cost(&k) + v.len() + 1).sum();
+    max.saturating_sub(env).saturating_sub(ARG_HEADROOM).max(4096)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(s: &str) -> impl Iterator<Item = OsString> + '_ {
+        s.split(' ').map(OsString::from)
+    }
+
+    #[test]
+    fn parse_semicolon_and_plus() {
+        let c = Command::parse(Mode::Exec, words("echo x{}y ; rest")).unwrap();
+        assert!(!c.is_batch());
+        assert_eq!(c.argv_for(OsStr::new("P")), ["echo", "xPy"]);
+
+        let c = Command::parse(Mode::Exec, words("rm -f {} +")).unwrap();
+        assert!(c.is_batch());
+        assert_eq!(c.argv, ["rm", "-f"]);
+    }
+
+    #[test]
+    fn plus_not_after_braces_is_an_argument() {
+        let c = Command::parse(Mode::Exec, words("expr 1 + 2 ;")).unwrap();
+        assert!(!c.is_batch());
+        assert_eq!(c.argv, ["expr", "1", "+", "2"]);
+    }
+
+    #[test]
+    fn malformed_commands_error() {
+        for bad in ["echo {}", ";", "{} +", "cp {} {} +"] {
+            assert!(
+                Command::parse(Mode::Exec, words(bad)).is_err(),
+                "{bad:?} should fail"
+            );
+        }
+    }
+
+    #[test]
+    fn okdir_rejects_plus() {
+        assert!(Command::parse(Mode::OkDir, words("rm {} +")).is_err());
+        let c = Command::parse(Mode::OkDir, words("rm {} ;")).unwrap();
+        assert!(c.in_dir() && !c.is_batch());
+    }
+
+    #[test]
+    fn dispatcher_splits_batches_at_the_limit() {
+        let cmd = Command::parse(Mode::Exec, words("true {} +")).unwrap();
+        let (tx, rx) = bounded(16);
+        let per = arg_cost(OsStr::new("/p/aaaa"));
+        let mut d = Dispatcher {
+            commands: vec![cmd.clone()].into(),
+            tx,
+            pending: vec![Pending {
+                paths: Vec::new(),
+                used: cmd.base_cost(),
+                dir: None,
+            }],
+            limit: cmd.base_cost() + 2 * per,
+        };
+        for _ in 0..5 {
+            d.dispatch(std::path::Path::new("/p/aaaa"), None);
+        }
+        d.finish();
+        let sizes: Vec<usize> = rx.iter().map(|j| j.paths.len()).collect();
+        assert_eq!(sizes, [2, 2, 1]);
+    }
+}
@@ -0,0 +1,503 @@
+// SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
+// SPDX-License-Identifier: MIT
+
+//! find-style boolean expressions: predicate leaves joined by `!`/`-not`,
+//! `-a`/`-and` (or plain juxtaposition), `-o`/`-or` and parentheses.
+//!
+//! The arg parser records every predicate and operator as a [`Token`], in
+//! argv order; [`parse`] turns that stream into an [`Expr`] using find's
+//! precedence (`!` binds tightest, then AND, then OR). A command line with no
+//! operator never builds a tree — its predicates are lowered onto the flat
+//! [`Args`](crate::args::Args) fields and keep the hard-wired AND chain in
+//! [`run`](crate::run). [`Matcher`] is the compiled form (globs and regexes
+//! built once) that the walker threads evaluate per entry.
+
+use crate::args;
+use crate::filetype::{self, EntryType};
+use crate::meta::{self, Meta};
+use crate::walk::{Entry, StatAt};
+use crate::{glob, regex};
+use ::regex::bytes::RegexSet;
+use anyhow::{anyhow, Error};
+use globset::GlobSet;
+use std::path::Path;
+
+/// A predicate leaf, still in its parsed (uncompiled) form. Pattern leaves
+/// carry their own case-insensitivity (`-iname` vs `-name`).
+#[derive(Debug, Clone)]
+pub enum Pred {
+    /// `-name`: glob over the file name.
+    Name { pattern: String, case_insensitive: bool },
+    /// `-path`/`-wholename`: glob over the full path.
+    Path { pattern: String, case_insensitive: bool },
+    /// `-regex`: regular expression over the full path.
+    Regex { pattern: String, case_insensitive: bool },
+    /// `-lname`: glob over a symlink's target.
+    Lname { pattern: String, case_insensitive: bool },
+    /// `-type`/`-empty`: any of the listed types (`-type f,d`).
+    Type(Vec<args::FileType>),
+    /// One stat-based predicate (`-size`, `-mtime`, `-perm`, …).
+    Meta(Box<meta::Predicates>),
+    /// `-readable`/`-writable`/`-executable` ([`meta::access`] bits).
+    Access(u8),
+    /// Always true (`-print`, `-true`).
+    True,
+    /// Always false (`-false`).
+    False,
+}
+
+impl Pred {
+    pub fn name(pattern: String, case_insensitive: bool) -> Self {
+        Pred::Name { pattern, case_insensitive }
+    }
+
+    pub fn path(pattern: String, case_insensitive: bool) -> Self {
+        Pred::Path { pattern, case_insensitive }
+    }
+
+    pub fn regex(pattern: String, case_insensitive: bool) -> Self {
+        Pred::Regex { pattern, case_insensitive }
+    }
+
+    pub fn lname(pattern: String, case_insensitive: bool) -> Self {
+        Pred::Lname { pattern, case_insensitive }
+    }
+}
+
+/// How a predicate was written on the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Spelling {
+    /// minifind's own `-n`, `--name`, …
+    Ours,
+    /// find's single-dash `-name`, `-type`, `-size`, …
+    Find,
+}
+
+/// One argv item of an expression, in command-line order.
+#[derive(Debug, Clone)]
+pub enum Token {
+    Pred(Pred, Spelling),
+    Not,
+    And,
+    Or,
+    Open,
+    Close,
+}
+
+impl Token {
+    /// Whether this token needs a real tree: any operator, or `-false`
+    /// (which the flat AND chain has no field for). A command line without
+    /// one is lowered onto the flat filters.
+    pub fn needs_tree(&self) -> bool {
+        !matches!(self, Token::Pred(p, _) if !matches!(p, Pred::False))
+    }
+
+    /// Whether this token ends an operand written the find way — a
+    /// find-spelled predicate or a `)` — so a following `-o` is find's OR
+    /// rather than minifind's `--one-filesystem`.
+    pub fn ends_find_operand(&self) -> bool {
+        matches!(self, Token::Pred(_, Spelling::Find) | Token::Close)
+    }
+}
+
+/// A parsed boolean expression over [`Pred`] leaves.
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Pred(Pred),
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+impl Expr {
+    /// Whether any leaf is a `-type`/`-empty` test; the flat default type set
+    /// then widens to every type so the expression alone decides.
+    pub fn has_type(&self) -> bool {
+        match self {
+            Expr::Pred(p) => matches!(p, Pred::Type(_)),
+            Expr::Not(e) => e.has_type(),
+            Expr::And(v) | Expr::Or(v) => v.iter().any(Expr::has_type),
+        }
+    }
+}
+
+/// Builds an [`Expr`] from `tokens` with find's precedence: `!` > AND
+/// (explicit or implied by juxtaposition) > OR.
+///
+/// # Errors
+///
+/// A dangling operator, unbalanced or empty parentheses, or no operand.
+pub fn parse(tokens: Vec<Token>) -> Result<Expr, Error> {
+    let mut p = Parser { tokens: tokens.into_iter().peekable() };
+    let expr = p.or()?;
+    match p.tokens.next() {
+        None => Ok(expr),
+        Some(Token::Close) => {
+            Err(anyhow!("invalid expression: unmatched ')'"))
+        }
+        // `or` stops only at `)` or the end, but never panic on user input
+        Some(_) => Err(anyhow!("invalid expression: unexpected token")),
+    }
+}
+
+struct Parser {
+    tokens: std::iter::Peekable<std::vec::IntoIter<Token>>,
+}
+
+impl Parser {
+    fn or(&mut self) -> Result<Expr, Error> {
+        let mut terms = vec![self.and("")?];
+        while matches!(self.tokens.peek(), Some(Token::Or)) {
+            self.tokens.next();
+            terms.push(self.and("-o")?);
+        }
+        Ok(flatten(terms, Expr::Or))
+    }
+
+    /// `after` names the operator that demanded this operand (for errors).
+    fn and(&mut self, after: &str) -> Result<Expr, Error> {
+        let mut terms = vec![self.not(after)?];
+        loop {
+            match self.tokens.peek() {
+                Some(Token::And) => {
+                    self.tokens.next();
+                    terms.push(self.not("-a")?);
+                }
+                // juxtaposition is an implicit -a
+                Some(Token::Pred(..) | Token::Not | Token::Open) => {
+                    terms.push(self.not("-a")?);
+                }
+                _ => break,
+            }
+        }
+        Ok(flatten(terms, Expr::And))
+    }
+
+    fn not(&mut self, after: &str) -> Result<Expr, Error> {
+        match self.tokens.next() {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.not("!")?))),
+            Some(Token::Open) => {
+                if matches!(self.tokens.peek(), Some(Token::Close)) {
+                    return Err(anyhow!(
+                        "invalid expression: empty parentheses are not allowed"
+                    ));
+                }
+                let inner = self.or()?;
+                match self.tokens.next() {
+                    Some(Token::Close) => Ok(inner),
+                    _ => Err(anyhow!("invalid expression: unmatched '('")),
+                }
+            }
+            Some(Token::Pred(p, _)) => Ok(Expr::Pred(p)),
+            Some(Token::And) => Err(anyhow!(
+                "invalid expression: binary operator '-a' with nothing before it"
+            )),
+            Some(Token::Or) => Err(anyhow!(
+                "invalid expression: binary operator '-o' with nothing before it"
+            )),
+            Some(Token::Close) | None if after.is_empty() => {
+                Err(anyhow!("invalid expression: expected an operand"))
+            }
+            Some(Token::Close) | None => Err(anyhow!(
+                "invalid expression: expected an operand after '{after}'"
+            )),
+        }
+    }
+}
+
+/// Collapses a single-term AND/OR into the term itself.
+fn flatten(mut terms: Vec<Expr>, ctor: fn(Vec<Expr>) -> Expr) -> Expr {
+    if terms.len() == 1 {
+        terms.pop().unwrap()
+    } else {
+        ctor(terms)
+    }
+}
+
+/// The compiled form of an [`Expr`], evaluated per entry in the walkers.
+pub struct Matcher {
+    root: Node,
+    // union of every Meta leaf's statx mask: one fetch serves all leaves
+    mask: u32,
+}
+
+enum Node {
+    Name(GlobSet),
+    Path(GlobSet),
+    Regex(RegexSet),
+    Lname(GlobSet),
+    Type(filetype::FileType),
+    Meta(meta::Predicates),
+    Access(u8),
+    Const(bool),
+    Not(Box<Node>),
+    And(Vec<Node>),
+    Or(Vec<Node>),
+}
+
+/// Per-walker-thread evaluation state: the run's reference time and the
+/// `-nouser`/`-nogroup` lookup memo.
+pub struct EvalCtx {
+    pub now: i64,
+    #[cfg(unix)]
+    pub nss: meta::NssCache,
+}
+
+impl EvalCtx {
+    pub fn new(now: i64) -> Self {
+        EvalCtx {
+            now,
+            #[cfg(unix)]
+            nss: meta::NssCache::default(),
+        }
+    }
+}
+
+impl Matcher {
+    /// Compiles `expr`; `case_insensitive` (the global `-i`) applies to every
+    /// pattern leaf on top of its own flag.
+    ///
+    /// # Errors
+    ///
+    /// An invalid glob or regex pattern.
+    pub fn compile(
+        expr: &Expr,
+        case_insensitive: bool,
+    ) -> Result<Self, Error> {
+        let root = compile_node(expr, case_insensitive)?;
+        let mut mask = 0;
+        root.visit_meta(&mut |p| mask |= p.mask());
+        Ok(Matcher { root, mask })
+    }
+
+    /// Whether the entry satisfies the expression. The `statx` is fetched at
+    /// most once (and only when a metadata leaf is actually reached);
+    /// unstattable entries fail every metadata leaf, like find.
+    pub fn matches(
+        &self,
+        entry: &Entry,
+        stat: &StatAt,
+        cx: &mut EvalCtx,
+    ) -> bool {
+        let mut probe = Probe { entry, stat, cx, mask: self.mask, meta: None };
+        probe.eval(&self.root)
+    }
+}
+
+fn compile_node(expr: &Expr, ci: bool) -> Result<Node, Error> {
+    let glob = |p: &String, own: bool| {
+        glob::build_glob_set(Some(std::slice::from_ref(p)), own || ci)
+    };
+    Ok(match expr {
+        Expr::Pred(p) => match p {
+            Pred::Name { pattern, case_insensitive } => {
+                Node::Name(glob(pattern, *case_insensitive)?)
+            }
+            Pred::Path { pattern, case_insensitive } => {
+                Node::Path(glob(pattern, *case_insensitive)?)
+            }
+            Pred::Lname { pattern, case_insensitive } => {
+                Node::Lname(glob(pattern, *case_insensitive)?)
+            }
+            Pred::Regex { pattern, case_insensitive } => {
+                Node::Regex(regex::build_regex_set(
+                    Some(std::slice::from_ref(pattern)),
+                    *case_insensitive || ci,
+                )?)
+            }
+            Pred::Type(t) => Node::Type(filetype::FileType::new(t)),
+            Pred::Meta(m) => Node::Meta((**m).clone()),
+            Pred::Access(a) => Node::Access(*a),
+            Pred::True => Node::Const(true),
+            Pred::False => Node::Const(false),
+        },
+        Expr::Not(e) => Node::Not(Box::new(compile_node(e, ci)?)),
+        Expr::And(v) => Node::And(
+            v.iter().map(|e| compile_node(e, ci)).collect::<Result<_, _>>()?,
+        ),
+        Expr::Or(v) => Node::Or(
+            v.iter().map(|e| compile_node(e, ci)).collect::<Result<_, _>>()?,
+        ),
+    })
+}
+
+impl Node {
+    fn visit_meta(&self, f: &mut impl FnMut(&meta::Predicates)) {
+        match self {
+            Node::Meta(p) => f(p),
+            Node::Not(n) => n.visit_meta(f),
+            Node::And(v) | Node::Or(v) => {
+                v.iter().for_each(|n| n.visit_meta(f));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// One entry's evaluation: borrows the entry and lazily caches its `Meta`.
+struct Probe<'a, 'b> {
+    entry: &'a Entry,
+    stat: &'a StatAt<'b>,
+    cx: &'a mut EvalCtx,
+    mask: u32,
+    // None = not fetched yet; Some(None) = fetch failed
+    meta: Option<Option<Meta>>,
+}
+
+impl Probe<'_, '_> {
+    fn meta(&mut self) -> Option<Meta> {
+        *self.meta.get_or_insert_with(|| self.stat.fetch(self.mask).ok())
+    }
+
+    fn eval(&mut self, node: &Node) -> bool {
+        match node {
+            Node::Name(g) => g.is_match(self.entry.file_name()),
+            Node::Path(g) => g.is_match(&self.entry.path),
+            Node::Regex(r) => {
+                r.is_match(&regex::path_to_bytes(&self.entry.path))
+            }
+            Node::Lname(g) => {
+                self.entry.file_type == EntryType::Symlink
+                    && self
+                        .stat
+                        .readlink()
+                        .is_ok_and(|t| g.is_match(Path::new(&t)))
+            }
+            Node::Type(t) => {
+                !t.ignore_filetype_at(self.entry.file_type, self.stat)
+            }
+            Node::Meta(p) => {
+                let Some(m) = self.meta() else {
+                    return false;
+                };
+                #[cfg(unix)]
+                if !p.owner_matches(&m, &mut self.cx.nss) {
+                    return false;
+                }
+                p.matches(&m, self.cx.now)
+            }
+            Node::Access(a) => self.stat.access(*a),
+            Node::Const(b) => *b,
+            Node::Not(n) => !self.eval(n),
+            Node::And(v) => v.iter().all(|n| self.eval(n)),
+            Node::Or(v) => v.iter().any(|n| self.eval(n)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(p: &str) -> Token {
+        Token::Pred(
+            Pred::Name { pattern: p.to_string(), case_insensitive: false },
+            Spelling::Find,
+        )
+    }
+
+    /// Renders an `Expr` as a compact s-expression for structural asserts.
+    fn show(e: &Expr) -> String {
+        match e {
+            Expr::Pred(Pred::Name { pattern, .. }) => pattern.clone(),
+            Expr::Pred(p) => format!("{p:?}"),
+            Expr::Not(e) => format!("(! {})", show(e)),
+            Expr::And(v) => format!(
+                "(and {})",
+                v.iter().map(show).collect::<Vec<_>>().join(" ")
+            ),
+            Expr::Or(v) => format!(
+                "(or {})",
+                v.iter().map(show).collect::<Vec<_>>().join(" ")
+            ),
+        }
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        // a b -o c  ==  (a AND b) OR c
+        let e = parse(vec![name("a"), name("b"), Token::Or, name("c")]);
+        assert_eq!(show(&e.unwrap()), "(or (and a b) c)");
+    }
+
+    #[test]
+    fn not_binds_tightest() {
+        // ! a -a b  ==  (NOT a) AND b
+        let e = parse(vec![Token::Not, name("a"), Token::And, name("b")]);
+        assert_eq!(show(&e.unwrap()), "(and (! a) b)");
+    }
+
+    #[test]
+    fn parentheses_override_precedence() {
+        // ( a -o b ) ! c
+        let e = parse(vec![
+            Token::Open,
+            name("a"),
+            Token::Or,
+            name("b"),
+            Token::Close,
+            Token::Not,
+            name("c"),
+        ]);
+        assert_eq!(show(&e.unwrap()), "(and (or a b) (! c))");
+    }
+
+    #[test]
+    fn double_negation_nests() {
+        let e = parse(vec![Token::Not, Token::Not, name("a")]);
+        assert_eq!(show(&e.unwrap()), "(! (! a))");
+    }
+
+    #[test]
+    fn malformed_expressions_error() {
+        assert!(parse(vec![Token::Or, name("a")]).is_err());
+        assert!(parse(vec![name("a"), Token::Or]).is_err());
+        assert!(parse(vec![name("a"), Token::And]).is_err());
+        assert!(parse(vec![Token::Not]).is_err());
+        assert!(parse(vec![Token::Open, name("a")]).is_err());
+        assert!(parse(vec![name("a"), Token::Close]).is_err());
+        assert!(parse(vec![Token::Open, Token::Close]).is_err());
+    }
+
+    #[test]
+    fn has_type_sees_nested_leaves() {
+        let e = parse(vec![
+            name("a"),
+            Token::Or,
+            Token::Not,
+            Token::Pred(
+                Pred::Type(vec![args::FileType::File]),
+                Spelling::Ours,
+            ),
+        ])
+        .unwrap();
+        assert!(e.has_type());
+        assert!(!parse(vec![name("a")]).unwrap().has_type());
+    }
+
+    #[test]
+    fn compile_unions_meta_masks() {
+        let size = meta::Predicates {
+            size: Some(meta::SizePred::parse("+1k").unwrap()),
+            ..Default::default()
+        };
+        let uid = meta::Predicates {
+            uid: Some(meta::IdPred::exact(0)),
+            ..Default::default()
+        };
+        let e = parse(vec![
+            Token::Pred(Pred::Meta(Box::new(size)), Spelling::Find),
+            Token::Or,
+            Token::Pred(Pred::Meta(Box::new(uid)), Spelling::Find),
+        ])
+        .unwrap();
+        let m = Matcher::compile(&e, false).unwrap();
+        assert_eq!(m.mask, meta::mask::SIZE | meta::mask::UID);
+    }
+
+    #[test]
+    fn compile_rejects_bad_pattern() {
+        let e = parse(vec![name("[oops")]).unwrap();
+        assert!(Matcher::compile(&e, false).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 // SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
 // SPDX-License-Identifier: MIT
 
+use crate::walk::StatAt;
 use std::path::Path;
 
 // Each selectable find(1) -type maps to one bit in a u8 mask, so
@@ -26,6 +27,47 @@ pub enum EntryType {
     Socket,
 }
 
+impl EntryType {
+    /// find's `-type` letter for this type (`%y` in `--printf`).
+    pub fn type_char(self) -> u8 {
+        match self {
+            EntryType::File => b'f',
+            EntryType::Dir => b'd',
+            EntryType::Symlink => b'l',
+            EntryType::BlockDevice => b'b',
+            EntryType::CharDevice => b'c',
+            EntryType::Fifo => b'p',
+            EntryType::Socket => b's',
+        }
+    }
+
+    /// Classifies the `S_IFMT` bits of a full `st_mode`.
+    #[cfg(unix)]
+    // mode_t is u32 on Linux but u16 on macOS/BSD
+    #[allow(clippy::unnecessary_cast)]
+    pub fn from_mode(mode: u32) -> Option<Self> {
+        let fmt = mode & libc::S_IFMT as u32;
+        let ty = if fmt == libc::S_IFREG as u32 {
+            EntryType::File
+        } else if fmt == libc::S_IFDIR as u32 {
+            EntryType::Dir
+        } else if fmt == libc::S_IFLNK as u32 {
+            EntryType::Symlink
+        } else if fmt == libc::S_IFBLK as u32 {
+            EntryType::BlockDevice
+        } else if fmt == libc::S_IFCHR as u32 {
+            EntryType::CharDevice
+        } else if fmt == libc::S_IFIFO as u32 {
+            EntryType::Fifo
+        } else if fmt == libc::S_IFSOCK as u32 {
+            EntryType::Socket
+        } else {
+            return None;
+        };
+        Some(ty)
+    }
+}
+
 #[derive(Default, Copy, Clone)]
 pub struct FileType {
     selected: u8,
@@ -70,6 +112,16 @@ impl FileType {
         self.empty && !Self::is_empty(path, ty == EntryType::Dir)
     }
 
+    /// [`ignore_filetype`](Self::ignore_filetype) with the emptiness
+    /// checked through the walker's [`StatAt`] rather than by path.
+    #[inline]
+    pub fn ignore_filetype_at(self, ty: EntryType, stat: &StatAt) -> bool {
+        if Self::type_bit(ty) & self.selected == 0 {
+            return true;
+        }
+        self.empty && !stat.is_empty(ty == EntryType::Dir)
+    }
+
     #[inline]
     fn type_bit(ty: EntryType) -> u8 {
         match ty {
@@ -106,6 +158,15 @@ mod tests {
         FileType::new(types)
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn entry_type_from_mode_and_char() {
+        let dir = EntryType::from_mode(0o040_755).unwrap();
+        assert_eq!(dir, EntryType::Dir);
+        assert_eq!(dir.type_char(), b'd');
+        assert_eq!(EntryType::from_mode(0o644), None);
+    }
+
     #[test]
     fn new_empty_slice_selects_nothing() {
         let f = ft(&[]);
@@ -9,13 +9,28 @@ use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
 pub fn build_glob_set(
     patterns: Option<&[String]>,
     case_insensitive: bool,
+) -> Result<GlobSet, Error> {
+    build_mixed_glob_set(patterns, None, case_insensitive)
+}
+
+/// Builds one `GlobSet` from `patterns` plus `ipatterns` (the `-iname`-style
+/// ones), which match case-insensitively regardless of `case_insensitive`.
+pub fn build_mixed_glob_set(
+    patterns: Option<&[String]>,
+    ipatterns: Option<&[String]>,
+    case_insensitive: bool,
 ) -> Result<GlobSet, Error> {
     let mut builder = GlobSetBuilder::new();
 
-    for p in patterns.into_iter().flatten() {
+    let tagged = patterns
+        .into_iter()
+        .flatten()
+        .map(|p| (p, case_insensitive))
+        .chain(ipatterns.into_iter().flatten().map(|p| (p, true)));
+    for (p, ci) in tagged {
         builder.add(
             GlobBuilder::new(p)
-                .case_insensitive(case_insensitive)
+                .case_insensitive(ci)
                 .build()
                 .context("Unable to parse and build glob pattern")?,
         );
@@ -74,6 +89,18 @@ mod tests {
         assert!(gs.is_match("main.RS"));
     }
 
+    #[test]
+    fn test_build_mixed_glob_set_case_per_pattern() {
+        let patterns = vec!["*.RS".to_string()];
+        let ipatterns = vec!["*.JPG".to_string()];
+        let gs =
+            build_mixed_glob_set(Some(&patterns), Some(&ipatterns), false)
+                .unwrap();
+        assert!(!gs.is_match("main.rs"));
+        assert!(gs.is_match("main.RS"));
+        assert!(gs.is_match("photo.jpg"));
+    }
+
     #[test]
     fn test_build_glob_set_invalid_pattern() {
         // Unclosed bracket is an invalid glob pattern
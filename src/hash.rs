@@ -0,0 +1,189 @@
+// SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
+// SPDX-License-Identifier: MIT
+
+//! `--hash sha256|blake3|xxh3` — a digest line per matched regular file.
+//!
+//! Files are read in the walker threads, opened with `openat` on the
+//! parent directory fd the walk already holds, and each line is written the
+//! way `sha256sum`/`b3sum` write theirs (`HASH  PATH`), so the output checks
+//! back with `sha256sum -c`. Like those tools, a path holding a backslash or
+//! a line break gets them escaped and the line a leading `\`; under `--null`
+//! lines end in NUL and paths go out raw, as with `sha256sum -z`.
+
+use crate::regex::path_to_bytes;
+use anyhow::{anyhow, Error};
+use sha2::Digest;
+use std::io::{self, Read};
+use std::path::Path;
+use xxhash_rust::xxh3::Xxh3;
+
+/// Which digest `--hash` computes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algo {
+    /// SHA-256, as `sha256sum`.
+    Sha256,
+    /// BLAKE3, as `b3sum`.
+    Blake3,
+    /// 64-bit XXH3, as `xxhsum -H3`; fast, but not for tamper checks.
+    Xxh3,
+}
+
+impl Algo {
+    /// Parses a `--hash` value.
+    ///
+    /// # Errors
+    ///
+    /// An unknown algorithm.
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        match s {
+            "sha256" => Ok(Algo::Sha256),
+            "blake3" => Ok(Algo::Blake3),
+            "xxh3" => Ok(Algo::Xxh3),
+            _ => Err(anyhow!(
+                "invalid --hash '{s}' (expected sha256, blake3 or xxh3)"
+            )),
+        }
+    }
+}
+
+/// A worker's hasher, with its read buffer reused across files.
+pub struct Hasher {
+    algo: Algo,
+    buf: Vec<u8>,
+}
+
+impl Hasher {
+    /// A hasher computing `algo`.
+    pub fn new(algo: Algo) -> Self {
+        Hasher { algo, buf: vec![0; 128 * 1024] }
+    }
+
+    /// Appends `HASH  PATH` for `file`, read to its end, to `out`, ended by
+    /// `separator`.
+    ///
+    /// # Errors
+    ///
+    /// A read failure; `out` is left untouched.
+    pub fn render(
+        &mut self,
+        file: impl Read,
+        path: &Path,
+        separator: u8,
+        out: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        let digest = self.digest(file)?;
+        let path = path_to_bytes(path);
+        let escape = separator == b'\n'
+            && path.iter().any(|b| matches!(b, b'\\' | b'\n' | b'\r'));
+        if escape {
+            out.push(b'\\');
+        }
+        for b in digest {
+            out.extend_from_slice(&hex(b));
+        }
+        out.extend_from_slice(b"  ");
+        if escape {
+            for &b in path.iter() {
+                match b {
+                    b'\\' => out.extend_from_slice(b"\\\\"),
+                    b'\n' => out.extend_from_slice(b"\\n"),
+                    b'\r' => out.extend_from_slice(b"\\r"),
+                    _ => out.push(b),
+                }
+            }
+        } else {
+            out.extend_from_slice(&path);
+        }
+        out.push(separator);
+        Ok(())
+    }
+
+    fn digest(&mut self, file: impl Read) -> io::Result<Vec<u8>> {
+        Ok(match self.algo {
+            Algo::Sha256 => {
+                let mut h = sha2::Sha256::new();
+                feed(file, &mut self.buf, |b| h.update(b))?;
+                h.finalize().to_vec()
+            }
+            Algo::Blake3 => {
+                let mut h = blake3::Hasher::new();
+                feed(file, &mut self.buf, |b| {
+                    h.update(b);
+                })?;
+                h.finalize().as_bytes().to_vec()
+            }
+            Algo::Xxh3 => {
+                let mut h = Xxh3::new();
+                feed(file, &mut self.buf, |b| h.update(b))?;
+                // canonical (big-endian) form, as xxhsum prints it
+                h.digest().to_be_bytes().to_vec()
+            }
+        })
+    }
+}
+
+// reads `file` to its end through `buf`
+pub(crate) fn feed(
+    mut file: impl Read,
+    buf: &mut [u8],
+    mut update: impl FnMut(&[u8]),
+) -> io::Result<()> {
+    loop {
+        match file.read(buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => update(&buf[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn hex(b: u8) -> [u8; 2] {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    [DIGITS[usize::from(b >> 4)], DIGITS[usize::from(b & 0xf)]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(algo: Algo, data: &[u8], path: &str, separator: u8) -> String {
+        let mut out = Vec::new();
+        Hasher::new(algo)
+            .render(data, Path::new(path), separator, &mut out)
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn digests_match_the_reference_tools() {
+        assert_eq!(
+            line(Algo::Sha256, b"abc", "/a", b'\n'),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  \
+             /a\n"
+        );
+        assert_eq!(
+            line(Algo::Blake3, b"abc", "/a", b'\n'),
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85  \
+             /a\n"
+        );
+        assert_eq!(
+            line(Algo::Xxh3, b"", "/a", b'\n'),
+            "2d06800538d394c2  /a\n"
+        );
+    }
+
+    #[test]
+    fn awkward_paths_are_escaped_like_sha256sum() {
+        let got = line(Algo::Xxh3, b"", "/a\\b\nc", b'\n');
+        assert_eq!(got, "\\2d06800538d394c2  /a\\\\b\\nc\n");
+        let raw = line(Algo::Xxh3, b"", "/a\nb", b'\0');
+        assert_eq!(raw, "2d06800538d394c2  /a\nb\0");
+    }
+
+    #[test]
+    fn algo_parses_and_rejects_unknown() {
+        assert_eq!(Algo::parse("blake3").unwrap(), Algo::Blake3);
+        assert!(Algo::parse("md5").is_err());
+    }
+}
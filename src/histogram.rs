@@ -0,0 +1,364 @@
+// SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
+// SPDX-License-Identifier: MIT
+
+//! `--histogram ext|size|age|owner|type` — matches tabulated into buckets.
+//!
+//! Each bucket gets the number of matches that fell into it and their total
+//! apparent size, printed as `COUNT<TAB>BYTES<TAB>BUCKET` once the walk is
+//! done. Like `--count`, every worker fills its own [`Local`] table, merged
+//! into the shared [`Table`] when the worker ends, so the walk never
+//! contends on a lock. Size and age buckets are listed smallest/youngest
+//! first and types in `--count=type` order; extensions and owners by total
+//! size, largest first.
+
+use crate::count;
+use crate::meta::{self, Meta};
+use crate::printf::Names;
+use crate::regex::path_to_bytes;
+use crate::walk::Entry;
+use anyhow::{anyhow, Error};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// What `--histogram` buckets by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum By {
+    /// The name's extension (after the last `.`), as-is.
+    Ext,
+    /// Apparent size, by powers of ten from 1 KiB.
+    Size,
+    /// Time since the last modification.
+    Age,
+    /// Owning user.
+    Owner,
+    /// Entry type.
+    Type,
+}
+
+impl By {
+    /// Parses a `--histogram` value.
+    ///
+    /// # Errors
+    ///
+    /// An unknown bucketing.
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        match s {
+            "ext" => Ok(By::Ext),
+            "size" => Ok(By::Size),
+            "age" => Ok(By::Age),
+            "owner" => Ok(By::Owner),
+            "type" => Ok(By::Type),
+            _ => Err(anyhow!(
+                "invalid --histogram '{s}' (expected ext, size, age, owner \
+                 or type)"
+            )),
+        }
+    }
+
+    /// The `stat` fields bucketing one match needs.
+    pub fn mask(self) -> u32 {
+        meta::mask::SIZE
+            | match self {
+                By::Age => meta::mask::MTIME,
+                By::Owner => meta::mask::UID,
+                By::Ext | By::Size | By::Type => 0,
+            }
+    }
+}
+
+/// Upper bounds of the size buckets (exclusive), after the empty one.
+const SIZES: [(u64, &str); 8] = [
+    (1 << 10, "< 1K"),
+    (10 << 10, "< 10K"),
+    (100 << 10, "< 100K"),
+    (1 << 20, "< 1M"),
+    (10 << 20, "< 10M"),
+    (100 << 20, "< 100M"),
+    (1 << 30, "< 1G"),
+    (10 << 30, "< 10G"),
+];
+
+/// Upper bounds of the age buckets in seconds (exclusive).
+const AGES: [(i64, &str); 5] = [
+    (3600, "< 1 hour"),
+    (meta::DAY, "< 1 day"),
+    (7 * meta::DAY, "< 1 week"),
+    (30 * meta::DAY, "< 30 days"),
+    (365 * meta::DAY, "< 1 year"),
+];
+
+// a bucket: its place in the listing, and a name for ext/owner/type
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Bucket {
+    Ranked(u8),
+    Ext(Vec<u8>),
+    Owner(u32),
+}
+
+fn bucket(by: By, entry: &Entry, m: &Meta, now: i64) -> Bucket {
+    match by {
+        By::Ext => {
+            let name = path_to_bytes(Path::new(entry.file_name()));
+            // a leading dot (`.bashrc`) starts a name, not an extension
+            let ext = match name.iter().rposition(|&b| b == b'.') {
+                Some(i) if i > 0 => name[i + 1..].to_vec(),
+                _ => Vec::new(),
+            };
+            Bucket::Ext(ext)
+        }
+        By::Size => Bucket::Ranked(match m.size {
+            0 => 0,
+            s => SIZES
+                .iter()
+                .position(|&(max, _)| s < max)
+                .map_or(SIZES.len() as u8 + 1, |i| i as u8 + 1),
+        }),
+        By::Age => Bucket::Ranked(match now - m.mtime {
+            age if age < 0 => 0,
+            age => AGES
+                .iter()
+                .position(|&(max, _)| age < max)
+                .map_or(AGES.len() as u8 + 1, |i| i as u8 + 1),
+        }),
+        By::Owner => Bucket::Owner(m.uid),
+        By::Type => Bucket::Ranked(count::slot(entry.file_type) as u8),
+    }
+}
+
+/// Count and total size of one bucket.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Cell {
+    count: u64,
+    bytes: u64,
+}
+
+/// Matches per bucket.
+#[derive(Debug)]
+pub struct Table {
+    by: By,
+    now: i64,
+    cells: HashMap<Bucket, Cell>,
+}
+
+impl Table {
+    /// An empty table bucketing by `by`, with ages taken against `now`.
+    pub fn new(by: By, now: i64) -> Self {
+        Table { by, now, cells: HashMap::new() }
+    }
+
+    /// Adds one match, `m` holding at least [`By::mask`].
+    pub fn add(&mut self, entry: &Entry, m: &Meta) {
+        let cell =
+            self.cells.entry(bucket(self.by, entry, m, self.now)).or_default();
+        cell.count += 1;
+        cell.bytes = cell.bytes.saturating_add(m.size);
+    }
+
+    fn merge(&mut self, other: &mut Table) {
+        for (b, c) in other.cells.drain() {
+            let cell = self.cells.entry(b).or_default();
+            cell.count += c.count;
+            cell.bytes = cell.bytes.saturating_add(c.bytes);
+        }
+    }
+
+    /// Appends the table to `out`, one `COUNT<TAB>BYTES<TAB>BUCKET` line per
+    /// bucket ended by `separator`.
+    pub fn render(&self, names: &mut Names, separator: u8, out: &mut Vec<u8>) {
+        let mut rows: Vec<(&Bucket, &Cell)> = self.cells.iter().collect();
+        rows.sort_by(|a, b| match (a.0, b.0) {
+            (Bucket::Ranked(x), Bucket::Ranked(y)) => x.cmp(y),
+            _ => b.1.bytes.cmp(&a.1.bytes).then_with(|| a.0.cmp_name(b.0)),
+        });
+        for (b, c) in rows {
+            let _ = write!(out, "{}\t{}\t", c.count, c.bytes);
+            self.label(b, names, out);
+            out.push(separator);
+        }
+    }
+
+    fn label(&self, b: &Bucket, names: &mut Names, out: &mut Vec<u8>) {
+        match (self.by, b) {
+            (_, Bucket::Ext(e)) if e.is_empty() => {
+                out.extend_from_slice(b"(none)");
+            }
+            (_, Bucket::Ext(e)) => out.extend_from_slice(e),
+            (_, Bucket::Owner(uid)) => {
+                #[cfg(unix)]
+                if let Some(n) = names.user_name(*uid) {
+                    out.extend_from_slice(n.as_bytes());
+                    return;
+                }
+                #[cfg(not(unix))]
+                let _ = names;
+                let _ = write!(out, "{uid}");
+            }
+            (By::Size, &Bucket::Ranked(r)) => {
+                let text = match r {
+                    0 => "0",
+                    r => {
+                        SIZES.get(usize::from(r) - 1).map_or(">= 10G", |s| s.1)
+                    }
+                };
+                out.extend_from_slice(text.as_bytes());
+            }
+            (By::Age, &Bucket::Ranked(r)) => {
+                let text = match r {
+                    0 => "future",
+                    r => AGES
+                        .get(usize::from(r) - 1)
+                        .map_or(">= 1 year", |a| a.1),
+                };
+                out.extend_from_slice(text.as_bytes());
+            }
+            (_, &Bucket::Ranked(r)) => {
+                out.push(count::TYPES[usize::from(r)].type_char());
+            }
+        }
+    }
+}
+
+impl Bucket {
+    // ext/owner ties: by name, so the listing is stable
+    fn cmp_name(&self, other: &Bucket) -> std::cmp::Ordering {
+        match (self, other) {
+            (Bucket::Ext(a), Bucket::Ext(b)) => a.cmp(b),
+            (Bucket::Owner(a), Bucket::Owner(b)) => a.cmp(b),
+            _ => std::cmp::Ordering::Equal,
+        }
+    }
+}
+
+/// One worker's table, merged into `into` when the worker ends.
+pub struct Local<'a> {
+    table: Table,
+    into: &'a Mutex<Table>,
+}
+
+impl<'a> Local<'a> {
+    /// An empty table shaped like `into`.
+    pub fn new(into: &'a Mutex<Table>) -> Self {
+        let (by, now) = into.lock().map_or((By::Type, 0), |t| (t.by, t.now));
+        Local { table: Table::new(by, now), into }
+    }
+
+    /// Adds one match, `m` holding at least [`By::mask`].
+    #[inline]
+    pub fn add(&mut self, entry: &Entry, m: &Meta) {
+        self.table.add(entry, m);
+    }
+}
+
+impl Drop for Local<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut shared) = self.into.lock() {
+            shared.merge(&mut self.table);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filetype::EntryType;
+    use std::path::PathBuf;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn entry(name: &str, file_type: EntryType) -> Entry {
+        Entry {
+            path: PathBuf::from(name),
+            file_type,
+            depth: 1,
+            order: None,
+            subtree: None,
+            root: 0,
+        }
+    }
+
+    fn meta(size: u64, mtime: i64) -> Meta {
+        Meta {
+            size,
+            mtime,
+            ctime: 0,
+            atime: 0,
+            mode: 0,
+            uid: 4_000_000_000,
+            gid: 0,
+            nlink: 1,
+            ino: 0,
+            blocks: 0,
+            rdev: (0, 0),
+            dev: 0,
+        }
+    }
+
+    fn table(by: By, rows: &[(&str, EntryType, u64, i64)]) -> String {
+        let shared = Mutex::new(Table::new(by, NOW));
+        let (mut a, mut b) = (Local::new(&shared), Local::new(&shared));
+        for (i, &(name, ty, size, mtime)) in rows.iter().enumerate() {
+            let l = if i % 2 == 0 { &mut a } else { &mut b };
+            l.add(&entry(name, ty), &meta(size, mtime));
+        }
+        drop((a, b));
+        let mut out = Vec::new();
+        let t = shared.into_inner().unwrap();
+        t.render(&mut Names::default(), b'\n', &mut out);
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn extensions_list_largest_first() {
+        let f = EntryType::File;
+        let got = table(
+            By::Ext,
+            &[
+                ("/r/a.rs", f, 10, 0),
+                ("/r/b.log", f, 500, 0),
+                ("/r/c.rs", f, 30, 0),
+                ("/r/.bashrc", f, 7, 0),
+                ("/r/Makefile", f, 1, 0),
+            ],
+        );
+        assert_eq!(got, "1\t500\tlog\n2\t40\trs\n2\t8\t(none)\n");
+    }
+
+    #[test]
+    fn sizes_and_ages_list_in_bucket_order() {
+        let f = EntryType::File;
+        let rows = [
+            ("/a", f, 2 << 20, NOW - 2 * meta::DAY),
+            ("/b", f, 0, NOW - 60),
+            ("/c", f, 512, NOW + 60),
+            ("/d", f, 20 << 30, NOW - 400 * meta::DAY),
+        ];
+        assert_eq!(
+            table(By::Size, &rows),
+            format!(
+                "1\t0\t0\n1\t512\t< 1K\n1\t{}\t< 10M\n1\t{}\t>= 10G\n",
+                2 << 20,
+                20_u64 << 30
+            )
+        );
+        let ages = table(By::Age, &rows);
+        let labels: Vec<&str> =
+            ages.lines().map(|l| l.rsplit('\t').next().unwrap()).collect();
+        assert_eq!(labels, ["future", "< 1 hour", "< 1 week", ">= 1 year"]);
+    }
+
+    #[test]
+    fn types_and_unknown_owners() {
+        let rows =
+            [("/a", EntryType::Dir, 4096, 0), ("/b", EntryType::File, 1, 0)];
+        assert_eq!(table(By::Type, &rows), "1\t1\tf\n1\t4096\td\n");
+        assert_eq!(table(By::Owner, &rows), "2\t4097\t4000000000\n");
+    }
+
+    #[test]
+    fn by_parses_and_rejects_unknown() {
+        assert_eq!(By::parse("owner").unwrap(), By::Owner);
+        assert!(By::parse("group").is_err());
+    }
+}
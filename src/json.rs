@@ -0,0 +1,246 @@
+// SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
+// SPDX-License-Identifier: MIT
+
+//! `--json[=FIELDS]` — one JSON object per matched entry (JSON Lines).
+//!
+//! The field list is parsed once at arg-parse time into a [`Format`]; like
+//! [`crate::printf`], entries are rendered in the walker threads and only the
+//! `stat` fields the list names are fetched ([`Format::mask`]), so the default
+//! `path,name,depth,type` object never stats at all.
+
+use crate::filetype::EntryType;
+use crate::meta::{self, Meta};
+use crate::walk::{Entry, StatAt};
+use anyhow::{anyhow, Error};
+use std::io::Write;
+use std::path::Path;
+
+/// Fields emitted when `--json` is given without a list.
+pub const DEFAULT_FIELDS: &str = "path,name,depth,type";
+
+/// A parsed `--json` field list, in output order.
+#[derive(Debug, Clone)]
+pub struct Format {
+    fields: Vec<Field>,
+    mask: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Path,
+    Name,
+    Depth,
+    Type,
+    Size,
+    Mtime,
+    Ctime,
+    Atime,
+    Mode,
+    Uid,
+    Gid,
+    Nlink,
+    Ino,
+}
+
+impl Field {
+    fn parse(s: &str) -> Option<Self> {
+        let f = match s {
+            "path" => Field::Path,
+            "name" => Field::Name,
+            "depth" => Field::Depth,
+            "type" => Field::Type,
+            "size" => Field::Size,
+            "mtime" => Field::Mtime,
+            "ctime" => Field::Ctime,
+            "atime" => Field::Atime,
+            "mode" => Field::Mode,
+            "uid" => Field::Uid,
+            "gid" => Field::Gid,
+            "nlink" => Field::Nlink,
+            "ino" => Field::Ino,
+            _ => return None,
+        };
+        Some(f)
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Field::Path => "path",
+            Field::Name => "name",
+            Field::Depth => "depth",
+            Field::Type => "type",
+            Field::Size => "size",
+            Field::Mtime => "mtime",
+            Field::Ctime => "ctime",
+            Field::Atime => "atime",
+            Field::Mode => "mode",
+            Field::Uid => "uid",
+            Field::Gid => "gid",
+            Field::Nlink => "nlink",
+            Field::Ino => "ino",
+        }
+    }
+
+    fn mask(self) -> u32 {
+        match self {
+            Field::Size => meta::mask::SIZE,
+            Field::Mtime => meta::mask::MTIME,
+            Field::Ctime => meta::mask::CTIME,
+            Field::Atime => meta::mask::ATIME,
+            Field::Mode => meta::mask::MODE,
+            Field::Uid => meta::mask::UID,
+            Field::Gid => meta::mask::GID,
+            Field::Nlink => meta::mask::NLINK,
+            Field::Ino => meta::mask::INO,
+            Field::Path | Field::Name | Field::Depth | Field::Type => 0,
+        }
+    }
+}
+
+impl Format {
+    /// Parses a comma-separated field list (see [`DEFAULT_FIELDS`]).
+    ///
+    /// # Errors
+    ///
+    /// An empty list, or an unknown or repeated field name.
+    pub fn parse(list: &str) -> Result<Self, Error> {
+        let mut fields = Vec::new();
+        for name in list.split(',').map(str::trim) {
+            let f = Field::parse(name).ok_or_else(|| {
+                anyhow!(
+                    "invalid --json field '{name}' (expected path, name, \
+                     depth, type, size, mtime, ctime, atime, mode, uid, gid, \
+                     nlink or ino)"
+                )
+            })?;
+            if fields.contains(&f) {
+                return Err(anyhow!("duplicate --json field '{name}'"));
+            }
+            fields.push(f);
+        }
+        let mask = fields.iter().fold(0, |a, f| a | f.mask());
+        Ok(Format { fields, mask })
+    }
+
+    /// The union of the `stat` fields the list reads; 0 = no `stat`.
+    pub fn mask(&self) -> u32 {
+        self.mask
+    }
+
+    /// Appends `entry` as one JSON object plus a newline to `out`.
+    ///
+    /// # Errors
+    ///
+    /// The failed `stat`, leaving `out` untouched.
+    pub fn render(
+        &self,
+        entry: &Entry,
+        stat: &StatAt,
+        out: &mut Vec<u8>,
+    ) -> std::io::Result<()> {
+        let meta =
+            if self.mask == 0 { None } else { Some(stat.fetch(self.mask)?) };
+        out.push(b'{');
+        for (i, &f) in self.fields.iter().enumerate() {
+            if i > 0 {
+                out.push(b',');
+            }
+            let _ = write!(out, "\"{}\":", f.key());
+            render_field(f, entry, meta.as_ref(), out);
+        }
+        out.extend_from_slice(b"}\n");
+        Ok(())
+    }
+}
+
+fn render_field(
+    f: Field,
+    entry: &Entry,
+    meta: Option<&Meta>,
+    out: &mut Vec<u8>,
+) {
+    // stat fields always have `meta` (their bits are in the fetched mask)
+    let m = || meta.expect("stat field requested by the field mask");
+    let _ = match f {
+        Field::Path => return write_str(out, &entry.path),
+        Field::Name => return write_str(out, Path::new(entry.file_name())),
+        Field::Depth => write!(out, "{}", entry.depth),
+        Field::Type => write!(out, "\"{}\"", type_name(entry.file_type)),
+        Field::Size => write!(out, "{}", m().size),
+        Field::Mtime => write!(out, "{}", m().mtime),
+        Field::Ctime => write!(out, "{}", m().ctime),
+        Field::Atime => write!(out, "{}", m().atime),
+        // octal string, as `ls`/`stat` users read permission bits
+        Field::Mode => write!(out, "\"{:04o}\"", m().mode & 0o7777),
+        Field::Uid => write!(out, "{}", m().uid),
+        Field::Gid => write!(out, "{}", m().gid),
+        Field::Nlink => write!(out, "{}", m().nlink),
+        Field::Ino => write!(out, "{}", m().ino),
+    };
+}
+
+fn type_name(ty: EntryType) -> &'static str {
+    match ty {
+        EntryType::File => "file",
+        EntryType::Dir => "directory",
+        EntryType::Symlink => "symlink",
+        EntryType::BlockDevice => "block-device",
+        EntryType::CharDevice => "char-device",
+        EntryType::Fifo => "pipe",
+        EntryType::Socket => "socket",
+    }
+}
+
+/// Writes `path` as a JSON string. JSON strings are Unicode, so bytes that
+/// are not valid UTF-8 become U+FFFD.
+fn write_str(out: &mut Vec<u8>, path: &Path) {
+    out.push(b'"');
+    for c in path.to_string_lossy().chars() {
+        match c {
+            '"' => out.extend_from_slice(b"\\\""),
+            '\\' => out.extend_from_slice(b"\\\\"),
+            '\n' => out.extend_from_slice(b"\\n"),
+            '\r' => out.extend_from_slice(b"\\r"),
+            '\t' => out.extend_from_slice(b"\\t"),
+            c if u32::from(c) < 0x20 || c == '\u{7f}' => {
+                let _ = write!(out, "\\u{:04x}", u32::from(c));
+            }
+            c => {
+                let mut buf = [0u8; 4];
+                out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+        }
+    }
+    out.push(b'"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_fields_need_no_stat() {
+        assert_eq!(Format::parse(DEFAULT_FIELDS).unwrap().mask(), 0);
+    }
+
+    #[test]
+    fn field_list_selects_mask_bits() {
+        let f = Format::parse("path, size,mtime").unwrap();
+        assert_eq!(f.mask(), meta::mask::SIZE | meta::mask::MTIME);
+        assert_eq!(f.fields, [Field::Path, Field::Size, Field::Mtime]);
+    }
+
+    #[test]
+    fn bad_field_lists_error() {
+        for bad in ["", "path,bogus", "size,size"] {
+            assert!(Format::parse(bad).is_err(), "{bad:?} should fail");
+        }
+    }
+
+    #[test]
+    fn strings_are_escaped() {
+        let mut out = Vec::new();
+        write_str(&mut out, Path::new("a\"b\\c\nd\u{1}"));
+        assert_eq!(out, br#""a\"b\\c\nd\u0001""#);
+    }
+}
@@ -7,7 +7,9 @@
 //! traversal pipeline can be driven through a public API and exercised by
 //! integration tests. [`run`] spawns a dedicated output thread, walks every
 //! requested path in parallel via [`walk::walk_parallel`], filters entries by
-//! file type / glob / regex, and writes matched paths to a caller-supplied sink.
+//! file type / glob / regex (and any [`expr`] boolean expression), and writes
+//! matched paths — or [`printf`]-formatted lines — to a caller-supplied sink,
+//! handing them to any [`exec`] commands on the way.
 //!
 //! # Examples
 //!
@@ -27,18 +29,39 @@ use std::num::NonZeroU32;
 #[cfg(unix)]
 use std::os::unix::ffi::OsStrExt;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::thread;
+use std::time::Instant;
 
 pub mod args;
+pub mod color;
+pub mod content;
+pub mod count;
+pub mod duplicates;
+pub mod exec;
+pub mod expr;
 pub mod filetype;
 pub mod glob;
+pub mod hash;
+pub mod histogram;
 pub mod interrupt;
+pub mod json;
+pub mod ls;
 pub mod meta;
+pub mod mime;
+pub mod pathstyle;
+pub mod printf;
+pub mod quote;
 pub mod ratelimit;
 pub mod regex;
+pub mod report;
 pub mod sched;
+pub mod sort;
+pub mod stats;
+pub mod summarize;
+pub mod top;
+pub mod tree;
 pub mod walk;
 
 use args::Args;
@@ -54,23 +77,180 @@ const BATCH_SIZE: usize = 256;
 /// insensitive to it across ~2..16.
 const CHAN_MULT: usize = 4;
 
-/// Per-walker-thread accumulator; sends [`Entry`] items in batches and
+/// How a completed run should exit. Like find, any error met on the way (an
+/// unreadable directory, a vanished entry, a failed action) makes it non-zero.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Status {
+    /// An `-exec … +` batch failed, or a command could not be started.
+    pub exec_failed: bool,
+    /// A `--delete` could not remove some entry.
+    pub delete_failed: bool,
+    /// Errors reported on stderr (or counted under `--quiet-errors`).
+    pub errors: usize,
+}
+
+impl Status {
+    /// Whether the process should exit 0.
+    pub fn success(self) -> bool {
+        !self.exec_failed && !self.delete_failed && self.errors == 0
+    }
+}
+
+/// A matched entry on its way to the output thread, with its `--printf` or
+/// `--json` line when the worker rendered one and its parent directory for
+/// `-execdir` (the entry's `StatAt` is gone by then).
+struct Record {
+    entry: Entry,
+    line: Option<Vec<u8>>,
+    dir: Option<walk::ExecDir>,
+    // the `--sort size|mtime` or `--top` value (0 otherwise)
+    key: i64,
+    // the `--color` style picked in the worker
+    paint: color::Paint,
+}
+
+impl Record {
+    /// A whole end-of-walk report (`--count`, `--histogram`) as one record.
+    fn report(line: Vec<u8>) -> Self {
+        let entry = Entry {
+            path: PathBuf::new(),
+            file_type: filetype::EntryType::Dir,
+            depth: 0,
+            order: None,
+            subtree: None,
+            root: 0,
+        };
+        Record {
+            entry,
+            line: Some(line),
+            dir: None,
+            key: 0,
+            paint: color::Paint::default(),
+        }
+    }
+}
+
+/// Sorts buffered records for `--sort` (then `--reverse`); `--ordered`
+/// records arrive in order and are only reversed.
+fn sort_records(
+    records: &mut [Record],
+    key: Option<sort::Key>,
+    reverse: bool,
+) {
+    if let Some(k) = key {
+        records.sort_by(|a, b| k.compare(&a.entry, a.key, &b.entry, b.key));
+    }
+    if reverse {
+        records.reverse();
+    }
+}
+
+/// A walker thread's visitor, with `--ordered` listings passed on to the
+/// [`sort::Sequencer`].
+struct Sequenced<'a, F> {
+    visit: F,
+    sequencer: Option<Sequencing<'a>>,
+}
+
+impl<F: FnMut(Entry, &walk::StatAt) -> WalkState> walk::Visitor
+    for Sequenced<'_, F>
+{
+    fn visit(&mut self, entry: Entry, stat: &walk::StatAt) -> WalkState {
+        (self.visit)(entry, stat)
+    }
+
+    fn listed(&mut self, listing: walk::Listing) {
+        if let Some(s) = &self.sequencer {
+            s.seq.listed(&s.held, listing);
+        }
+    }
+
+    fn finished(&mut self, dir: u64) {
+        if let Some(s) = &self.sequencer {
+            s.seq.finished(&s.held, dir);
+        }
+    }
+}
+
+impl<F> Drop for Sequenced<'_, F> {
+    fn drop(&mut self) {
+        if let Some(s) = &self.sequencer {
+            s.seq.keep(&s.held);
+        }
+    }
+}
+
+impl top::Ranked for Record {
+    fn key(&self) -> i64 {
+        self.key
+    }
+
+    fn path(&self) -> &Path {
+        &self.entry.path
+    }
+}
+
+/// Per-walker-thread accumulator; sends [`Record`] items in batches and
 /// flushes the partial tail on `Drop` (when the visitor closure ends).
-struct BatchSender {
-    buf: Vec<Entry>,
-    tx: Sender<Vec<Entry>>,
+struct BatchSender<'a> {
+    buf: Vec<Record>,
+    tx: Sender<Vec<Record>>,
     closed: bool,
+    // --top: this worker's candidates, sent only on `Drop`
+    top: Option<top::Top<Record>>,
+    // --ordered: records are held for the sequencer instead
+    seq: Option<Sequencing<'a>>,
+}
+
+/// `--ordered`: the shared [`sort::Sequencer`] and this walker thread's
+/// records not yet handed to it.
+#[derive(Clone)]
+struct Sequencing<'a> {
+    seq: &'a sort::Sequencer<Record>,
+    held: sort::Held<Record>,
 }
 
-impl BatchSender {
-    fn new(tx: Sender<Vec<Entry>>) -> Self {
-        Self { buf: Vec::with_capacity(BATCH_SIZE), tx, closed: false }
+impl<'a> BatchSender<'a> {
+    fn new(tx: Sender<Vec<Record>>) -> Self {
+        Self {
+            buf: Vec::with_capacity(BATCH_SIZE),
+            tx,
+            closed: false,
+            top: None,
+            seq: None,
+        }
+    }
+
+    /// A sender that holds each record for the sequencer (`--ordered`), or
+    /// batches when there is none.
+    fn sequenced(
+        tx: Sender<Vec<Record>>,
+        seq: Option<Sequencing<'a>>,
+    ) -> Self {
+        let mut s = Self::new(tx);
+        s.seq = seq;
+        s
+    }
+
+    /// A sender that keeps only the best `n` records (`--top`).
+    fn keeping(tx: Sender<Vec<Record>>, n: usize, smallest: bool) -> Self {
+        let mut s = Self::new(tx);
+        s.top = Some(top::Top::new(n, smallest));
+        s
     }
 
-    /// Queues `entry`, flushing when full. Returns `false` once the channel
+    /// Queues `record`, flushing when full. Returns `false` once the channel
     /// has closed, signalling the caller to stop walking.
-    fn push(&mut self, entry: Entry) -> bool {
-        self.buf.push(entry);
+    fn push(&mut self, record: Record) -> bool {
+        if let (Some(s), Some(place)) = (&self.seq, record.entry.order) {
+            s.held.borrow_mut().push((place, record));
+            return s.seq.is_open();
+        }
+        if let Some(t) = self.top.as_mut() {
+            t.push(record);
+            return true;
+        }
+        self.buf.push(record);
         if self.buf.len() >= BATCH_SIZE {
             self.flush()
         } else {
@@ -93,8 +273,11 @@ impl BatchSender {
     }
 }
 
-impl Drop for BatchSender {
+impl Drop for BatchSender<'_> {
     fn drop(&mut self) {
+        if let Some(t) = self.top.take() {
+            self.buf.extend(t.into_sorted());
+        }
         self.flush();
     }
 }
@@ -108,35 +291,57 @@ impl Drop for BatchSender {
 /// # Errors
 ///
 /// Signal registration failure, an invalid glob/regex pattern, or no paths.
-pub fn run<W, F>(args: &Args, make_out: F) -> Result<(), Error>
+/// A failing `-exec` is not an error; it is reported in the [`Status`].
+pub fn run<W, F>(args: &Args, make_out: F) -> Result<Status, Error>
 where
     W: Write,
     F: FnOnce() -> W + Send + 'static,
 {
     let shutdown = Arc::new(AtomicBool::new(false));
+    let delete_failed = AtomicBool::new(false);
+    // --delete under --max-results: matches claimed so far, across workers
+    let claimed = AtomicUsize::new(0);
+    let errors =
+        report::Errors::new(args.quiet_errors, args.ignore_readdir_race);
+    let stats = stats::Stats::new(args.stats);
+    let started = Instant::now();
+    let abs_fallbacks = walk::abs_fallbacks();
     interrupt::setup_interrupt_handler(&shutdown)?;
 
-    let glob_name =
-        glob::build_glob_set(args.name.as_deref(), args.case_insensitive)?;
-    let glob_enabled = args.name.is_some();
+    // -iname/-iregex patterns share their set, flagged case-insensitive
+    let glob_name = glob::build_mixed_glob_set(
+        args.name.as_deref(),
+        args.iname.as_deref(),
+        args.case_insensitive,
+    )?;
+    let glob_enabled = args.name.is_some() || args.iname.is_some();
 
-    let regex_name =
-        regex::build_regex_set(args.regex.as_deref(), args.case_insensitive)?;
-    let regex_enabled = args.regex.is_some();
+    let regex_name = regex::build_mixed_regex_set(
+        args.regex.as_deref(),
+        args.iregex.as_deref(),
+        args.case_insensitive,
+    )?;
+    let regex_enabled = args.regex.is_some() || args.iregex.is_some();
+    let any = args.any;
 
     // -path/-wholename: glob over the full path. globset's default lets `*`
     // cross `/`, matching find's -path semantics (file_name() glob never sees
     // a separator, so --name is unaffected).
-    let glob_path = glob::build_glob_set(
+    let glob_path = glob::build_mixed_glob_set(
         args.path_glob.as_deref(),
+        args.ipath_glob.as_deref(),
         args.case_insensitive,
     )?;
-    let path_glob_enabled = args.path_glob.is_some();
+    let path_glob_enabled =
+        args.path_glob.is_some() || args.ipath_glob.is_some();
 
     // -lname: glob over a symlink's target (matched after a readlink).
-    let glob_lname =
-        glob::build_glob_set(args.lname.as_deref(), args.case_insensitive)?;
-    let lname_enabled = args.lname.is_some();
+    let glob_lname = glob::build_mixed_glob_set(
+        args.lname.as_deref(),
+        args.ilname.as_deref(),
+        args.case_insensitive,
+    )?;
+    let lname_enabled = args.lname.is_some() || args.ilname.is_some();
     let access = args.access;
 
     // built here so a bad glob errors before the walk; applied in the walker
@@ -150,47 +355,216 @@ where
     let meta_mask = predicates.mask();
     let now = meta::now_secs();
 
-    let (tx, rx) = bounded::<Vec<Entry>>(CHAN_MULT * (args.threads - 1));
+    // -o/!/parentheses: compiled once, evaluated after the flat chain
+    let matcher = args
+        .expr
+        .as_ref()
+        .map(|e| expr::Matcher::compile(e, args.case_insensitive))
+        .transpose()?;
+
+    // --mime: type globs, matched like names (MIME types ignore case)
+    let mime_globs = args
+        .mime
+        .as_deref()
+        .map(|m| glob::build_glob_set(Some(m), true))
+        .transpose()?;
+    let mime_globs = mime_globs.as_ref();
+
+    let class = args.class;
+
+    // --contains: compiled once, tried last
+    let contains = args
+        .contains
+        .as_deref()
+        .map(|p| {
+            content::Contains::new(p, args.case_insensitive, args.max_filesize)
+        })
+        .transpose()?;
+    let contains = contains.as_ref();
+
+    let printf = args.printf.as_ref();
+    let json = args.json.as_ref();
+    let ls = args.ls;
+    let hash = args.hash;
+    let tree = args.tree;
+    let top_n = args.top.filter(|&n| n > 0);
+    let top_by = args.by;
+    let summarize = args.summarize;
+    let summarize_depth = args.summarize_depth.unwrap_or(usize::MAX);
+
+    let (tx, rx) = bounded::<Vec<Record>>(CHAN_MULT * (args.threads - 1));
 
     // NUL for --null (xargs -0 / find -print0), else newline
     let separator: u8 = if args.null { b'\0' } else { b'\n' };
 
     // result cap (None/0 = unlimited); the sole writer enforces it
     let max_results = args.max_results.filter(|&n| n > 0);
+    let print = args.print;
+    let exec_dir = args.exec.iter().any(exec::Command::in_dir);
+    // matches that wait on the output thread for the rest of the walk
+    let held = args.sort.is_some() || args.ordered || top_n.is_some();
+    let delete = args.delete;
+    let (sort_key, ordered, reverse) = (args.sort, args.ordered, args.reverse);
+    let path_style = args.path_style;
+    let quote = args.quote.unwrap_or_default();
+    // --color: LS_COLORS parsed once; workers pick, the writer paints
+    let palette =
+        (args.color == color::When::Always).then(color::Palette::from_env);
+    let palette = palette.map(Arc::new);
+    // --delete/--summarize walk directories post-order; --ordered keeps that
+    let post_order = delete || summarize;
+    // roots arrive absolute; like find's `.`, the working directory itself is
+    // emptied but kept
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let cwd = &cwd;
+
+    // -exec: executor threads fed by the output thread's dispatcher
+    let (pool, mut dispatcher) = if args.exec.is_empty() {
+        (None, None)
+    } else {
+        let (pool, d) =
+            exec::Pool::spawn(&args.exec, args.jobs, args.group_output);
+        (Some(pool), Some(d))
+    };
 
+    let writer_palette = palette.clone();
     let print_thread = thread::spawn(move || {
         // the BufWriter coalesces, so write paths in directly (no scratch)
         let mut stdout = BufWriter::with_capacity(256 * 1024, make_out());
         let mut written: usize = 0;
+        // --quote/--color scratch, reused across lines
+        let (mut quoted, mut painted) = (Vec::new(), Vec::new());
+        // --tree: every match is placed first, the drawing comes at the end
+        let mut drawing = tree.then(tree::Tree::default);
 
-        'outer: for batch in rx {
-            for entry in batch {
-                #[cfg(unix)]
-                stdout
-                    .write_all(entry.path.as_os_str().as_bytes())
-                    .unwrap_or(());
-                #[cfg(not(unix))]
-                stdout
-                    .write_all(entry.path.to_string_lossy().as_bytes())
-                    .unwrap_or(());
-                stdout.write_all(&[separator]).unwrap_or(());
-
-                // dropping `rx` on the Nth result closes the channel; the
-                // walkers observe that as WalkState::Quit
-                written += 1;
-                if max_results.is_some_and(|n| written >= n) {
-                    break 'outer;
+        // writes one record and hands it to -exec; false once the result cap
+        // is reached
+        let mut emit = |Record { entry, line, dir, key, paint }: Record| {
+            if let Some(d) = dispatcher.as_mut() {
+                d.dispatch(&entry.path, dir.as_ref());
+            }
+            // --top: the key goes before a plain path
+            if top_n.is_some() && print && line.is_none() {
+                let mut column = Vec::new();
+                top_by.write(key, &mut column);
+                column.push(b'\t');
+                stdout.write_all(&column).unwrap_or(());
+            }
+            // a --printf/--json line carries its own terminators; a bare
+            // -exec prints nothing
+            match line {
+                _ if !print => {}
+                Some(line) => stdout.write_all(&line).unwrap_or(()),
+                None if drawing.is_some() => {
+                    if let Some(t) = drawing.as_mut() {
+                        t.insert(&entry.path, entry.depth, paint);
+                    }
+                }
+                None if quote != quote::Style::Literal
+                    || writer_palette.is_some() =>
+                {
+                    quoted.clear();
+                    let path = regex::path_to_bytes(&entry.path);
+                    quote::write(quote, &path, &mut quoted);
+                    let out = match &writer_palette {
+                        Some(p) => {
+                            painted.clear();
+                            p.write(paint, &quoted, &mut painted);
+                            &mut painted
+                        }
+                        None => &mut quoted,
+                    };
+                    out.push(separator);
+                    stdout.write_all(out).unwrap_or(());
+                }
+                None => {
+                    #[cfg(unix)]
+                    stdout
+                        .write_all(entry.path.as_os_str().as_bytes())
+                        .unwrap_or(());
+                    #[cfg(not(unix))]
+                    stdout
+                        .write_all(entry.path.to_string_lossy().as_bytes())
+                        .unwrap_or(());
+                    stdout.write_all(&[separator]).unwrap_or(());
+                }
+            }
+            written += 1;
+            max_results.is_none_or(|n| written < n)
+        };
+
+        if let Some(n) = top_n {
+            // each worker sent its best n; the best n of those win
+            let mut best = top::Top::new(n, reverse);
+            for record in rx.iter().flatten() {
+                best.push(record);
+            }
+            for record in best.into_sorted() {
+                if !emit(record) {
+                    break;
+                }
+            }
+        } else if sort_key.is_some() || (ordered && reverse) {
+            // the whole walk is needed before the first line; the cap then
+            // applies to the sorted output
+            let mut all: Vec<Record> = rx.iter().flatten().collect();
+            sort_records(&mut all, sort_key, reverse);
+            for record in all {
+                if !emit(record) {
+                    break;
+                }
+            }
+        } else {
+            // dropping `rx` on the Nth result closes the channel; the
+            // walkers observe that as WalkState::Quit; --ordered batches
+            // arrive already in order
+            'outer: for batch in rx {
+                for record in batch {
+                    if !emit(record) {
+                        break 'outer;
+                    }
                 }
             }
         }
 
+        if let Some(t) = drawing {
+            let mut out = Vec::new();
+            t.render(quote, writer_palette.as_deref(), &mut out);
+            stdout.write_all(&out).unwrap_or(());
+        }
         stdout.flush().unwrap_or(());
+        // sends the partial `+` batches; the pool drains after this
+        if let Some(d) = dispatcher {
+            d.finish();
+        }
     });
 
     // dedup roots
     let unique_paths: Vec<&Path> =
         args.path.iter().map(PathBuf::as_path).unique().collect();
+    // --printf's %H: each starting point in the form its matches take
+    let starts: Vec<PathBuf> = match printf {
+        Some(_) => {
+            let mut r = pathstyle::Rewriter::new(path_style, cwd);
+            unique_paths
+                .iter()
+                .map(|p| r.rewrite(p, 0).unwrap_or_else(|| p.to_path_buf()))
+                .collect()
+        }
+        None => Vec::new(),
+    };
     let filetype_proto = filetype::FileType::new(&args.file_type);
+    // --count: each worker's tally lands here when it ends
+    let counts = std::sync::Mutex::new(count::Counts::new(unique_paths.len()));
+    // --duplicates: and each worker's regular files here
+    let candidates = std::sync::Mutex::new(Vec::new());
+    // --summarize: hard-linked files already counted
+    let links = summarize::Links::default();
+    // --histogram: and each worker's buckets here
+    let table = std::sync::Mutex::new(histogram::Table::new(
+        args.histogram.unwrap_or(histogram::By::Type),
+        now,
+    ));
 
     // root = depth 0; gated in the visitor so shallower levels still descend
     let min_depth = args.min_depth.unwrap_or(0);
@@ -201,6 +575,18 @@ where
 
     #[cfg(target_os = "linux")]
     let idle = args.idle;
+    // per worker, best-effort; failures leave it at normal priority
+    let enter = || {
+        #[cfg(target_os = "linux")]
+        if idle {
+            let _ = sched::set_idle_cpu();
+            let _ = sched::set_idle_io();
+        }
+    };
+
+    // --ordered: walkers report where each match and directory belongs
+    let sequencer =
+        ordered.then(|| sort::Sequencer::new(tx.clone(), post_order));
 
     // Every per-entry filter runs in the walker threads, cheapest first, so
     // statx is reached only after type/name/regex have kept the entry.
@@ -209,25 +595,62 @@ where
         &unique_paths,
         limiter.as_ref(),
         exclude,
+        &errors,
+        &stats,
         || {
-            // per worker, best-effort; failures leave it at normal priority
-            #[cfg(target_os = "linux")]
-            if idle {
-                let _ = sched::set_idle_cpu();
-                let _ = sched::set_idle_io();
-            }
+            enter();
             let filetype = filetype_proto;
             let shutdown = Arc::clone(&shutdown);
+            let delete_failed = &delete_failed;
+            let claimed = &claimed;
+            let links = &links;
+            let errors = &errors;
+            let stats = &stats;
             // reborrow so the move-visitor captures `&GlobSet`, not copies
             let glob_name = &glob_name;
+            let starts = &starts;
             let regex_name = &regex_name;
             let glob_path = &glob_path;
             let glob_lname = &glob_lname;
+            let matcher = matcher.as_ref();
+            let palette = palette.as_deref();
+            let sequencer = sequencer
+                .as_ref()
+                .map(|seq| Sequencing { seq, held: sort::Held::default() });
             // per-thread memo for -nouser/-nogroup reverse lookups
-            #[cfg(unix)]
-            let mut nss = meta::NssCache::default();
-            let mut batch = BatchSender::new(tx.clone());
-            move |entry: Entry, stat: &walk::StatAt| {
+            let mut ecx = expr::EvalCtx::new(now);
+            // per-thread memo for --printf's %u/%g and --ls owner names
+            let mut names = printf::Names::default();
+            // --contains: per-thread read buffer
+            let mut scratch = Vec::new();
+            // --hash: per-thread hasher and read buffer
+            let mut hasher = hash.map(hash::Hasher::new);
+            // --relative-to/--canonical, with its per-thread parent memo
+            let mut paths = pathstyle::Rewriter::new(path_style, cwd);
+            let mut counter = args.count.map(|_| count::Local::new(&counts));
+            let mut buckets =
+                args.histogram.map(|_| histogram::Local::new(&table));
+            let mut dups =
+                args.duplicates.then(|| duplicates::Local::new(&candidates));
+            let mut batch = match top_n {
+                Some(n) => BatchSender::keeping(tx.clone(), n, reverse),
+                None => BatchSender::sequenced(tx.clone(), sequencer.clone()),
+            };
+            // --summarize: totals print through their own path rewrite and
+            // batch, whatever the directory's own match decided
+            let mut report_paths = pathstyle::Rewriter::new(path_style, cwd);
+            let mut reports =
+                BatchSender::sequenced(tx.clone(), sequencer.clone());
+            // a starting point's failures stand even under
+            // -ignore_readdir_race
+            let report = |entry: &Entry, e: &std::io::Error| {
+                if entry.depth == 0 {
+                    errors.start_io(&entry.path, e);
+                } else {
+                    errors.io(&entry.path, e);
+                }
+            };
+            let mut visit = move |mut entry: Entry, stat: &walk::StatAt| {
                 if shutdown.load(Ordering::Relaxed) {
                     return WalkState::Quit;
                 }
@@ -235,17 +658,40 @@ where
                 if entry.depth < min_depth {
                     return WalkState::Continue;
                 }
-                if filetype.ignore_filetype(entry.file_type, &entry.path) {
+                if filetype.ignore_filetype_at(entry.file_type, stat) {
                     return WalkState::Continue;
                 }
-                if glob_enabled && !glob_name.is_match(entry.file_name()) {
-                    return WalkState::Continue;
+                // the working directory itself survives --delete, whatever
+                // form its path is printed in
+                let at_cwd = delete && entry.path == *cwd;
+                // from here on the path is the one printed and matched
+                // a --count leaf may come without a path (nothing reads it)
+                let mut walked = None;
+                if !entry.path.as_os_str().is_empty() {
+                    if let Some(p) = paths.rewrite(&entry.path, entry.depth) {
+                        walked = Some(std::mem::replace(&mut entry.path, p));
+                    }
                 }
-                // regex matches the full path; glob only the file name
-                if regex_enabled
-                    && !regex_name.is_match(&regex::path_to_bytes(&entry.path))
-                {
-                    return WalkState::Continue;
+                // regex matches the full path; glob only the file name.
+                // Both must hold, or either one under --any.
+                if glob_enabled || regex_enabled {
+                    let by_name = || {
+                        glob_enabled && glob_name.is_match(entry.file_name())
+                    };
+                    let by_regex = || {
+                        regex_enabled
+                            && regex_name
+                                .is_match(&regex::path_to_bytes(&entry.path))
+                    };
+                    let hit = if any {
+                        by_name() || by_regex()
+                    } else {
+                        (!glob_enabled || by_name())
+                            && (!regex_enabled || by_regex())
+                    };
+                    if !hit {
+                        return WalkState::Continue;
+                    }
                 }
                 // -path/-wholename
                 if path_glob_enabled && !glob_path.is_match(&entry.path) {
@@ -257,46 +703,342 @@ where
                         return WalkState::Continue;
                     }
                     match stat.readlink() {
-                        Some(t) if glob_lname.is_match(Path::new(&t)) => {}
-                        _ => return WalkState::Continue,
+                        Ok(t) if glob_lname.is_match(Path::new(&t)) => {}
+                        Ok(_) => return WalkState::Continue,
+                        Err(e) => {
+                            report(&entry, &e);
+                            return WalkState::Continue;
+                        }
                     }
                 }
-                // stat-based predicates (lazy); unstattable → skipped, like find
+                // a size fetched by a filter below, so --stats need not
+                // stat the match again
+                let mut size = None;
+                // stat-based predicates (lazy); unstattable → reported and
+                // skipped, like find
                 if meta_active {
-                    let Ok(m) = stat.fetch(meta_mask) else {
-                        return WalkState::Continue;
+                    let m = match stat.fetch(meta_mask) {
+                        Ok(m) => m,
+                        Err(e) => {
+                            report(&entry, &e);
+                            return WalkState::Continue;
+                        }
                     };
+                    size = meta::size_if(meta_mask, &m);
                     if !predicates.matches(&m, now) {
                         return WalkState::Continue;
                     }
                     // -nouser/-nogroup: reject when the id *does* resolve
                     #[cfg(unix)]
-                    {
-                        if predicates.nouser && nss.user_exists(m.uid) {
+                    if !predicates.owner_matches(&m, &mut ecx.nss) {
+                        return WalkState::Continue;
+                    }
+                }
+                // -readable/-writable/-executable (faccessat, real uid/gid)
+                if access != 0 && !stat.access(access) {
+                    return WalkState::Continue;
+                }
+                // the boolean expression, if any (evaluated left to right,
+                // short-circuiting like find)
+                if matcher.is_some_and(|m| !m.matches(&entry, stat, &mut ecx))
+                {
+                    return WalkState::Continue;
+                }
+                // --mime: reads the head of a regular file (other types by
+                // their inode/… name)
+                if let Some(g) = mime_globs {
+                    match mime::of(entry.file_type, stat) {
+                        Ok(ty) if g.is_match(ty) => {}
+                        Ok(_) => return WalkState::Continue,
+                        Err(e) => {
+                            report(&entry, &e);
+                            return WalkState::Continue;
+                        }
+                    }
+                }
+                // --text/--binary/--encoding: the head of a regular file
+                if let Some(c) = class {
+                    if entry.file_type != filetype::EntryType::File {
+                        return WalkState::Continue;
+                    }
+                    match c.matches(stat) {
+                        Ok(true) => {}
+                        Ok(false) => return WalkState::Continue,
+                        Err(e) => {
+                            report(&entry, &e);
                             return WalkState::Continue;
                         }
-                        if predicates.nogroup && nss.group_exists(m.gid) {
+                    }
+                }
+                // --contains: reads the file, so it goes last; only regular
+                // files have contents to search
+                if let Some(c) = contains {
+                    if entry.file_type != filetype::EntryType::File {
+                        return WalkState::Continue;
+                    }
+                    match c.matches(stat, &mut scratch) {
+                        Ok(true) => {}
+                        Ok(false) => return WalkState::Continue,
+                        Err(e) => {
+                            report(&entry, &e);
                             return WalkState::Continue;
                         }
                     }
                 }
-                // -readable/-writable/-executable (faccessat, real uid/gid)
-                if access != 0 && !stat.access(access) {
+                // --count: a match is only counted
+                if let Some(c) = counter.as_mut() {
+                    c.add(entry.root, entry.file_type);
+                    stats.matched_entry(entry.file_type, stat, size);
+                    return WalkState::Continue;
+                }
+                // --duplicates: a matched regular file is only collected
+                if let Some(d) = dups.as_mut() {
+                    if entry.file_type != filetype::EntryType::File {
+                        return WalkState::Continue;
+                    }
+                    match stat.fetch(meta::mask::SIZE) {
+                        Ok(m) => {
+                            stats.matched(m.size);
+                            d.add(m.size, entry.path, stat.anchored());
+                        }
+                        Err(e) => report(&entry, &e),
+                    }
+                    return WalkState::Continue;
+                }
+                // --histogram: a match only lands in its bucket
+                if let (Some(h), Some(by)) = (buckets.as_mut(), args.histogram)
+                {
+                    match stat.fetch(by.mask()) {
+                        Ok(m) => {
+                            h.add(&entry, &m);
+                            size = size.or(meta::size_if(by.mask(), &m));
+                        }
+                        Err(e) => {
+                            report(&entry, &e);
+                            return WalkState::Continue;
+                        }
+                    }
+                    stats.matched_entry(entry.file_type, stat, size);
                     return WalkState::Continue;
                 }
+                // --summarize: a match only adds its usage to the totals
+                if summarize {
+                    let m = match stat.fetch(summarize::MASK) {
+                        Ok(m) => m,
+                        Err(e) => {
+                            report(&entry, &e);
+                            return WalkState::Continue;
+                        }
+                    };
+                    let is_dir = entry.file_type == filetype::EntryType::Dir;
+                    let file = entry.file_type == filetype::EntryType::File;
+                    stats.matched(if file { m.size } else { 0 });
+                    return WalkState::Tally(links.usage(&m, is_dir));
+                }
+                // --printf/--json/--ls/--hash render here, while the entry's
+                // StatAt is valid; one that cannot be read is reported and
+                // skipped
+                let mut line = None;
+                let rendered = if let Some(f) = printf {
+                    let buf = line.insert(Vec::new());
+                    let start = &starts[entry.root];
+                    f.render(&entry, start, stat, &mut names, buf)
+                } else if let Some(j) = json {
+                    j.render(&entry, stat, line.insert(Vec::new()))
+                } else if ls {
+                    let buf = line.insert(Vec::new());
+                    ls::render(&entry, stat, &mut names, now, quote, buf)
+                } else if let Some(h) = hasher.as_mut() {
+                    // only regular files have contents to hash
+                    if entry.file_type != filetype::EntryType::File {
+                        return WalkState::Continue;
+                    }
+                    let buf = line.insert(Vec::new());
+                    stat.open().and_then(|file| {
+                        h.render(file, &entry.path, separator, buf)
+                    })
+                } else {
+                    Ok(())
+                };
+                if let Err(e) = rendered {
+                    report(&entry, &e);
+                    return WalkState::Continue;
+                }
+                // --sort size|mtime and --top read their value while the
+                // StatAt is valid, and before --delete removes the entry
+                let key_mask = match sort_key {
+                    _ if top_n.is_some() => top_by.mask(),
+                    Some(k) => k.mask(),
+                    None => 0,
+                };
+                let key = if key_mask == 0 {
+                    0
+                } else {
+                    match stat.fetch(key_mask) {
+                        Ok(m) => {
+                            size = size.or(meta::size_if(key_mask, &m));
+                            match sort_key {
+                                _ if top_n.is_some() => top_by.value(&m),
+                                Some(k) => k.value(&m),
+                                None => 0,
+                            }
+                        }
+                        Err(e) => {
+                            report(&entry, &e);
+                            return WalkState::Continue;
+                        }
+                    }
+                };
+                // --delete with a cap: the output thread would cut off too
+                // late, so each worker claims its match before removing it
+                if delete
+                    && max_results.is_some_and(|n| {
+                        claimed.fetch_add(1, Ordering::Relaxed) >= n
+                    })
+                {
+                    return WalkState::Quit;
+                }
+                // counted before --delete removes the entry, reusing a size
+                // fetched above
+                stats.matched_entry(entry.file_type, stat, size);
+                // --delete: the walker visits a directory only after its
+                // subtree, so its matched contents are already gone
+                if delete && !at_cwd {
+                    let is_dir = entry.file_type == filetype::EntryType::Dir;
+                    if let Err(e) = stat.unlink(is_dir) {
+                        errors.error(format_args!(
+                            "cannot delete '{}': {}",
+                            entry.path.display(),
+                            report::reason(&e)
+                        ));
+                        delete_failed.store(true, Ordering::Relaxed);
+                    }
+                }
+                // -execdir holds the parent fd until its command has run;
+                // a match held back for the whole walk finds it by path
+                // instead, so the held fds do not pile up
+                let dir = exec_dir.then(|| match &walked {
+                    _ if !held => stat.exec_dir(),
+                    Some(p) => walk::ExecDir::at(p),
+                    None => walk::ExecDir::at(&entry.path),
+                });
+                // a --printf/--json line is never painted
+                let paint = match palette {
+                    Some(p) if line.is_none() => p.pick(&entry, stat),
+                    _ => color::Paint::default(),
+                };
                 // stop walking once the output channel closes
-                if !batch.push(entry) {
+                if !batch.push(Record { entry, line, dir, key, paint }) {
                     return WalkState::Quit;
                 }
                 WalkState::Continue
-            }
+            };
+            let visit = move |entry: Entry, stat: &walk::StatAt| {
+                // directories are visited after their subtree, with its total
+                let Some(below) = entry
+                    .subtree
+                    .filter(|_| summarize && entry.depth <= summarize_depth)
+                else {
+                    return visit(entry, stat);
+                };
+                let mut report = Entry {
+                    path: entry.path.clone(),
+                    file_type: entry.file_type,
+                    depth: entry.depth,
+                    order: entry.order,
+                    subtree: None,
+                    root: entry.root,
+                };
+                let state = visit(entry, stat);
+                let total = match state {
+                    WalkState::Quit => return state,
+                    WalkState::Tally(own) => below.plus(own),
+                    WalkState::Continue => below,
+                };
+                if let Some(p) =
+                    report_paths.rewrite(&report.path, report.depth)
+                {
+                    report.path = p;
+                }
+                let mut line = Vec::new();
+                summarize::render(
+                    total,
+                    &report.path,
+                    quote,
+                    separator,
+                    &mut line,
+                );
+                // --sort size orders by the total
+                let key = match sort_key {
+                    Some(sort::Key::Size) => {
+                        i64::try_from(total.bytes).unwrap_or(i64::MAX)
+                    }
+                    Some(k) if k.mask() != 0 => {
+                        stat.fetch(k.mask()).map_or(0, |m| k.value(&m))
+                    }
+                    _ => 0,
+                };
+                let record = Record {
+                    entry: report,
+                    line: Some(line),
+                    dir: None,
+                    key,
+                    paint: color::Paint::default(),
+                };
+                if !reports.push(record) {
+                    return WalkState::Quit;
+                }
+                state
+            };
+            Sequenced { visit, sequencer }
         },
     );
+    // --ordered: after an early stop, what was still waiting
+    if let Some(s) = sequencer {
+        s.finish();
+    }
 
+    // --count: one record carrying the whole report
+    if let (Some(by), Ok(counts)) = (args.count, counts.into_inner()) {
+        let mut line = Vec::new();
+        counts.render(by, &unique_paths, quote, separator, &mut line);
+        let _ = tx.send(vec![Record::report(line)]);
+    }
+    // --histogram: likewise
+    if let (true, Ok(table)) = (args.histogram.is_some(), table.into_inner()) {
+        let mut line = Vec::new();
+        table.render(&mut printf::Names::default(), separator, &mut line);
+        let _ = tx.send(vec![Record::report(line)]);
+    }
+    // --duplicates: hashed on a fresh pool as wide as the walk's
+    if let (true, Ok(files)) = (args.duplicates, candidates.into_inner()) {
+        let pool = duplicates::Pool {
+            workers: (args.threads - 1).max(1),
+            limiter: limiter.as_ref(),
+            quit: &shutdown,
+            errors: &errors,
+            enter: &enter,
+        };
+        let groups = duplicates::groups(files, &pool);
+        if !shutdown.load(Ordering::Relaxed) {
+            let mut line = Vec::new();
+            duplicates::render(&groups, quote, separator, &mut line);
+            let _ = tx.send(vec![Record::report(line)]);
+        }
+    }
     drop(tx);
     print_thread.join().unwrap();
+    let exec_failed = pool.is_some_and(exec::Pool::join);
+    if args.stats {
+        let abs = walk::abs_fallbacks().saturating_sub(abs_fallbacks);
+        eprint!("{}", stats.summary(&errors, abs, started.elapsed()));
+    }
 
-    Ok(())
+    Ok(Status {
+        exec_failed,
+        delete_failed: delete_failed.into_inner(),
+        errors: errors.count(),
+    })
 }
 
 /// Raises the soft `RLIMIT_NOFILE` to the hard limit, giving the walker
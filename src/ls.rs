@@ -0,0 +1,182 @@
+// SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
+// SPDX-License-Identifier: MIT
+
+//! `--ls`/`-ls` — GNU find's long listing, byte for byte:
+//!
+//! ```text
+//!   1221157      4 drwxr-xr-x   3 root     root         4096 Oct 18 06:00 /r/a
+//! ```
+//!
+//! inode, 1 KiB blocks, mode, links, owner, group, size (or `major, minor`
+//! for a device), modification time (with the year instead of the clock once
+//! it is six months old or in the future), path and, for a symlink,
+//! `-> target`, both escaped by `--quote`. Rendered in the worker like
+//! `--printf`, with the same per-thread owner/group name memo.
+
+use crate::filetype::EntryType;
+use crate::meta::{self, Meta};
+use crate::printf::{self, Names};
+use crate::quote;
+use crate::regex::path_to_bytes;
+use crate::walk::{Entry, StatAt};
+use std::io::Write;
+
+/// The `stat` fields a line needs.
+pub const MASK: u32 = meta::mask::INO
+    | meta::mask::BLOCKS
+    | meta::mask::MODE
+    | meta::mask::NLINK
+    | meta::mask::UID
+    | meta::mask::GID
+    | meta::mask::SIZE
+    | meta::mask::MTIME;
+
+/// Half a Gregorian year: older (or future) times show the year, like `ls`.
+const SIX_MONTHS: i64 = 31_556_952 / 2;
+
+/// Appends `entry`'s line to `out`, newline included.
+///
+/// # Errors
+///
+/// The failed `stat`; the caller reports and skips the entry.
+pub fn render(
+    entry: &Entry,
+    stat: &StatAt,
+    names: &mut Names,
+    now: i64,
+    quote: quote::Style,
+    out: &mut Vec<u8>,
+) -> std::io::Result<()> {
+    let m = stat.fetch(MASK)?;
+    line(entry, &m, names, now, quote, out);
+    if entry.file_type == EntryType::Symlink {
+        if let Ok(t) = stat.readlink() {
+            out.extend_from_slice(b" -> ");
+            quote::write(quote, &path_to_bytes(&t), out);
+        }
+    }
+    out.push(b'\n');
+    Ok(())
+}
+
+/// Everything up to and including the path.
+fn line(
+    entry: &Entry,
+    m: &Meta,
+    names: &mut Names,
+    now: i64,
+    quote: quote::Style,
+    out: &mut Vec<u8>,
+) {
+    let kib = m.blocks.div_ceil(2);
+    let mode = printf::symbolic_mode(entry.file_type, m.mode);
+    let _ = write!(out, "{:>9} {kib:>6} ", m.ino);
+    out.extend_from_slice(&mode);
+    let _ = write!(out, " {:>3} ", m.nlink);
+    owner(out, m.uid, names, true);
+    owner(out, m.gid, names, false);
+    match entry.file_type {
+        EntryType::BlockDevice | EntryType::CharDevice => {
+            let _ = write!(out, "{:>3}, {:>3} ", m.rdev.0, m.rdev.1);
+        }
+        _ => {
+            let _ = write!(out, "{:>8} ", m.size);
+        }
+    }
+    let fmt: &[u8] = if m.mtime > now || now - m.mtime > SIX_MONTHS {
+        b"%b %e  %Y\0"
+    } else {
+        b"%b %e %H:%M\0"
+    };
+    printf::strftime(out, m.mtime, fmt);
+    out.push(b' ');
+    quote::write(quote, &path_to_bytes(&entry.path), out);
+}
+
+/// The owner (or group) name padded to 8, or the id when it has none.
+fn owner(out: &mut Vec<u8>, id: u32, names: &mut Names, user: bool) {
+    #[cfg(unix)]
+    {
+        let name =
+            if user { names.user_name(id) } else { names.group_name(id) };
+        if let Some(n) = name {
+            let _ = write!(out, "{n:<8} ");
+            return;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = (names, user);
+    let _ = write!(out, "{id:<8} ");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn meta(mode: u32, mtime: i64) -> Meta {
+        Meta {
+            size: 4096,
+            mtime,
+            ctime: 0,
+            atime: 0,
+            mode,
+            uid: 4_000_000_000,
+            gid: 4_000_000_000,
+            nlink: 3,
+            ino: 1_221_157,
+            blocks: 8,
+            rdev: (1, 3),
+            dev: 0,
+        }
+    }
+
+    fn listed(ty: EntryType, m: &Meta, now: i64) -> String {
+        let entry = Entry {
+            path: PathBuf::from("/r/a"),
+            file_type: ty,
+            depth: 1,
+            order: None,
+            subtree: None,
+            root: 0,
+        };
+        let mut out = Vec::new();
+        line(
+            &entry,
+            m,
+            &mut Names::default(),
+            now,
+            quote::Style::Literal,
+            &mut out,
+        );
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn columns_match_find_ls() {
+        let got = listed(EntryType::Dir, &meta(0o755, 0), 0);
+        let head =
+            "  1221157      4 drwxr-xr-x   3 4000000000 4000000000     4096 ";
+        assert!(got.starts_with(head), "{got}");
+        assert!(got.ends_with(" /r/a"), "{got}");
+    }
+
+    #[test]
+    fn devices_show_major_minor() {
+        let got = listed(EntryType::CharDevice, &meta(0o666, 0), 0);
+        assert!(got.contains(" 4000000000   1,   3 "), "{got}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn old_and_future_times_show_the_year() {
+        let now = 1_700_000_000;
+        let recent = listed(EntryType::File, &meta(0o644, now - 60), now);
+        let old =
+            listed(EntryType::File, &meta(0o644, now - SIX_MONTHS - 1), now);
+        let future = listed(EntryType::File, &meta(0o644, now + 3600), now);
+        assert!(recent.contains(':'), "{recent}");
+        assert!(!old.contains(':') && old.contains("  2023 "), "{old}");
+        assert!(future.contains("  2023 "), "{future}");
+    }
+}
@@ -3,7 +3,7 @@
 
 use anyhow::Error;
 use mimalloc::MiMalloc;
-use std::io;
+use std::io::{self, IsTerminal};
 use std::thread;
 
 use minifind::args::Args;
@@ -13,7 +13,7 @@ static GLOBAL: MiMalloc = MiMalloc;
 
 /// Binary entry point: wires parsed CLI args into the library pipeline.
 fn main() -> Result<(), Error> {
-    let args = Args::parse();
+    let mut args = Args::parse();
 
     // honor the requested thread count, but warn past the core count
     let available =
@@ -24,6 +24,27 @@ fn main() -> Result<(), Error> {
         eprintln!("minifind: {w}");
     }
 
+    // a hostile name must not reach a terminal raw; pipes and -0 stay exact
+    let tty = io::stdout().is_terminal();
+    if args.quote.is_none() && !args.null && tty {
+        args.quote = Some(minifind::quote::Style::Shell);
+    }
+    // --printf's names too, as GNU find's on a terminal
+    if let (Some(f), Some(q), true) = (args.printf.as_mut(), args.quote, tty) {
+        f.quote_names(q);
+    }
+
+    // --color=auto: a terminal, unless NO_COLOR (https://no-color.org) is set
+    if args.color == minifind::color::When::Auto {
+        let tty = io::stdout().is_terminal()
+            && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty());
+        args.color = if tty {
+            minifind::color::When::Always
+        } else {
+            minifind::color::When::Never
+        };
+    }
+
     minifind::interrupt::reset_sigpipe();
 
     // give the fd-anchored walker headroom for its pinned-parent-fd frontier
@@ -39,5 +60,9 @@ fn main() -> Result<(), Error> {
     }
 
     // defer locking stdout to the output thread (StdoutLock is not Send)
-    minifind::run(&args, || io::stdout().lock())
+    let status = minifind::run(&args, || io::stdout().lock())?;
+    if !status.success() {
+        std::process::exit(1);
+    }
+    Ok(())
 }
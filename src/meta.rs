@@ -28,6 +28,7 @@ pub mod mask {
     pub const GID: u32 = 1 << 6;
     pub const NLINK: u32 = 1 << 7;
     pub const INO: u32 = 1 << 8;
+    pub const BLOCKS: u32 = 1 << 9;
 }
 
 /// Seconds per day / minute, the units for the time predicates.
@@ -56,6 +57,12 @@ pub struct Meta {
     pub gid: u32,
     pub nlink: u64,
     pub ino: u64,
+    /// Allocated 512-byte blocks.
+    pub blocks: u64,
+    /// A device node's `(major, minor)`.
+    pub rdev: (u32, u32),
+    /// The device holding the entry; always filled (0 off Unix).
+    pub dev: u64,
 }
 
 /// find's `N` / `+N` / `-N` numeric comparison (`+` = greater, `-` = less).
@@ -498,6 +505,46 @@ impl Predicates {
         }
         true
     }
+
+    /// `-nouser`/`-nogroup` over an already-fetched `meta`: rejects when the
+    /// id *does* resolve. Kept out of [`matches`](Self::matches) because it
+    /// needs the caller's per-thread [`NssCache`].
+    #[cfg(unix)]
+    pub fn owner_matches(&self, meta: &Meta, nss: &mut NssCache) -> bool {
+        !(self.nouser && nss.user_exists(meta.uid)
+            || self.nogroup && nss.group_exists(meta.gid))
+    }
+
+    /// Whether `self` and `other` both set one scalar predicate, which a
+    /// [`merge`](Self::merge) could not AND.
+    pub fn overlaps(&self, other: &Predicates) -> bool {
+        self.size.is_some() && other.size.is_some()
+            || self.perm.is_some() && other.perm.is_some()
+            || self.uid.is_some() && other.uid.is_some()
+            || self.gid.is_some() && other.gid.is_some()
+            || self.links.is_some() && other.links.is_some()
+            || self.inum.is_some() && other.inum.is_some()
+    }
+
+    /// Folds `other` into `self` (AND). A repeated scalar predicate replaces
+    /// the earlier one, so callers check [`overlaps`](Self::overlaps) first.
+    pub fn merge(&mut self, other: Predicates) {
+        self.size = other.size.or(self.size);
+        self.times.extend(other.times);
+        self.perm = other.perm.or(self.perm);
+        self.uid = other.uid.or(self.uid);
+        self.gid = other.gid.or(self.gid);
+        self.links = other.links.or(self.links);
+        self.inum = other.inum.or(self.inum);
+        self.newer.extend(other.newer);
+        self.nouser |= other.nouser;
+        self.nogroup |= other.nogroup;
+    }
+}
+
+/// `m`'s size, if `mask` fetched it.
+pub fn size_if(mask: u32, m: &Meta) -> Option<u64> {
+    (mask & mask::SIZE != 0).then_some(m.size)
 }
 
 /// Wall-clock now in whole seconds since the Unix epoch — captured once at run
@@ -599,15 +646,18 @@ fn nss_gid(name: &str) -> Option<u32> {
     }
 }
 
-/// Per-walker-thread memo for the reverse lookups behind `-nouser`/`-nogroup`.
-/// Owner ids repeat heavily within a tree, so caching `uid/gid → has-entry`
-/// turns the per-entry `getpwuid_r`/`getgrgid_r` into one lookup per distinct
-/// id. One cache per thread keeps it lock-free.
+/// Per-walker-thread memo for the reverse lookups behind `-nouser`/`-nogroup`
+/// and the `%u`/`%g` output fields. Owner ids repeat heavily within a tree, so
+/// caching `uid/gid → has-entry` (and `→ name`) turns the per-entry
+/// `getpwuid_r`/`getgrgid_r` into one lookup per distinct id. One cache per
+/// thread keeps it lock-free.
 #[cfg(unix)]
 #[derive(Default)]
 pub struct NssCache {
     users: std::collections::HashMap<u32, bool>,
     groups: std::collections::HashMap<u32, bool>,
+    user_names: std::collections::HashMap<u32, Option<Box<str>>>,
+    group_names: std::collections::HashMap<u32, Option<Box<str>>>,
 }
 
 #[cfg(unix)]
@@ -621,6 +671,96 @@ impl NssCache {
     pub fn group_exists(&mut self, gid: u32) -> bool {
         *self.groups.entry(gid).or_insert_with(|| nss_group_exists(gid))
     }
+
+    /// The passwd name for `uid`, or `None` when it resolves to no entry.
+    pub fn user_name(&mut self, uid: u32) -> Option<&str> {
+        self.user_names
+            .entry(uid)
+            .or_insert_with(|| nss_user_name(uid))
+            .as_deref()
+    }
+
+    /// The group name for `gid`, or `None` when it resolves to no entry.
+    pub fn group_name(&mut self, gid: u32) -> Option<&str> {
+        self.group_names
+            .entry(gid)
+            .or_insert_with(|| nss_group_name(gid))
+            .as_deref()
+    }
+}
+
+/// `getpwuid_r` (reentrant) → the user name, or `None` if unknown.
+#[cfg(unix)]
+fn nss_user_name(uid: u32) -> Option<Box<str>> {
+    // SAFETY: `passwd` is repr(C) of ints and nullable pointers; all-zero is a
+    // valid initial state that getpwuid_r overwrites on success.
+    let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 1024];
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+    loop {
+        // SAFETY: valid out-pointers and a live owned buffer; getpwuid_r is
+        // reentrant (safe under parallelism).
+        let rc = unsafe {
+            libc::getpwuid_r(
+                uid,
+                &mut pwd,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut result,
+            )
+        };
+        if rc == 0 {
+            if result.is_null() {
+                return None;
+            }
+            // SAFETY: on success pw_name points at a NUL-terminated string
+            // inside `buf`, which is still alive here.
+            let name = unsafe { std::ffi::CStr::from_ptr(pwd.pw_name) };
+            return Some(name.to_string_lossy().into());
+        }
+        if rc == libc::ERANGE {
+            buf.resize(buf.len() * 2, 0);
+            continue;
+        }
+        return None;
+    }
+}
+
+/// `getgrgid_r` (reentrant) → the group name, or `None` if unknown.
+#[cfg(unix)]
+fn nss_group_name(gid: u32) -> Option<Box<str>> {
+    // SAFETY: `group` is repr(C) of ints and nullable pointers; all-zero is a
+    // valid initial state that getgrgid_r overwrites on success.
+    let mut grp: libc::group = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 1024];
+    let mut result: *mut libc::group = std::ptr::null_mut();
+    loop {
+        // SAFETY: valid out-pointers and a live owned buffer; getgrgid_r is
+        // reentrant (safe under parallelism).
+        let rc = unsafe {
+            libc::getgrgid_r(
+                gid,
+                &mut grp,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut result,
+            )
+        };
+        if rc == 0 {
+            if result.is_null() {
+                return None;
+            }
+            // SAFETY: on success gr_name points at a NUL-terminated string
+            // inside `buf`, which is still alive here.
+            let name = unsafe { std::ffi::CStr::from_ptr(grp.gr_name) };
+            return Some(name.to_string_lossy().into());
+        }
+        if rc == libc::ERANGE {
+            buf.resize(buf.len() * 2, 0);
+            continue;
+        }
+        return None;
+    }
 }
 
 /// `getpwuid_r` (reentrant): does a passwd entry exist for `uid`?
@@ -702,6 +842,9 @@ mod tests {
             gid: 0,
             nlink: 0,
             ino: 0,
+            blocks: 0,
+            rdev: (0, 0),
+            dev: 0,
         }
     }
 
@@ -807,6 +950,27 @@ mod tests {
         assert_eq!(p.mask(), mask::ATIME);
     }
 
+    #[test]
+    fn merge_accumulates_and_later_scalar_wins() {
+        let mut p = Predicates {
+            size: Some(SizePred::parse("+1k").unwrap()),
+            times: vec![TimePred::mtime("-1", DAY).unwrap()],
+            ..meta_p()
+        };
+        p.merge(Predicates {
+            size: Some(SizePred::parse("-2k").unwrap()),
+            times: vec![TimePred::atime("+1", DAY).unwrap()],
+            ..meta_p()
+        });
+        assert_eq!(p.times.len(), 2);
+        // the later `-2k` replaced `+1k`: a 10-byte file now matches
+        let mut m = meta();
+        m.size = 10;
+        m.mtime = 0;
+        m.atime = -2 * DAY;
+        assert!(p.matches(&m, 0));
+    }
+
     #[cfg(unix)]
     #[test]
     fn nss_cache_resolves_root_and_misses_high_id() {
@@ -815,6 +979,15 @@ mod tests {
         assert!(!cache.user_exists(4_000_000_000)); // no such uid
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn nss_cache_names_root_and_misses_high_id() {
+        let mut cache = NssCache::default();
+        assert_eq!(cache.user_name(0), Some("root"));
+        assert_eq!(cache.user_name(4_000_000_000), None);
+        assert_eq!(cache.group_name(4_000_000_000), None);
+    }
+
     /// A `Predicates` with everything empty, for `..` struct-update in tests.
     fn meta_p() -> Predicates {
         Predicates::default()
@@ -0,0 +1,141 @@
+// SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
+// SPDX-License-Identifier: MIT
+
+//! `--mime GLOB` and `%e` — an entry's MIME type, from its first bytes.
+//!
+//! A regular file is identified by its magic number, whatever its name
+//! says: the first [`HEAD`] bytes are read through the parent-anchored fd and
+//! checked against [`SIGNATURES`]. A file no signature claims is
+//! `text/plain` when it looks like text (see [`content::encoding`]), else
+//! `application/octet-stream`; an empty one is `inode/x-empty`. Other entry
+//! types get `file --mime-type`'s `inode/…` names without being read.
+
+use crate::content;
+use crate::filetype::EntryType;
+use crate::walk::StatAt;
+use std::io;
+
+/// How much of a file identification reads.
+pub const HEAD: usize = content::PROBE;
+
+/// `(offset, magic, type)`, tried in order; the first match wins.
+pub const SIGNATURES: &[(usize, &[u8], &str)] = &[
+    // executables and objects
+    (0, b"\x7fELF", "application/x-elf"),
+    (0, b"MZ", "application/x-dosexec"),
+    (0, b"\xcf\xfa\xed\xfe", "application/x-mach-binary"),
+    (0, b"\xce\xfa\xed\xfe", "application/x-mach-binary"),
+    (0, b"\0asm", "application/wasm"),
+    (0, b"#!", "text/x-shellscript"),
+    // archives and compression
+    (0, b"PK\x03\x04", "application/zip"),
+    (0, b"PK\x05\x06", "application/zip"),
+    (0, b"\x1f\x8b", "application/gzip"),
+    (0, b"BZh", "application/x-bzip2"),
+    (0, b"\xfd7zXZ\0", "application/x-xz"),
+    (0, b"\x28\xb5\x2f\xfd", "application/zstd"),
+    (0, b"\x04\x22\x4d\x18", "application/x-lz4"),
+    (0, b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+    (0, b"Rar!\x1a\x07", "application/x-rar"),
+    (0, b"!<arch>\n", "application/x-archive"),
+    (257, b"ustar", "application/x-tar"),
+    // documents and databases
+    (0, b"%PDF-", "application/pdf"),
+    (0, b"%!PS", "application/postscript"),
+    (0, b"{\\rtf", "text/rtf"),
+    (0, b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1", "application/x-ole-storage"),
+    (0, b"SQLite format 3\0", "application/vnd.sqlite3"),
+    (0, b"<?xml", "text/xml"),
+    // images
+    (0, b"\x89PNG\r\n\x1a\n", "image/png"),
+    (0, b"\xff\xd8\xff", "image/jpeg"),
+    (0, b"GIF87a", "image/gif"),
+    (0, b"GIF89a", "image/gif"),
+    (8, b"WEBP", "image/webp"),
+    (0, b"II*\0", "image/tiff"),
+    (0, b"MM\0*", "image/tiff"),
+    (0, b"BM", "image/bmp"),
+    // audio and video
+    (0, b"ID3", "audio/mpeg"),
+    (0, b"fLaC", "audio/flac"),
+    (0, b"OggS", "audio/ogg"),
+    (8, b"WAVE", "audio/x-wav"),
+    (8, b"AVI ", "video/x-msvideo"),
+    (4, b"ftyp", "video/mp4"),
+    (0, b"\x1a\x45\xdf\xa3", "video/x-matroska"),
+];
+
+/// The type of a file whose first bytes are `head`.
+pub fn sniff(head: &[u8]) -> &'static str {
+    if head.is_empty() {
+        return "inode/x-empty";
+    }
+    let claimed = SIGNATURES.iter().find(|(at, magic, _)| {
+        // the tags at offset 8 only count inside a RIFF container
+        (*at != 8 || head.starts_with(b"RIFF"))
+            && head.get(*at..at + magic.len()).is_some_and(|h| h == *magic)
+    });
+    claimed.map_or_else(|| fallback(head), |(_, _, ty)| ty)
+}
+
+fn fallback(head: &[u8]) -> &'static str {
+    if content::encoding(head).is_none() {
+        return "application/octet-stream";
+    }
+    let start = head.trim_ascii_start();
+    let html = [b"<!doctype html".as_slice(), b"<html"].iter().any(|tag| {
+        start.get(..tag.len()).is_some_and(|s| s.eq_ignore_ascii_case(tag))
+    });
+    if html {
+        "text/html"
+    } else {
+        "text/plain"
+    }
+}
+
+/// The type of the entry behind `stat`; only a regular file is read.
+///
+/// # Errors
+///
+/// The open or read failure.
+pub fn of(file_type: EntryType, stat: &StatAt) -> io::Result<&'static str> {
+    Ok(match file_type {
+        EntryType::File => {
+            let mut head = [0; HEAD];
+            let n = content::read_head(stat, &mut head)?;
+            sniff(&head[..n])
+        }
+        EntryType::Dir => "inode/directory",
+        EntryType::Symlink => "inode/symlink",
+        EntryType::BlockDevice => "inode/blockdevice",
+        EntryType::CharDevice => "inode/chardevice",
+        EntryType::Fifo => "inode/fifo",
+        EntryType::Socket => "inode/socket",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn magic_numbers_win_over_names() {
+        assert_eq!(sniff(b"\x7fELF\x02\x01\x01"), "application/x-elf");
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\0\0"), "image/png");
+        assert_eq!(sniff(b"RIFF\0\0\0\0WEBPVP8 "), "image/webp");
+        assert_eq!(sniff(b"\0\0\0\x18ftypmp42"), "video/mp4");
+        let mut tar = vec![0; 512];
+        tar[257..262].copy_from_slice(b"ustar");
+        assert_eq!(sniff(&tar), "application/x-tar");
+    }
+
+    #[test]
+    fn unclaimed_files_fall_back_on_their_bytes() {
+        assert_eq!(sniff(b""), "inode/x-empty");
+        assert_eq!(sniff(b"hello\n"), "text/plain");
+        assert_eq!(sniff(b"  <!DOCTYPE HTML>\n"), "text/html");
+        assert_eq!(sniff(b"\x01\x02\0\x03"), "application/octet-stream");
+        // an offset-8 tag outside a RIFF container claims nothing
+        assert_eq!(sniff(b"abcdefghWAVE"), "text/plain");
+    }
+}
@@ -0,0 +1,170 @@
+// SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
+// SPDX-License-Identifier: MIT
+
+//! `--relative-to`, `--absolute-path` and `--canonical` — the form a matched
+//! path takes.
+//!
+//! Starting points are made absolute at parse time, so that is the default.
+//! The walker itself always works on that absolute path; a [`Rewriter`] in
+//! each worker turns it into the requested form before `--path`/`--regex`
+//! match, so what is matched is what gets printed (and handed to `-exec`).
+
+use normpath::PathExt;
+use std::path::{Component, Path, PathBuf};
+
+/// How matched paths are written.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// The absolute path below the normalized starting point (the default,
+    /// `--absolute-path`).
+    #[default]
+    Absolute,
+    /// Relative to the entry's starting point, which itself is `.`
+    /// (`--relative-to ROOT`).
+    Root,
+    /// Relative to the current directory, with `..` as needed
+    /// (`--relative-to CWD`).
+    Cwd,
+    /// With every symlinked directory along the way resolved (`--canonical`).
+    Canonical,
+}
+
+impl Style {
+    /// Parses a `--relative-to` value.
+    ///
+    /// # Errors
+    ///
+    /// Anything but `ROOT` or `CWD` (in any case).
+    pub fn parse_relative(s: &str) -> Result<Self, anyhow::Error> {
+        match s.to_ascii_lowercase().as_str() {
+            "root" => Ok(Style::Root),
+            "cwd" => Ok(Style::Cwd),
+            _ => Err(anyhow::anyhow!(
+                "invalid --relative-to '{s}' (expected ROOT or CWD)"
+            )),
+        }
+    }
+}
+
+/// Per-worker path rewriting for one [`Style`].
+#[derive(Debug)]
+pub struct Rewriter {
+    style: Style,
+    cwd: PathBuf,
+    // the last parent resolved by --canonical and its resolution; siblings
+    // arrive together, so one entry hits most of the time
+    last: Option<(PathBuf, PathBuf)>,
+}
+
+impl Rewriter {
+    /// A rewriter for `style`; `cwd` is what `--relative-to CWD` is relative
+    /// to.
+    pub fn new(style: Style, cwd: &Path) -> Self {
+        Rewriter { style, cwd: cwd.to_path_buf(), last: None }
+    }
+
+    /// `path` (`depth` below its starting point) in the configured form, or
+    /// `None` when it stays as it is.
+    pub fn rewrite(&mut self, path: &Path, depth: usize) -> Option<PathBuf> {
+        match self.style {
+            Style::Absolute => None,
+            Style::Root => Some(below_root(path, depth)),
+            Style::Cwd => Some(relative(path, &self.cwd)),
+            Style::Canonical => self.canonical(path, depth),
+        }
+    }
+
+    /// Resolves the parent and keeps the entry's own name, so a symlink is
+    /// still printed as itself; `None` when resolution fails (the entry
+    /// vanished).
+    fn canonical(&mut self, path: &Path, depth: usize) -> Option<PathBuf> {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name())
+        else {
+            return None;
+        };
+        if depth == 0 {
+            return path.normalize().ok().map(|p| p.into_path_buf());
+        }
+        match &self.last {
+            Some((p, resolved)) if p == parent => Some(resolved.join(name)),
+            _ => {
+                let resolved = parent.normalize().ok()?.into_path_buf();
+                let out = resolved.join(name);
+                self.last = Some((parent.to_path_buf(), resolved));
+                Some(out)
+            }
+        }
+    }
+}
+
+/// The last `depth` components of `path`; `.` for the starting point itself.
+fn below_root(path: &Path, depth: usize) -> PathBuf {
+    let root = path.ancestors().nth(depth).unwrap_or(Path::new(""));
+    match path.strip_prefix(root) {
+        Ok(rest) if !rest.as_os_str().is_empty() => rest.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// `path` relative to `base` (both absolute): the shared prefix dropped, one
+/// `..` per remaining `base` component.
+fn relative(path: &Path, base: &Path) -> PathBuf {
+    let mut rest = path.components().peekable();
+    let mut up = base.components().peekable();
+    while let (Some(a), Some(b)) = (rest.peek(), up.peek()) {
+        if a != b {
+            break;
+        }
+        rest.next();
+        up.next();
+    }
+    let mut out: PathBuf = up.map(|_| Component::ParentDir).collect();
+    out.extend(rest);
+    if out.as_os_str().is_empty() {
+        out.push(".");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_to_root_strips_the_starting_point() {
+        assert_eq!(below_root(Path::new("/r/a/b"), 2), Path::new("a/b"));
+        assert_eq!(below_root(Path::new("/r"), 0), Path::new("."));
+        assert_eq!(below_root(Path::new("/a"), 1), Path::new("a"));
+    }
+
+    #[test]
+    fn relative_to_cwd_climbs_with_dotdot() {
+        let cwd = Path::new("/r/a");
+        assert_eq!(relative(Path::new("/r/a/x"), cwd), Path::new("x"));
+        assert_eq!(relative(Path::new("/r/b/y"), cwd), Path::new("../b/y"));
+        assert_eq!(relative(Path::new("/r/a"), cwd), Path::new("."));
+        assert_eq!(relative(Path::new("/"), cwd), Path::new("../.."));
+    }
+
+    #[test]
+    fn relative_to_parses_either_case() {
+        assert_eq!(Style::parse_relative("ROOT").unwrap(), Style::Root);
+        assert_eq!(Style::parse_relative("cwd").unwrap(), Style::Cwd);
+        assert!(Style::parse_relative("home").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn canonical_resolves_linked_parents_but_not_the_leaf() {
+        let tmp = tempfile::tempdir().unwrap();
+        let real = tmp.path().join("real");
+        std::fs::create_dir(&real).unwrap();
+        std::os::unix::fs::symlink(&real, tmp.path().join("link")).unwrap();
+        let base = tmp.path().normalize().unwrap().into_path_buf();
+        let mut rw = Rewriter::new(Style::Canonical, Path::new("/"));
+        let got = rw.rewrite(&tmp.path().join("link/f"), 2).unwrap();
+        assert_eq!(got, base.join("real/f"));
+        let got = rw.rewrite(&tmp.path().join("link"), 1).unwrap();
+        assert_eq!(got, base.join("link"));
+    }
+}
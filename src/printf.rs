@@ -0,0 +1,699 @@
+// SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
+// SPDX-License-Identifier: MIT
+
+//! `--printf FORMAT` — GNU find's `-printf` output directives.
+//!
+//! The format is parsed once at arg-parse time into a [`Format`] (so a bad
+//! directive errors before the walk) and rendered per matched entry in the
+//! walker threads, where the entry's [`StatAt`] is still valid. A format only
+//! pays for the `stat` fields its directives read: [`Format::mask`] is the
+//! union of their [`meta::mask`] bits, and a format without stat directives
+//! never stats at all. On a terminal the binary has the name directives
+//! escaped in the `--quote` style, as GNU find does.
+
+use crate::filetype::EntryType;
+use crate::meta::{self, Meta};
+use crate::mime;
+use crate::quote;
+use crate::regex::path_to_bytes;
+use crate::walk::{Entry, StatAt};
+use anyhow::{anyhow, Error};
+use std::io::Write;
+use std::path::Path;
+
+/// A parsed `--printf` template.
+#[derive(Debug, Clone)]
+pub struct Format {
+    items: Vec<Item>,
+    mask: u32,
+    // how %p %P %H %f %h %l are escaped (see quote_names)
+    quote: quote::Style,
+}
+
+#[derive(Debug, Clone)]
+enum Item {
+    Lit(Vec<u8>),
+    Field(Spec, Directive),
+}
+
+/// printf-style `%-10.3x` flags: left-justify, width, precision.
+#[derive(Debug, Clone, Copy, Default)]
+struct Spec {
+    left: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Directive {
+    /// `%p`: the path as printed.
+    Path,
+    /// `%P`: the path with its starting point removed.
+    Relative,
+    /// `%H`: the starting point the entry was found under.
+    Start,
+    /// `%f`: the file name.
+    Name,
+    /// `%h`: the leading directories (`.` when there are none).
+    Dir,
+    /// `%s`: size in bytes.
+    Size,
+    /// `%m`: permission bits in octal.
+    Perm,
+    /// `%M`: permissions in `ls -l` symbolic form.
+    Mode,
+    /// `%u` / `%g`: owner / group name (the number when unknown).
+    User,
+    Group,
+    /// `%U` / `%G`: numeric owner / group id.
+    Uid,
+    Gid,
+    /// `%a` `%c` `%t` (ctime(3) form) and `%A` `%C` `%T` + key.
+    Time(TimeField, Option<u8>),
+    /// `%n`: hard-link count.
+    Links,
+    /// `%i`: inode number.
+    Inode,
+    /// `%d`: depth below the starting point.
+    Depth,
+    /// `%y`: find's `-type` letter.
+    Type,
+    /// `%Y`: the type with a final symlink followed (`N`/`L` if dangling or
+    /// looping).
+    TargetType,
+    /// `%l`: a symlink's target (empty for non-symlinks).
+    Target,
+    /// `%e`: the MIME type from the first bytes (no find equivalent).
+    Mime,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TimeField {
+    Atime,
+    Ctime,
+    Mtime,
+}
+
+/// The `%Tk` keys GNU find accepts; everything but `@` and `+` is handed to
+/// strftime(3).
+const TIME_KEYS: &[u8] = b"@+aAbBcdDhHIjklmMprsSTUwWxXyYZ";
+
+impl Directive {
+    fn mask(self) -> u32 {
+        match self {
+            Directive::Size => meta::mask::SIZE,
+            Directive::Perm | Directive::Mode => meta::mask::MODE,
+            Directive::User | Directive::Uid => meta::mask::UID,
+            Directive::Group | Directive::Gid => meta::mask::GID,
+            Directive::Time(TimeField::Atime, _) => meta::mask::ATIME,
+            Directive::Time(TimeField::Ctime, _) => meta::mask::CTIME,
+            Directive::Time(TimeField::Mtime, _) => meta::mask::MTIME,
+            Directive::Links => meta::mask::NLINK,
+            Directive::Inode => meta::mask::INO,
+            _ => 0,
+        }
+    }
+
+    /// Whether the field is a path or name that `--quote` escapes.
+    fn is_name(self) -> bool {
+        matches!(
+            self,
+            Directive::Path
+                | Directive::Relative
+                | Directive::Start
+                | Directive::Name
+                | Directive::Dir
+                | Directive::Target
+        )
+    }
+
+    /// Whether precision truncates the rendered text (strings) or is ignored
+    /// (numbers).
+    fn is_text(self) -> bool {
+        matches!(
+            self,
+            Directive::Path
+                | Directive::Relative
+                | Directive::Start
+                | Directive::Name
+                | Directive::Dir
+                | Directive::Mode
+                | Directive::User
+                | Directive::Group
+                | Directive::Time(..)
+                | Directive::Target
+                | Directive::Mime
+        )
+    }
+}
+
+impl Format {
+    /// Parses a GNU find `-printf` format: `%` directives (with optional
+    /// `-`/`0` flags, width and `.precision`) and backslash escapes.
+    ///
+    /// # Errors
+    ///
+    /// An unknown directive, time key or escape, or a trailing `%` / `\`.
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        let b = s.as_bytes();
+        let mut items = Vec::new();
+        let mut lit = Vec::new();
+        let mut i = 0;
+        while i < b.len() {
+            match b[i] {
+                b'\\' => {
+                    i += 1;
+                    let Some(&c) = b.get(i) else {
+                        return Err(anyhow!("--printf format ends with '\\'"));
+                    };
+                    i += 1;
+                    match c {
+                        b'a' => lit.push(0x07),
+                        b'b' => lit.push(0x08),
+                        b'f' => lit.push(0x0c),
+                        b'n' => lit.push(b'\n'),
+                        b'r' => lit.push(b'\r'),
+                        b't' => lit.push(b'\t'),
+                        b'v' => lit.push(0x0b),
+                        b'\\' => lit.push(b'\\'),
+                        // \c: stop printing from this format right here
+                        b'c' => break,
+                        // up to three digits, stopping (as GNU does) before one
+                        // that would carry the byte past \377
+                        b'0'..=b'7' => {
+                            let mut v = c - b'0';
+                            for _ in 0..2 {
+                                match b.get(i).and_then(|&d| match d {
+                                    b'0'..=b'7' => {
+                                        v.checked_mul(8)?.checked_add(d - b'0')
+                                    }
+                                    _ => None,
+                                }) {
+                                    Some(next) => {
+                                        v = next;
+                                        i += 1;
+                                    }
+                                    None => break,
+                                }
+                            }
+                            lit.push(v);
+                        }
+                        _ => {
+                            return Err(anyhow!(
+                                "invalid --printf escape '\\{}'",
+                                char::from(c)
+                            ))
+                        }
+                    }
+                }
+                b'%' => {
+                    i += 1;
+                    if b.get(i) == Some(&b'%') {
+                        lit.push(b'%');
+                        i += 1;
+                        continue;
+                    }
+                    let (spec, dir, next) = parse_field(b, i)?;
+                    i = next;
+                    if !lit.is_empty() {
+                        items.push(Item::Lit(std::mem::take(&mut lit)));
+                    }
+                    items.push(Item::Field(spec, dir));
+                }
+                c => {
+                    lit.push(c);
+                    i += 1;
+                }
+            }
+        }
+        if !lit.is_empty() {
+            items.push(Item::Lit(lit));
+        }
+        let mask = items
+            .iter()
+            .map(|it| match it {
+                Item::Field(_, d) => d.mask(),
+                Item::Lit(_) => 0,
+            })
+            .fold(0, |a, m| a | m);
+        Ok(Format { items, mask, quote: quote::Style::Literal })
+    }
+
+    /// The union of the `stat` fields the directives read; 0 = no `stat`.
+    pub fn mask(&self) -> u32 {
+        self.mask
+    }
+
+    /// Escapes the path and name directives (`%p %P %H %f %h %l`) in
+    /// `style`; they are written raw otherwise.
+    pub fn quote_names(&mut self, style: quote::Style) {
+        self.quote = style;
+    }
+
+    /// Appends `entry` rendered through the format to `out`; `start` is its
+    /// starting point as printed (`%H`).
+    ///
+    /// # Errors
+    ///
+    /// The failed `stat`, leaving `out` untouched; the caller reports and
+    /// skips the entry as it does for unstattable metadata predicates.
+    pub fn render(
+        &self,
+        entry: &Entry,
+        start: &Path,
+        stat: &StatAt,
+        nss: &mut Names,
+        out: &mut Vec<u8>,
+    ) -> std::io::Result<()> {
+        let meta =
+            if self.mask == 0 { None } else { Some(stat.fetch(self.mask)?) };
+        let path = path_to_bytes(&entry.path);
+        let start = path_to_bytes(start);
+        let (mut field, mut raw) = (Vec::new(), Vec::new());
+        for item in &self.items {
+            match item {
+                Item::Lit(l) => out.extend_from_slice(l),
+                Item::Field(spec, dir) => {
+                    field.clear();
+                    render_field(
+                        *dir,
+                        entry,
+                        (&path, &start),
+                        stat,
+                        meta.as_ref(),
+                        nss,
+                        &mut field,
+                    );
+                    if self.quote != quote::Style::Literal
+                        && dir.is_name()
+                        && !field.is_empty()
+                    {
+                        std::mem::swap(&mut field, &mut raw);
+                        field.clear();
+                        quote::write(self.quote, &raw, &mut field);
+                    }
+                    pad(*spec, dir.is_text(), &field, out);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses the flags/width/precision and directive letter(s) starting at `i`
+/// (just past the `%`); returns the index after the directive.
+fn parse_field(
+    b: &[u8],
+    mut i: usize,
+) -> Result<(Spec, Directive, usize), Error> {
+    let mut spec = Spec::default();
+    while let Some(&c) = b.get(i) {
+        match c {
+            b'-' => spec.left = true,
+            // accepted like GNU find, which pads every field with spaces
+            b'0' | b'+' | b' ' | b'#' => {}
+            _ => break,
+        }
+        i += 1;
+    }
+    let digits = |i: &mut usize| {
+        let mut v = 0usize;
+        while let Some(&d @ b'0'..=b'9') = b.get(*i) {
+            v = v.saturating_mul(10).saturating_add(usize::from(d - b'0'));
+            *i += 1;
+        }
+        v
+    };
+    spec.width = digits(&mut i);
+    if b.get(i) == Some(&b'.') {
+        i += 1;
+        spec.precision = Some(digits(&mut i));
+    }
+    let Some(&c) = b.get(i) else {
+        return Err(anyhow!("--printf format ends with '%'"));
+    };
+    i += 1;
+    let dir = match c {
+        b'p' => Directive::Path,
+        b'P' => Directive::Relative,
+        b'H' => Directive::Start,
+        b'f' => Directive::Name,
+        b'h' => Directive::Dir,
+        b's' => Directive::Size,
+        b'm' => Directive::Perm,
+        b'M' => Directive::Mode,
+        b'u' => Directive::User,
+        b'g' => Directive::Group,
+        b'U' => Directive::Uid,
+        b'G' => Directive::Gid,
+        b'a' => Directive::Time(TimeField::Atime, None),
+        b'c' => Directive::Time(TimeField::Ctime, None),
+        b't' => Directive::Time(TimeField::Mtime, None),
+        b'A' | b'C' | b'T' => {
+            let field = match c {
+                b'A' => TimeField::Atime,
+                b'C' => TimeField::Ctime,
+                _ => TimeField::Mtime,
+            };
+            match b.get(i) {
+                Some(&k) if TIME_KEYS.contains(&k) => {
+                    i += 1;
+                    Directive::Time(field, Some(k))
+                }
+                Some(&k) => {
+                    return Err(anyhow!(
+                        "invalid --printf time key '%{}{}'",
+                        char::from(c),
+                        char::from(k)
+                    ))
+                }
+                None => {
+                    return Err(anyhow!(
+                        "--printf directive '%{}' needs a time key",
+                        char::from(c)
+                    ))
+                }
+            }
+        }
+        b'n' => Directive::Links,
+        b'i' => Directive::Inode,
+        b'd' => Directive::Depth,
+        b'y' => Directive::Type,
+        b'Y' => Directive::TargetType,
+        b'l' => Directive::Target,
+        b'e' => Directive::Mime,
+        _ => {
+            return Err(anyhow!(
+                "invalid --printf directive '%{}'",
+                char::from(c)
+            ))
+        }
+    };
+    Ok((spec, dir, i))
+}
+
+/// Owner/group name source for `%u`/`%g`: the per-thread NSS memo on Unix.
+#[cfg(unix)]
+pub type Names = meta::NssCache;
+
+/// Off Unix there is no NSS; `%u`/`%g` print the numeric id.
+#[cfg(not(unix))]
+#[derive(Default)]
+pub struct Names;
+
+// `path` and its starting point `start` (%H) come as printed
+fn render_field(
+    dir: Directive,
+    entry: &Entry,
+    (path, start): (&[u8], &[u8]),
+    stat: &StatAt,
+    meta: Option<&Meta>,
+    nss: &mut Names,
+    out: &mut Vec<u8>,
+) {
+    // stat directives always have `meta` (their bits are in the fetched mask)
+    let m = || meta.expect("stat field requested by the format mask");
+    match dir {
+        Directive::Path => out.extend_from_slice(path),
+        Directive::Relative => out.extend_from_slice(below(path, entry.depth)),
+        Directive::Start => out.extend_from_slice(start),
+        Directive::Name => {
+            out.extend_from_slice(&path_to_bytes(entry.file_name()));
+        }
+        Directive::Dir => match path.iter().rposition(|&c| c == b'/') {
+            Some(cut) => out.extend_from_slice(&path[..cut]),
+            None => out.push(b'.'),
+        },
+        Directive::Size => write_num(out, m().size),
+        Directive::Perm => {
+            let _ = write!(out, "{:o}", m().mode & 0o7777);
+        }
+        Directive::Mode => {
+            out.extend_from_slice(&symbolic_mode(entry.file_type, m().mode));
+        }
+        Directive::User => {
+            let uid = m().uid;
+            #[cfg(unix)]
+            if let Some(name) = nss.user_name(uid) {
+                return out.extend_from_slice(name.as_bytes());
+            }
+            write_num(out, uid);
+        }
+        Directive::Group => {
+            let gid = m().gid;
+            #[cfg(unix)]
+            if let Some(name) = nss.group_name(gid) {
+                return out.extend_from_slice(name.as_bytes());
+            }
+            write_num(out, gid);
+        }
+        Directive::Uid => write_num(out, m().uid),
+        Directive::Gid => write_num(out, m().gid),
+        Directive::Time(field, key) => {
+            let t = match field {
+                TimeField::Atime => m().atime,
+                TimeField::Ctime => m().ctime,
+                TimeField::Mtime => m().mtime,
+            };
+            write_time(out, t, key);
+        }
+        Directive::Links => write_num(out, m().nlink),
+        Directive::Inode => write_num(out, m().ino),
+        Directive::Depth => write_num(out, entry.depth),
+        Directive::Type => out.push(entry.file_type.type_char()),
+        Directive::TargetType => out.push(target_type(entry, stat)),
+        Directive::Target => {
+            if entry.file_type == EntryType::Symlink {
+                if let Ok(t) = stat.readlink() {
+                    out.extend_from_slice(&path_to_bytes(&t));
+                }
+            }
+        }
+        Directive::Mime => {
+            if let Ok(t) = mime::of(entry.file_type, stat) {
+                out.extend_from_slice(t.as_bytes());
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = nss;
+}
+
+/// `%Y`: a symlink's target type, `N` when it dangles, `L` when it loops.
+fn target_type(entry: &Entry, stat: &StatAt) -> u8 {
+    if entry.file_type != EntryType::Symlink {
+        return entry.file_type.type_char();
+    }
+    #[cfg(unix)]
+    {
+        match stat.followed().fetch(meta::mask::MODE) {
+            Ok(m) => {
+                EntryType::from_mode(m.mode).map_or(b'U', EntryType::type_char)
+            }
+            Err(e) if e.raw_os_error() == Some(libc::ELOOP) => b'L',
+            Err(_) => b'N',
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = stat;
+        b'l'
+    }
+}
+
+/// `%P`: the last `depth` components of `path`, whatever form the starting
+/// point before them was printed in.
+fn below(path: &[u8], depth: usize) -> &[u8] {
+    if depth == 0 {
+        return b"";
+    }
+    let mut cut = path.len();
+    for _ in 0..depth {
+        match path[..cut].iter().rposition(|&c| c == b'/') {
+            Some(p) => cut = p,
+            None => return path,
+        }
+    }
+    &path[cut + 1..]
+}
+
+fn write_num(out: &mut Vec<u8>, n: impl std::fmt::Display) {
+    let _ = write!(out, "{n}");
+}
+
+/// Applies width/justification (and precision, for text) to one field.
+fn pad(spec: Spec, text: bool, field: &[u8], out: &mut Vec<u8>) {
+    let field = match spec.precision {
+        Some(p) if text && p < field.len() => &field[..p],
+        _ => field,
+    };
+    let fill = spec.width.saturating_sub(field.len());
+    if spec.left {
+        out.extend_from_slice(field);
+        out.resize(out.len() + fill, b' ');
+    } else {
+        out.resize(out.len() + fill, b' ');
+        out.extend_from_slice(field);
+    }
+}
+
+/// `ls -l`-style `drwxr-xr-x` for `%M`: the type letter, then rwx triplets
+/// with set-id and sticky bits folded in as `s`/`S` and `t`/`T`.
+pub fn symbolic_mode(ty: EntryType, mode: u32) -> [u8; 10] {
+    let mut s = *b"----------";
+    s[0] = match ty {
+        EntryType::File => b'-',
+        EntryType::Dir => b'd',
+        EntryType::Symlink => b'l',
+        EntryType::BlockDevice => b'b',
+        EntryType::CharDevice => b'c',
+        EntryType::Fifo => b'p',
+        EntryType::Socket => b's',
+    };
+    for (i, shift) in [6u32, 3, 0].into_iter().enumerate() {
+        let bits = (mode >> shift) & 0o7;
+        let at = 1 + i * 3;
+        if bits & 0o4 != 0 {
+            s[at] = b'r';
+        }
+        if bits & 0o2 != 0 {
+            s[at + 1] = b'w';
+        }
+        if bits & 0o1 != 0 {
+            s[at + 2] = b'x';
+        }
+    }
+    let special = [(0o4000, 3, b's'), (0o2000, 6, b's'), (0o1000, 9, b't')];
+    for (bit, at, c) in special {
+        if mode & bit != 0 {
+            s[at] = if s[at] == b'x' { c } else { c.to_ascii_uppercase() };
+        }
+    }
+    s
+}
+
+/// Renders `t` (seconds since the epoch) for a time directive: ctime(3) form
+/// without a key, else the `%Tk` key.
+fn write_time(out: &mut Vec<u8>, t: i64, key: Option<u8>) {
+    match key {
+        Some(b'@') => write_num(out, t),
+        Some(b'+') => strftime(out, t, b"%Y-%m-%d+%H:%M:%S\0"),
+        Some(k) => strftime(out, t, &[b'%', k, 0]),
+        None => strftime(out, t, b"%a %b %e %H:%M:%S %Y\0"),
+    }
+}
+
+/// strftime(3) over local time; `fmt` is NUL-terminated.
+#[cfg(unix)]
+pub(crate) fn strftime(out: &mut Vec<u8>, t: i64, fmt: &[u8]) {
+    let time = t as libc::time_t;
+    // SAFETY: `tm` is a plain repr(C) struct; localtime_r fills it in.
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    // SAFETY: valid pointers to live locals; localtime_r is reentrant.
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return write_num(out, t);
+    }
+    let mut buf = [0u8; 128];
+    // SAFETY: `buf` is writable for its full length, `fmt` is
+    // NUL-terminated and `tm` was filled in above.
+    let n = unsafe {
+        libc::strftime(
+            buf.as_mut_ptr().cast(),
+            buf.len(),
+            fmt.as_ptr().cast(),
+            &tm,
+        )
+    };
+    out.extend_from_slice(&buf[..n]);
+}
+
+/// Off Unix there is no localtime_r/strftime binding; print epoch seconds.
+#[cfg(not(unix))]
+pub(crate) fn strftime(out: &mut Vec<u8>, t: i64, _fmt: &[u8]) {
+    write_num(out, t);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(s: &str) -> Vec<String> {
+        Format::parse(s)
+            .unwrap()
+            .items
+            .iter()
+            .map(|it| match it {
+                Item::Lit(l) => String::from_utf8_lossy(l).into_owned(),
+                Item::Field(_, d) => format!("{d:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_directives_and_literals() {
+        assert_eq!(spec("%p %s\\n"), ["Path", " ", "Size", "\n"]);
+        assert_eq!(spec("100%%"), ["100%"]);
+        assert_eq!(spec("%T@"), ["Time(Mtime, Some(64))"]);
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(spec("a\\tb\\0c\\101"), ["a\tb\0cA"]);
+        // a third digit that would overflow the byte stays literal
+        assert_eq!(spec("\\101\\777"), ["A?7"]);
+        // \c stops the format
+        assert_eq!(spec("%p\\cignored"), ["Path"]);
+    }
+
+    #[test]
+    fn malformed_formats_error() {
+        for bad in ["%", "%z", "%T", "%Tq", "\\", "\\q"] {
+            assert!(Format::parse(bad).is_err(), "{bad:?} should fail");
+        }
+    }
+
+    #[test]
+    fn mask_covers_only_used_fields() {
+        assert_eq!(Format::parse("%p %f %d %y\\n").unwrap().mask(), 0);
+        let f = Format::parse("%s %m %u %Tk").unwrap();
+        assert_eq!(
+            f.mask(),
+            meta::mask::SIZE
+                | meta::mask::MODE
+                | meta::mask::UID
+                | meta::mask::MTIME
+        );
+    }
+
+    #[test]
+    fn below_keeps_depth_components() {
+        assert_eq!(below(b"/r/a/b", 2), b"a/b");
+        assert_eq!(below(b"/r", 0), b"");
+        assert_eq!(below(b"/a", 1), b"a");
+        assert_eq!(below(b"a/b", 2), b"a/b");
+    }
+
+    #[test]
+    fn padding_and_precision() {
+        let mut out = Vec::new();
+        let s = Spec { width: 5, ..Spec::default() };
+        pad(s, false, b"42", &mut out);
+        pad(Spec { left: true, ..s }, true, b"ab", &mut out);
+        pad(s, false, b"7", &mut out);
+        pad(
+            Spec { precision: Some(2), ..Spec::default() },
+            true,
+            b"abc",
+            &mut out,
+        );
+        assert_eq!(out, b"   42ab       7ab");
+    }
+
+    #[test]
+    fn symbolic_mode_bits() {
+        assert_eq!(&symbolic_mode(EntryType::File, 0o644), b"-rw-r--r--");
+        assert_eq!(&symbolic_mode(EntryType::Dir, 0o1777), b"drwxrwxrwt");
+        assert_eq!(&symbolic_mode(EntryType::File, 0o4644), b"-rwSr--r--");
+        assert_eq!(&symbolic_mode(EntryType::File, 0o2755), b"-rwxr-sr-x");
+    }
+}
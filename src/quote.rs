@@ -0,0 +1,201 @@
+// SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
+// SPDX-License-Identifier: MIT
+
+//! `--quote=STYLE` — escaping hostile file names on output, after GNU `ls
+//! --quoting-style`.
+//!
+//! A name may hold a newline, a terminal escape sequence or bytes that are not
+//! UTF-8; written raw, it splits a line-based consumer's record or reprograms
+//! the terminal. Every style but `literal` makes such bytes visible, and
+//! `shell` output can always be pasted back into a POSIX shell. The binary
+//! picks `shell` by itself when stdout is a terminal.
+
+use anyhow::{anyhow, Error};
+
+/// How a path is written.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// The raw bytes (the default off a terminal).
+    #[default]
+    Literal,
+    /// Single-quoted when needed, control characters and invalid UTF-8 as
+    /// `$'\ooo'` (GNU `shell-escape`).
+    Shell,
+    /// Always double-quoted, with C escapes.
+    C,
+    /// C escapes without the quotes; spaces become `\ `.
+    Escape,
+}
+
+impl Style {
+    /// Parses a `--quote` value.
+    ///
+    /// # Errors
+    ///
+    /// An unknown style.
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        match s {
+            "literal" => Ok(Style::Literal),
+            "shell" => Ok(Style::Shell),
+            "c" => Ok(Style::C),
+            "escape" => Ok(Style::Escape),
+            _ => Err(anyhow!(
+                "invalid --quote style '{s}' (expected shell, c, literal or \
+                 escape)"
+            )),
+        }
+    }
+}
+
+/// Appends `name` to `out` in `style`.
+pub fn write(style: Style, name: &[u8], out: &mut Vec<u8>) {
+    match style {
+        Style::Literal => out.extend_from_slice(name),
+        Style::Shell => shell(name, out),
+        Style::C => {
+            out.push(b'"');
+            c_escape(name, b"\"", out);
+            out.push(b'"');
+        }
+        Style::Escape => c_escape(name, b" ", out),
+    }
+}
+
+/// Bytes that need no quoting in a POSIX shell word.
+fn is_shell_safe(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"%+,-./:=@_".contains(&b)
+}
+
+/// Calls `f` with each run of printable text (`true`) or of bytes to escape
+/// (`false`): control characters, C1 controls and invalid UTF-8.
+fn runs(name: &[u8], mut f: impl FnMut(&[u8], bool)) {
+    for chunk in name.utf8_chunks() {
+        let valid = chunk.valid();
+        let mut start = 0;
+        for (i, c) in valid.char_indices() {
+            if c.is_control() {
+                if start < i {
+                    f(&valid.as_bytes()[start..i], true);
+                }
+                f(&valid.as_bytes()[i..i + c.len_utf8()], false);
+                start = i + c.len_utf8();
+            }
+        }
+        if start < valid.len() {
+            f(&valid.as_bytes()[start..], true);
+        }
+        if !chunk.invalid().is_empty() {
+            f(chunk.invalid(), false);
+        }
+    }
+}
+
+/// Backslash escapes for one byte: the C letter where there is one, else
+/// three octal digits.
+fn escape_byte(b: u8, out: &mut Vec<u8>) {
+    let letter = match b {
+        0x07 => b'a',
+        0x08 => b'b',
+        b'\t' => b't',
+        b'\n' => b'n',
+        0x0b => b'v',
+        0x0c => b'f',
+        b'\r' => b'r',
+        _ => {
+            out.extend_from_slice(&[
+                b'\\',
+                b'0' + (b >> 6),
+                b'0' + (b >> 3 & 7),
+                b'0' + (b & 7),
+            ]);
+            return;
+        }
+    };
+    out.extend_from_slice(&[b'\\', letter]);
+}
+
+/// C escapes; `also` lists extra bytes that get a backslash.
+fn c_escape(name: &[u8], also: &[u8], out: &mut Vec<u8>) {
+    runs(name, |run, printable| {
+        for &b in run {
+            if !printable {
+                escape_byte(b, out);
+            } else if b == b'\\' || also.contains(&b) {
+                out.extend_from_slice(&[b'\\', b]);
+            } else {
+                out.push(b);
+            }
+        }
+    });
+}
+
+fn shell(name: &[u8], out: &mut Vec<u8>) {
+    if name.is_empty() {
+        out.extend_from_slice(b"''");
+        return;
+    }
+    if name.iter().copied().all(is_shell_safe) {
+        out.extend_from_slice(name);
+        return;
+    }
+    // quoted text and `$'…'` escapes alternate: 'a'$'\n''b'
+    runs(name, |run, printable| {
+        if printable {
+            out.push(b'\'');
+            for &b in run {
+                if b == b'\'' {
+                    out.extend_from_slice(b"'\\''");
+                } else {
+                    out.push(b);
+                }
+            }
+            out.push(b'\'');
+        } else {
+            out.extend_from_slice(b"$'");
+            for &b in run {
+                escape_byte(b, out);
+            }
+            out.push(b'\'');
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quoted(style: Style, name: &[u8]) -> String {
+        let mut out = Vec::new();
+        write(style, name, &mut out);
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn shell_quotes_only_when_needed() {
+        assert_eq!(quoted(Style::Shell, b"/r/a-b.txt"), "/r/a-b.txt");
+        assert_eq!(quoted(Style::Shell, b"/r/a b"), "'/r/a b'");
+        assert_eq!(quoted(Style::Shell, b"it's"), "'it'\\''s'");
+        assert_eq!(quoted(Style::Shell, b"~x"), "'~x'");
+    }
+
+    #[test]
+    fn shell_escapes_controls_and_invalid_utf8() {
+        assert_eq!(quoted(Style::Shell, b"a\nb"), "'a'$'\\n''b'");
+        assert_eq!(quoted(Style::Shell, b"\x1b[31m"), "$'\\033''[31m'");
+        assert_eq!(quoted(Style::Shell, b"x\xff"), "'x'$'\\377'");
+        assert_eq!(quoted(Style::Shell, "ž".as_bytes()), "'ž'");
+    }
+
+    #[test]
+    fn c_and_escape_styles() {
+        assert_eq!(quoted(Style::C, b"a \"b\"\t\\"), "\"a \\\"b\\\"\\t\\\\\"");
+        assert_eq!(quoted(Style::Escape, b"a b\n"), "a\\ b\\n");
+        assert_eq!(quoted(Style::Literal, b"a\nb"), "a\nb");
+    }
+
+    #[test]
+    fn styles_parse_and_reject_unknown() {
+        assert_eq!(Style::parse("c").unwrap(), Style::C);
+        assert!(Style::parse("locale").is_err());
+    }
+}
@@ -21,6 +21,24 @@ pub fn build_regex_set(
         .context("Unable to parse and build regular expression set")
 }
 
+/// Builds one `RegexSet` from `patterns` plus `ipatterns` (the `-iregex`
+/// ones), which match case-insensitively regardless of `case_insensitive`.
+pub fn build_mixed_regex_set(
+    patterns: Option<&[String]>,
+    ipatterns: Option<&[String]>,
+    case_insensitive: bool,
+) -> Result<RegexSet, Error> {
+    // an inline `(?i)` scopes case-insensitivity to the one pattern
+    let all =
+        patterns.into_iter().flatten().cloned().chain(
+            ipatterns.into_iter().flatten().map(|p| format!("(?i){p}")),
+        );
+    RegexSetBuilder::new(all)
+        .case_insensitive(case_insensitive)
+        .build()
+        .context("Unable to parse and build regular expression set")
+}
+
 /// Converts the given path to a byte slice, lossily on non-Unix.
 #[cfg(unix)]
 #[inline]
@@ -87,6 +105,18 @@ mod tests {
         assert!(rs.is_match(b"/src/main.RS"));
     }
 
+    #[test]
+    fn test_build_mixed_regex_set_case_per_pattern() {
+        let patterns = vec![r"/Photos/".to_string()];
+        let ipatterns = vec![r"\.JPG$".to_string()];
+        let rs =
+            build_mixed_regex_set(Some(&patterns), Some(&ipatterns), false)
+                .unwrap();
+        assert!(rs.is_match(b"/a/photo.jpg"));
+        assert!(rs.is_match(b"/Photos/x"));
+        assert!(!rs.is_match(b"/photos/x"));
+    }
+
     #[test]
     fn test_build_regex_set_invalid() {
         let patterns = vec!["[invalid".to_string()];
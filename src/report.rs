@@ -0,0 +1,149 @@
+// SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
+// SPDX-License-Identifier: MIT
+
+//! Runtime error reporting shared by the walker threads.
+//!
+//! find keeps going past an unreadable directory or a vanished entry but says
+//! so on stderr and exits non-zero at the end. [`Errors`] does the same: each
+//! failure is printed as `minifind: PATH: reason` (unless `--quiet-errors`)
+//! and counted, and the count becomes part of the run's exit status.
+//!
+//! Under `-ignore_readdir_race` an `ENOENT` for an entry deleted between
+//! `getdents` and its `statx`/`openat`/`readlinkat` is neither printed nor
+//! counted, as in find; a starting point that is gone is still an error.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// A thread-safe error counter that also prints each error.
+#[derive(Debug, Default)]
+pub struct Errors {
+    quiet: bool,
+    ignore_race: bool,
+    count: AtomicUsize,
+    // I/O errors by errno, for --stats
+    errnos: Mutex<BTreeMap<i32, usize>>,
+}
+
+impl Errors {
+    /// A reporter that prints to stderr unless `quiet`, and drops vanished
+    /// entries under `ignore_race`.
+    pub fn new(quiet: bool, ignore_race: bool) -> Self {
+        Errors { quiet, ignore_race, ..Self::default() }
+    }
+
+    /// Records `msg`, printing it as `minifind: MSG`.
+    pub fn error(&self, msg: impl Display) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        if !self.quiet {
+            eprintln!("minifind: {msg}");
+        }
+    }
+
+    /// Records an I/O failure on `path` as `minifind: PATH: reason`, unless
+    /// it only says the entry vanished and races are ignored.
+    pub fn io(&self, path: &Path, err: &io::Error) {
+        if self.ignore_race && err.kind() == io::ErrorKind::NotFound {
+            return;
+        }
+        self.start_io(path, err);
+    }
+
+    /// [`io`](Self::io) for a starting point, which was named rather than
+    /// read from a directory, so its vanishing is never a race.
+    pub fn start_io(&self, path: &Path, err: &io::Error) {
+        if let Some(code) = err.raw_os_error() {
+            if let Ok(mut errnos) = self.errnos.lock() {
+                *errnos.entry(code).or_default() += 1;
+            }
+        }
+        self.error(format_args!("{}: {}", path.display(), reason(err)));
+    }
+
+    /// How many errors were recorded so far.
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// The errors so far grouped by reason, in errno order; those without an
+    /// errno (loops, failed commands, …) come last as `other`.
+    pub fn by_reason(&self) -> Vec<(String, usize)> {
+        let mut out: Vec<(String, usize)> = match self.errnos.lock() {
+            Ok(errnos) => errnos
+                .iter()
+                .map(|(&code, &n)| {
+                    (reason(&io::Error::from_raw_os_error(code)), n)
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        let rest = self.count() - out.iter().map(|r| r.1).sum::<usize>();
+        if rest > 0 {
+            out.push(("other".to_string(), rest));
+        }
+        out
+    }
+}
+
+/// `err` as find words it: the OS message without std's `(os error N)`.
+pub fn reason(err: &io::Error) -> String {
+    let msg = err.to_string();
+    match err.raw_os_error() {
+        Some(code) => msg
+            .strip_suffix(&format!(" (os error {code})"))
+            .map_or_else(|| msg.clone(), str::to_owned),
+        None => msg,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reason_drops_the_os_error_suffix() {
+        let e = io::Error::from_raw_os_error(libc::ENOENT);
+        assert_eq!(reason(&e), "No such file or directory");
+        let e = io::Error::other("custom");
+        assert_eq!(reason(&e), "custom");
+    }
+
+    #[test]
+    fn quiet_errors_still_count() {
+        let errors = Errors::new(true, false);
+        errors.io(Path::new("/x"), &io::Error::other("gone"));
+        errors.error("more");
+        assert_eq!(errors.count(), 2);
+    }
+
+    #[test]
+    fn ignore_race_drops_only_enoent() {
+        let errors = Errors::new(true, true);
+        let gone = io::Error::from_raw_os_error(libc::ENOENT);
+        errors.io(Path::new("/x"), &gone);
+        assert_eq!(errors.count(), 0);
+        let denied = io::Error::from_raw_os_error(libc::EACCES);
+        errors.io(Path::new("/x"), &denied);
+        assert_eq!(errors.count(), 1);
+        // a starting point is never a race
+        errors.start_io(Path::new("/x"), &gone);
+        assert_eq!(errors.count(), 2);
+    }
+
+    #[test]
+    fn by_reason_groups_errnos_and_the_rest() {
+        let errors = Errors::new(true, false);
+        let denied = io::Error::from_raw_os_error(libc::EACCES);
+        errors.io(Path::new("/x"), &denied);
+        errors.io(Path::new("/y"), &denied);
+        errors.error("loop");
+        assert_eq!(
+            errors.by_reason(),
+            [("Permission denied".to_string(), 2), ("other".to_string(), 1)]
+        );
+    }
+}
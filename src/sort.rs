@@ -0,0 +1,345 @@
+// SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
+// SPDX-License-Identifier: MIT
+
+//! `--sort KEY` and `--ordered` — reproducible output order.
+//!
+//! The parallel walker emits in whatever order its threads happen to finish
+//! directories. `--sort` buffers every match on the output thread and sorts
+//! it by a [`Key`] once the walk is done (ties broken by path, so the result
+//! is total). `--ordered` streams instead: each entry carries its
+//! [`Place`] in its directory, each directory's [`Listing`] says which of
+//! those places are subdirectories, and a [`Sequencer`] releases matches in
+//! find's sequential pre-order as soon as everything before them is known —
+//! without a `stat` or a string comparison, and holding only the
+//! directories still in flight.
+
+use crate::meta;
+use crate::walk::{Entry, Listing, Place, STARTS};
+use anyhow::{anyhow, Error};
+use crossbeam_channel::Sender;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::Mutex;
+
+/// What `--sort` orders by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    /// File name, then path.
+    Name,
+    /// Whole path, component by component.
+    Path,
+    /// Size in bytes.
+    Size,
+    /// Modification time.
+    Mtime,
+    /// Depth below the starting point.
+    Depth,
+}
+
+impl Key {
+    /// Parses a `--sort` value.
+    ///
+    /// # Errors
+    ///
+    /// An unknown key.
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        match s {
+            "name" => Ok(Key::Name),
+            "path" => Ok(Key::Path),
+            "size" => Ok(Key::Size),
+            "mtime" => Ok(Key::Mtime),
+            "depth" => Ok(Key::Depth),
+            _ => Err(anyhow!(
+                "invalid --sort key '{s}' (expected name, path, size, mtime \
+                 or depth)"
+            )),
+        }
+    }
+
+    /// The `stat` field the key reads; 0 = none.
+    pub fn mask(self) -> u32 {
+        match self {
+            Key::Size => meta::mask::SIZE,
+            Key::Mtime => meta::mask::MTIME,
+            Key::Name | Key::Path | Key::Depth => 0,
+        }
+    }
+
+    /// The numeric sort value taken from `stat` in the walker thread (size or
+    /// mtime); 0 for the keys that need none.
+    pub fn value(self, m: &meta::Meta) -> i64 {
+        match self {
+            Key::Size => i64::try_from(m.size).unwrap_or(i64::MAX),
+            Key::Mtime => m.mtime,
+            Key::Name | Key::Path | Key::Depth => 0,
+        }
+    }
+
+    /// Compares two entries with their [`value`](Key::value)s; equal keys
+    /// fall back to the path.
+    pub fn compare(self, a: &Entry, av: i64, b: &Entry, bv: i64) -> Ordering {
+        let first = match self {
+            Key::Name => a.file_name().cmp(b.file_name()),
+            Key::Path => Ordering::Equal,
+            Key::Size | Key::Mtime => av.cmp(&bv),
+            Key::Depth => a.depth.cmp(&b.depth),
+        };
+        first.then_with(|| a.path.cmp(&b.path))
+    }
+}
+
+/// `--ordered`: puts the matches of a parallel walk back in sequential
+/// order and sends each run of them on as soon as it is complete.
+///
+/// Walker threads keep their matches in their own [`Held`] list and hand
+/// it over with the next directory [`Listing`] they report, or under
+/// `--delete` with a directory [`finished`](Sequencer::finished) after its
+/// contents — a match is always visited before both. A cursor walks the
+/// tree in find's order and stops at the first directory not yet listed.
+pub struct Sequencer<T> {
+    state: Mutex<State<T>>,
+    tx: Sender<Vec<T>>,
+    closed: AtomicBool,
+    // --delete/--summarize: a directory follows its contents
+    post: bool,
+}
+
+/// One walker thread's matches not yet handed to the [`Sequencer`].
+pub type Held<T> = Rc<RefCell<Vec<(Place, T)>>>;
+
+struct State<T> {
+    dirs: HashMap<u64, Dir<T>>,
+    // (dir, next index, next of its subdirs), outermost first
+    cursor: Vec<(u64, u32, usize)>,
+}
+
+/// A directory the cursor has not passed yet.
+struct Dir<T> {
+    items: HashMap<u32, T>,
+    listing: Option<Listing>,
+    finished: bool,
+}
+
+impl<T> Default for Dir<T> {
+    fn default() -> Self {
+        Dir { items: HashMap::new(), listing: None, finished: false }
+    }
+}
+
+impl<T> Sequencer<T> {
+    /// Sends in order on `tx`; `post` puts each directory after its
+    /// contents.
+    pub fn new(tx: Sender<Vec<T>>, post: bool) -> Self {
+        let state =
+            State { dirs: HashMap::new(), cursor: vec![(STARTS, 0, 0)] };
+        Sequencer {
+            state: Mutex::new(state),
+            tx,
+            closed: AtomicBool::new(false),
+            post,
+        }
+    }
+
+    /// Whether the channel is still open; once it closes, the walk should
+    /// stop.
+    pub fn is_open(&self) -> bool {
+        !self.closed.load(AtomicOrdering::Relaxed)
+    }
+
+    /// Takes `held`, and records a directory's listing: every match in it
+    /// has been visited.
+    pub fn listed(&self, held: &RefCell<Vec<(Place, T)>>, listing: Listing) {
+        if let Ok(mut st) = self.state.lock() {
+            take_held(&mut st, held);
+            let dir = st.dirs.entry(listing.dir).or_default();
+            dir.listing = Some(listing);
+            self.advance(&mut st);
+        }
+    }
+
+    /// Takes `held`, and records that directory `dir` has been visited
+    /// after its contents.
+    pub fn finished(&self, held: &RefCell<Vec<(Place, T)>>, dir: u64) {
+        if let Ok(mut st) = self.state.lock() {
+            take_held(&mut st, held);
+            st.dirs.entry(dir).or_default().finished = true;
+            self.advance(&mut st);
+        }
+    }
+
+    /// Takes what a walker thread still held when it stopped.
+    pub fn keep(&self, held: &RefCell<Vec<(Place, T)>>) {
+        if let Ok(mut st) = self.state.lock() {
+            take_held(&mut st, held);
+        }
+    }
+
+    /// Sends whatever an early stop left behind, in order: directories
+    /// never read count as empty, and unvisited ones are not waited for.
+    pub fn finish(self) {
+        if let Ok(mut st) = self.state.lock() {
+            self.advance_to(&mut st, true);
+        }
+    }
+
+    /// Moves the cursor as far as the listings received allow, sending the
+    /// matches it passes.
+    fn advance(&self, st: &mut State<T>) {
+        self.advance_to(st, false);
+    }
+
+    fn advance_to(&self, st: &mut State<T>, stopped: bool) {
+        let mut out = Vec::new();
+        while let Some(&(id, next, sub)) = st.cursor.last() {
+            let dir = st.dirs.get(&id);
+            let listing = dir.and_then(|d| d.listing.as_ref());
+            if listing.is_none() && !stopped {
+                break;
+            }
+            if next >= listing.map_or(0, |l| l.len) {
+                // the starting points have no entry of their own
+                let done = dir.is_some_and(|d| d.finished);
+                if self.post && id != STARTS && !done && !stopped {
+                    break;
+                }
+                st.dirs.remove(&id);
+                st.cursor.pop();
+                if let Some(up) = st.cursor.last_mut() {
+                    if self.post {
+                        out.extend(take(&mut st.dirs, up.0, up.1));
+                        up.1 += 1;
+                        up.2 += 1;
+                    }
+                }
+                continue;
+            }
+            match listing.and_then(|l| l.subdirs.get(sub)) {
+                Some(&(at, child)) if at == next => {
+                    // a subdirectory's own entry is pushed before its
+                    // listing, even a starting point's
+                    let ready = st
+                        .dirs
+                        .get(&child)
+                        .is_some_and(|d| d.listing.is_some());
+                    if !ready && !stopped {
+                        break;
+                    }
+                    if !self.post {
+                        out.extend(take(&mut st.dirs, id, next));
+                        if let Some(top) = st.cursor.last_mut() {
+                            top.1 += 1;
+                            top.2 += 1;
+                        }
+                    }
+                    st.cursor.push((child, 0, 0));
+                }
+                _ => {
+                    out.extend(take(&mut st.dirs, id, next));
+                    if let Some(top) = st.cursor.last_mut() {
+                        top.1 += 1;
+                    }
+                }
+            }
+        }
+        if !out.is_empty() && self.is_open() && self.tx.send(out).is_err() {
+            self.closed.store(true, AtomicOrdering::Relaxed);
+        }
+    }
+}
+
+/// Moves a walker thread's held matches into their directories.
+fn take_held<T>(st: &mut State<T>, held: &RefCell<Vec<(Place, T)>>) {
+    for (place, item) in held.borrow_mut().drain(..) {
+        st.dirs.entry(place.dir).or_default().items.insert(place.index, item);
+    }
+}
+
+/// Removes the match at `index` in `dir`, if there was one.
+fn take<T>(
+    dirs: &mut HashMap<u64, Dir<T>>,
+    dir: u64,
+    index: u32,
+) -> Option<T> {
+    dirs.get_mut(&dir)?.items.remove(&index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filetype::EntryType;
+    use std::path::PathBuf;
+
+    fn entry(path: &str, depth: usize) -> Entry {
+        Entry {
+            path: PathBuf::from(path),
+            file_type: EntryType::File,
+            depth,
+            order: None,
+            subtree: None,
+            root: 0,
+        }
+    }
+
+    #[test]
+    fn keys_parse_and_reject_unknown() {
+        assert_eq!(Key::parse("mtime").unwrap(), Key::Mtime);
+        assert_eq!(Key::Size.mask(), meta::mask::SIZE);
+        assert!(Key::parse("atime").is_err());
+    }
+
+    #[test]
+    fn ties_fall_back_to_the_path() {
+        let (a, b) = (entry("/r/x/f", 2), entry("/r/y/f", 2));
+        assert_eq!(Key::Name.compare(&a, 0, &b, 0), Ordering::Less);
+        assert_eq!(Key::Size.compare(&a, 5, &b, 1), Ordering::Greater);
+        assert_eq!(Key::Depth.compare(&b, 0, &a, 0), Ordering::Greater);
+    }
+
+    #[test]
+    fn sequencer_releases_pre_or_post_order_as_dirs_list() {
+        // one starting point (dir 1) holding a, sub/ (dir 2) holding x, b
+        let at = |dir, index| Place { dir, index };
+        let listing = |dir, len, subdirs: &[(u32, u64)]| Listing {
+            dir,
+            len,
+            subdirs: subdirs.to_vec(),
+        };
+        for post in [false, true] {
+            let (tx, rx) = crossbeam_channel::unbounded();
+            let seq = Sequencer::new(tx, post);
+            let held = RefCell::new(Vec::new());
+            let push = |place, item| held.borrow_mut().push((place, item));
+            seq.listed(&held, listing(STARTS, 1, &[(0, 1)]));
+            // the subdirectory is read first
+            push(at(2, 0), "x");
+            seq.listed(&held, listing(2, 1, &[]));
+            assert!(rx.try_recv().is_err());
+            if !post {
+                push(at(STARTS, 0), "root");
+            }
+            push(at(1, 0), "a");
+            push(at(1, 2), "b");
+            if !post {
+                push(at(1, 1), "sub");
+            }
+            seq.listed(&held, listing(1, 3, &[(1, 2)]));
+            if post {
+                push(at(1, 1), "sub");
+                seq.finished(&held, 2);
+                push(at(STARTS, 0), "root");
+                seq.finished(&held, 1);
+            }
+            seq.finish();
+            let got: Vec<_> = rx.try_iter().flatten().collect();
+            let want = if post {
+                ["a", "x", "sub", "b", "root"]
+            } else {
+                ["root", "a", "sub", "x", "b"]
+            };
+            assert_eq!(got, want);
+        }
+    }
+}
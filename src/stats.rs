@@ -0,0 +1,164 @@
+// SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
+// SPDX-License-Identifier: MIT
+
+//! `--stats` — what a run actually did, summarized on stderr at exit.
+//!
+//! [`Stats`] is shared by reference with the walker threads like
+//! [`Errors`]. Its counters are plain relaxed atomics bumped only when
+//! `--stats` is on, so a normal run pays one predictable branch per event.
+
+use crate::filetype::EntryType;
+use crate::meta;
+use crate::report::Errors;
+use crate::walk::StatAt;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Run counters; all of them stay 0 unless enabled.
+#[derive(Debug, Default)]
+pub struct Stats {
+    enabled: bool,
+    dirs: AtomicU64,
+    entries: AtomicU64,
+    matched: AtomicU64,
+    stat_calls: AtomicU64,
+    dt_unknown: AtomicU64,
+    bytes: AtomicU64,
+}
+
+impl Stats {
+    /// Counters that count only when `enabled`.
+    pub fn new(enabled: bool) -> Self {
+        Stats { enabled, ..Self::default() }
+    }
+
+    #[inline]
+    fn add(&self, counter: &AtomicU64, n: u64) {
+        if self.enabled {
+            counter.fetch_add(n, Ordering::Relaxed);
+        }
+    }
+
+    /// A directory was opened for reading.
+    #[inline]
+    pub fn dir_opened(&self) {
+        self.add(&self.dirs, 1);
+    }
+
+    /// The walker produced an entry (a starting point or a listed child).
+    #[inline]
+    pub fn entry_seen(&self) {
+        self.add(&self.entries, 1);
+    }
+
+    /// A `statx`/`stat` was issued.
+    #[inline]
+    pub fn stat_call(&self) {
+        self.add(&self.stat_calls, 1);
+    }
+
+    /// A `DT_UNKNOWN` entry needed a `stat` for its type.
+    #[inline]
+    pub fn dt_unknown(&self) {
+        self.add(&self.dt_unknown, 1);
+    }
+
+    /// An entry matched; `bytes` is its size (0 for non-files).
+    #[inline]
+    pub fn matched(&self, bytes: u64) {
+        self.add(&self.matched, 1);
+        self.add(&self.bytes, bytes);
+    }
+
+    /// An entry matched; a regular file's `size`, unless a filter already
+    /// fetched it, is `stat`ed for, a lookup left out of "stat calls" so
+    /// turning `--stats` on does not change it.
+    pub fn matched_entry(
+        &self,
+        ty: EntryType,
+        stat: &StatAt,
+        size: Option<u64>,
+    ) {
+        if self.enabled {
+            let bytes = match (ty, size) {
+                (EntryType::File, Some(size)) => size,
+                (EntryType::File, None) => stat
+                    .fetch_uncounted(meta::mask::SIZE)
+                    .map_or(0, |m| m.size),
+                _ => 0,
+            };
+            self.matched(bytes);
+        }
+    }
+
+    /// The summary: one `name  value` line per counter, then the errors by
+    /// reason.
+    pub fn summary(
+        &self,
+        errors: &Errors,
+        abs_fallbacks: u64,
+        elapsed: Duration,
+    ) -> String {
+        let get = |c: &AtomicU64| c.load(Ordering::Relaxed);
+        let entries = get(&self.entries);
+        let secs = elapsed.as_secs_f64();
+        // whole entries per second; a run too short to time reports 0
+        let rate = if secs > 0.0 { (entries as f64 / secs) as u64 } else { 0 };
+        let rows = [
+            ("directories opened", get(&self.dirs)),
+            ("entries seen", entries),
+            ("entries matched", get(&self.matched)),
+            ("bytes matched", get(&self.bytes)),
+            ("stat calls", get(&self.stat_calls)),
+            ("DT_UNKNOWN fallbacks", get(&self.dt_unknown)),
+            ("absolute-path opens", abs_fallbacks),
+            ("errors", errors.count() as u64),
+        ];
+        let mut out = String::new();
+        for (name, value) in rows {
+            let _ = writeln!(out, "{name:<22}{value}");
+        }
+        for (reason, n) in errors.by_reason() {
+            let _ = writeln!(out, "  {reason:<20}{n}");
+        }
+        let _ = writeln!(out, "{:<22}{secs:.3}s", "elapsed");
+        let _ = writeln!(out, "{:<22}{rate}", "entries/sec");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::path::Path;
+
+    #[test]
+    fn disabled_counters_stay_zero() {
+        let stats = Stats::new(false);
+        stats.entry_seen();
+        stats.matched(10);
+        assert_eq!(stats.entries.load(Ordering::Relaxed), 0);
+        assert_eq!(stats.bytes.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn summary_lists_counters_and_errors_by_reason() {
+        let stats = Stats::new(true);
+        stats.dir_opened();
+        stats.entry_seen();
+        stats.entry_seen();
+        stats.matched(4096);
+        let errors = Errors::new(true, false);
+        let denied = io::Error::from_raw_os_error(libc::EACCES);
+        errors.io(Path::new("/x"), &denied);
+        errors.io(Path::new("/y"), &denied);
+        let s = stats.summary(&errors, 0, Duration::from_secs(2));
+        assert!(s.contains("directories opened    1\n"), "{s}");
+        assert!(s.contains("bytes matched         4096\n"), "{s}");
+        assert!(s.contains("errors                2\n"), "{s}");
+        assert!(s.contains("  Permission denied   2\n"), "{s}");
+        assert!(s.contains("entries/sec           1\n"), "{s}");
+    }
+}
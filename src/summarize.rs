@@ -0,0 +1,130 @@
+// SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
+// SPDX-License-Identifier: MIT
+
+//! `--summarize` — per-directory totals of the matches below, like `du`.
+//!
+//! The walker visits each directory after its whole subtree (as for
+//! `--delete`) and hands it the [`Usage`] its matches added up to; a matched
+//! entry contributes its own apparent size and allocated blocks, so the
+//! totals honor every filter. Each directory up to `--summarize-depth` then
+//! prints as `KIB<TAB>BYTES<TAB>PATH`: allocated KiB first, as `du -k` does,
+//! then the apparent size. Like `du`, a file with several hard links counts
+//! only at the first one the walk reaches.
+
+use crate::meta::{self, Meta};
+use crate::quote;
+use crate::regex::path_to_bytes;
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// The `stat` fields one entry's usage needs.
+pub const MASK: u32 = meta::mask::SIZE
+    | meta::mask::BLOCKS
+    | meta::mask::NLINK
+    | meta::mask::INO;
+
+/// Apparent size and allocated 512-byte blocks.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Usage {
+    pub bytes: u64,
+    pub blocks: u64,
+}
+
+impl Usage {
+    /// One entry's own usage.
+    pub fn of(m: &Meta) -> Self {
+        Usage { bytes: m.size, blocks: m.blocks }
+    }
+
+    /// Both sums.
+    #[must_use]
+    pub fn plus(self, other: Usage) -> Self {
+        Usage {
+            bytes: self.bytes.saturating_add(other.bytes),
+            blocks: self.blocks.saturating_add(other.blocks),
+        }
+    }
+}
+
+/// The `(dev, ino)` of every hard-linked file counted so far, shared by the
+/// workers.
+#[derive(Default)]
+pub struct Links(Mutex<HashSet<(u64, u64)>>);
+
+impl Links {
+    /// A matched entry's own usage, or none for another link to a file
+    /// already counted; `is_dir` entries are never links.
+    pub fn usage(&self, m: &Meta, is_dir: bool) -> Usage {
+        if m.nlink > 1 && !is_dir {
+            let first = self
+                .0
+                .lock()
+                .map_or(true, |mut seen| seen.insert((m.dev, m.ino)));
+            if !first {
+                return Usage::default();
+            }
+        }
+        Usage::of(m)
+    }
+}
+
+/// Appends a directory's line to `out`, ended by `separator`.
+pub fn render(
+    total: Usage,
+    path: &Path,
+    style: quote::Style,
+    separator: u8,
+    out: &mut Vec<u8>,
+) {
+    let _ = write!(out, "{}\t{}\t", total.blocks.div_ceil(2), total.bytes);
+    quote::write(style, &path_to_bytes(path), out);
+    out.push(separator);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_show_kib_then_bytes() {
+        let total = Usage { bytes: 5000, blocks: 9 }
+            .plus(Usage { bytes: 1, blocks: 0 });
+        let mut out = Vec::new();
+        render(
+            total,
+            Path::new("/r/a b"),
+            quote::Style::Shell,
+            b'\n',
+            &mut out,
+        );
+        assert_eq!(out, b"5\t5001\t'/r/a b'\n");
+    }
+
+    #[test]
+    fn hard_links_count_once() {
+        let m = |dev, ino, nlink| Meta {
+            size: 10,
+            mtime: 0,
+            ctime: 0,
+            atime: 0,
+            mode: 0,
+            uid: 0,
+            gid: 0,
+            nlink,
+            ino,
+            blocks: 8,
+            rdev: (0, 0),
+            dev,
+        };
+        let links = Links::default();
+        let own = Usage { bytes: 10, blocks: 8 };
+        assert_eq!(links.usage(&m(1, 7, 2), false), own);
+        assert_eq!(links.usage(&m(1, 7, 2), false), Usage::default());
+        // another device, a single link, a directory: all their own
+        assert_eq!(links.usage(&m(2, 7, 2), false), own);
+        assert_eq!(links.usage(&m(1, 8, 1), false), own);
+        assert_eq!(links.usage(&m(1, 7, 2), true), own);
+    }
+}
@@ -0,0 +1,203 @@
+// SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
+// SPDX-License-Identifier: MIT
+
+//! `--top N --by KEY` — only the N largest (or newest) matches.
+//!
+//! Piping a full listing through `sort | head` costs a line per match; here
+//! each worker keeps its own bounded [`Top`] of at most N candidates, a
+//! `stat` key apiece, and hands it to the output thread when the walk ends.
+//! The output thread merges those few into the final N and prints each with
+//! its key. Ties are broken by path, so the winners are the same on every
+//! run.
+
+use crate::meta::{self, Meta};
+use crate::printf;
+use anyhow::{anyhow, Error};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::io::Write;
+use std::path::Path;
+
+/// What `--by` ranks on.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum By {
+    /// Size in bytes.
+    #[default]
+    Size,
+    /// Modification time.
+    Mtime,
+    /// Access time.
+    Atime,
+    /// Status change time.
+    Ctime,
+}
+
+impl By {
+    /// Parses a `--by` value.
+    ///
+    /// # Errors
+    ///
+    /// An unknown key.
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        match s {
+            "size" => Ok(By::Size),
+            "mtime" => Ok(By::Mtime),
+            "atime" => Ok(By::Atime),
+            "ctime" => Ok(By::Ctime),
+            _ => Err(anyhow!(
+                "invalid --by key '{s}' (expected size, mtime, atime or ctime)"
+            )),
+        }
+    }
+
+    /// The `stat` field the key reads.
+    pub fn mask(self) -> u32 {
+        match self {
+            By::Size => meta::mask::SIZE,
+            By::Mtime => meta::mask::MTIME,
+            By::Atime => meta::mask::ATIME,
+            By::Ctime => meta::mask::CTIME,
+        }
+    }
+
+    /// The key taken from `stat` in the walker thread.
+    pub fn value(self, m: &Meta) -> i64 {
+        match self {
+            By::Size => i64::try_from(m.size).unwrap_or(i64::MAX),
+            By::Mtime => m.mtime,
+            By::Atime => m.atime,
+            By::Ctime => m.ctime,
+        }
+    }
+
+    /// Appends `key` as printed before the path: bytes, or a local
+    /// `YYYY-MM-DD HH:MM:SS` time.
+    pub fn write(self, key: i64, out: &mut Vec<u8>) {
+        match self {
+            By::Size => {
+                let _ = write!(out, "{key}");
+            }
+            By::Mtime | By::Atime | By::Ctime => {
+                printf::strftime(out, key, b"%Y-%m-%d %H:%M:%S\0");
+            }
+        }
+    }
+}
+
+/// Something [`Top`] can rank: a key and, for ties, a path.
+pub trait Ranked {
+    fn key(&self) -> i64;
+    fn path(&self) -> &Path;
+}
+
+// a heap slot, ordered so that the better candidate is the greater one
+struct Slot<T> {
+    item: T,
+    // --reverse: the smallest key wins
+    smallest: bool,
+}
+
+impl<T: Ranked> Ord for Slot<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let by_key = self.item.key().cmp(&other.item.key());
+        let by_key = if self.smallest { by_key.reverse() } else { by_key };
+        // the lower path wins a tie
+        by_key.then_with(|| other.item.path().cmp(self.item.path()))
+    }
+}
+
+impl<T: Ranked> PartialOrd for Slot<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ranked> PartialEq for Slot<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Ranked> Eq for Slot<T> {}
+
+/// The best `n` items pushed so far: the largest keys, or the smallest when
+/// `smallest`.
+pub struct Top<T> {
+    n: usize,
+    smallest: bool,
+    // worst on top, so a better newcomer replaces it in O(log n)
+    heap: BinaryHeap<Reverse<Slot<T>>>,
+}
+
+impl<T: Ranked> Top<T> {
+    /// An empty selection of at most `n`.
+    pub fn new(n: usize, smallest: bool) -> Self {
+        Top { n, smallest, heap: BinaryHeap::with_capacity(n) }
+    }
+
+    /// Offers `item`; it is kept only while it ranks among the best `n`.
+    pub fn push(&mut self, item: T) {
+        let slot = Slot { item, smallest: self.smallest };
+        if self.heap.len() < self.n {
+            self.heap.push(Reverse(slot));
+        } else if let Some(mut worst) = self.heap.peek_mut() {
+            if slot > worst.0 {
+                *worst = Reverse(slot);
+            }
+        }
+    }
+
+    /// The kept items, best first.
+    pub fn into_sorted(self) -> Vec<T> {
+        // ascending Reverse = descending slots = best first
+        self.heap.into_sorted_vec().into_iter().map(|r| r.0.item).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    struct Item(i64, PathBuf);
+
+    impl Ranked for Item {
+        fn key(&self) -> i64 {
+            self.0
+        }
+        fn path(&self) -> &Path {
+            &self.1
+        }
+    }
+
+    fn top(keys: &[(i64, &str)], n: usize, smallest: bool) -> Vec<String> {
+        let mut t = Top::new(n, smallest);
+        for &(k, p) in keys {
+            t.push(Item(k, PathBuf::from(p)));
+        }
+        t.into_sorted()
+            .into_iter()
+            .map(|i| format!("{} {}", i.0, i.1.display()))
+            .collect()
+    }
+
+    #[test]
+    fn keeps_the_largest_best_first() {
+        let keys = [(5, "a"), (1, "b"), (9, "c"), (7, "d"), (3, "e")];
+        assert_eq!(top(&keys, 3, false), ["9 c", "7 d", "5 a"]);
+        assert_eq!(top(&keys, 2, true), ["1 b", "3 e"]);
+        assert_eq!(top(&keys, 9, false).len(), 5);
+    }
+
+    #[test]
+    fn ties_go_to_the_lower_path() {
+        let keys = [(4, "z"), (4, "m"), (4, "a"), (1, "b")];
+        assert_eq!(top(&keys, 2, false), ["4 a", "4 m"]);
+    }
+
+    #[test]
+    fn by_parses_and_rejects_unknown() {
+        assert_eq!(By::parse("ctime").unwrap(), By::Ctime);
+        assert!(By::parse("name").is_err());
+    }
+}
@@ -0,0 +1,132 @@
+// SPDX-FileCopyrightText: 2022 Dinko Korunic <dinko.korunic@gmail.com>
+// SPDX-License-Identifier: MIT
+
+//! `--tree` — matches drawn as an indented hierarchy, like `tree(1)`.
+//!
+//! The walker finishes directories in any order, so the output thread
+//! buffers every match into a [`Tree`] and draws it once the walk is done.
+//! Each match is placed under its starting point by its path components, so
+//! the directories leading to it appear even when the filters dropped them;
+//! siblings are drawn in name order.
+
+use crate::color::{Paint, Palette};
+use crate::quote;
+use crate::regex::path_to_bytes;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default)]
+struct Node {
+    // the --color style of a match; ancestors only placed stay unpainted
+    paint: Paint,
+    children: BTreeMap<OsString, Node>,
+}
+
+/// Matches grouped under their starting points.
+#[derive(Debug, Default)]
+pub struct Tree {
+    roots: BTreeMap<PathBuf, Node>,
+}
+
+impl Tree {
+    /// Places the match at `path`, `depth` levels below its starting point.
+    pub fn insert(&mut self, path: &Path, depth: usize, paint: Paint) {
+        let root = path.ancestors().nth(depth).unwrap_or(Path::new(""));
+        // below a `.` starting point (--relative-to) paths have no prefix
+        let key =
+            if root.as_os_str().is_empty() { Path::new(".") } else { root };
+        let mut node = self.roots.entry(key.to_path_buf()).or_default();
+        if let Ok(rest) = path.strip_prefix(root) {
+            for part in rest.iter() {
+                node = node.children.entry(part.to_owned()).or_default();
+            }
+        }
+        node.paint = paint;
+    }
+
+    /// Appends the drawing to `out`: each starting point (in path order) on
+    /// its own line, then its subtree.
+    pub fn render(
+        &self,
+        style: quote::Style,
+        palette: Option<&Palette>,
+        out: &mut Vec<u8>,
+    ) {
+        let mut draw = Draw { style, palette, prefix: Vec::new(), out };
+        for (root, node) in &self.roots {
+            draw.label(&path_to_bytes(root), node.paint);
+            draw.children(node);
+        }
+    }
+}
+
+struct Draw<'a> {
+    style: quote::Style,
+    palette: Option<&'a Palette>,
+    // the `│   `/`    ` columns of the enclosing levels
+    prefix: Vec<u8>,
+    out: &'a mut Vec<u8>,
+}
+
+impl Draw<'_> {
+    fn label(&mut self, name: &[u8], paint: Paint) {
+        let mut quoted = Vec::new();
+        quote::write(self.style, name, &mut quoted);
+        match self.palette {
+            Some(p) => p.write(paint, &quoted, self.out),
+            None => self.out.extend_from_slice(&quoted),
+        }
+        self.out.push(b'\n');
+    }
+
+    fn children(&mut self, node: &Node) {
+        let last = node.children.len().saturating_sub(1);
+        for (i, (name, child)) in node.children.iter().enumerate() {
+            self.out.extend_from_slice(&self.prefix);
+            let (branch, column) = if i == last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            self.out.extend_from_slice(branch.as_bytes());
+            self.label(&path_to_bytes(Path::new(name)), child.paint);
+            let depth = self.prefix.len();
+            self.prefix.extend_from_slice(column.as_bytes());
+            self.children(child);
+            self.prefix.truncate(depth);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drawn(tree: &Tree) -> String {
+        let mut out = Vec::new();
+        tree.render(quote::Style::Literal, None, &mut out);
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn places_matches_under_their_missing_ancestors() {
+        let mut t = Tree::default();
+        t.insert(Path::new("/r/b/deep/f"), 3, Paint::default());
+        t.insert(Path::new("/r/a"), 1, Paint::default());
+        t.insert(Path::new("/r/b/g"), 2, Paint::default());
+        assert_eq!(
+            drawn(&t),
+            "/r\n├── a\n└── b\n    ├── deep\n    │   └── f\n    └── g\n"
+        );
+    }
+
+    #[test]
+    fn relative_roots_show_as_dot() {
+        let mut t = Tree::default();
+        t.insert(Path::new("x"), 1, Paint::default());
+        t.insert(Path::new("/s"), 0, Paint::default());
+        t.insert(Path::new("."), 0, Paint::default());
+        assert_eq!(drawn(&t), "/s\n.\n└── x\n");
+    }
+}
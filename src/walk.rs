@@ -6,14 +6,17 @@
 
 use crate::args::Args;
 use crate::filetype::EntryType;
-use crate::meta::Meta;
+use crate::meta::{self, Meta};
 use crate::ratelimit::Limiter;
+use crate::report::Errors;
+use crate::stats::Stats;
+use crate::summarize::Usage;
 use crossbeam_deque::{Injector, Steal, Stealer, Worker};
 use crossbeam_utils::Backoff;
 use globset::GlobSet;
 use std::ffi::OsStr;
-use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::thread;
 
@@ -24,9 +27,21 @@ mod platform;
 #[path = "walk/fallback.rs"]
 mod platform;
 
+/// Opens so far, process-wide, that fell back from the parent fd to an
+/// absolute path (fd exhaustion); always 0 off Unix.
+pub(crate) fn abs_fallbacks() -> u64 {
+    #[cfg(unix)]
+    return platform::ABS_FALLBACK_COUNT.load(Ordering::Relaxed) as u64;
+    #[cfg(not(unix))]
+    0
+}
+
 /// Whether traversal should continue or stop entirely.
 pub enum WalkState {
     Continue,
+    /// Continue, adding the entry's own usage to its directories'
+    /// `--summarize` totals.
+    Tally(Usage),
     Quit,
 }
 
@@ -37,6 +52,64 @@ pub struct Entry {
     /// Distance from the starting path: 0 for a command-line root, 1 for its
     /// children, and so on (used by the `--min-depth` gate in the visitor).
     pub depth: usize,
+    /// Where a sequential walk would meet the entry (`--ordered`). `None`
+    /// unless `--ordered`.
+    pub order: Option<Place>,
+    /// `--summarize`: what the matches below a directory tallied, on its
+    /// visit after the subtree. `None` for every other visit.
+    pub subtree: Option<Usage>,
+    /// Index of the starting point the entry was reached from.
+    pub root: usize,
+}
+
+/// An entry's place in its directory (`--ordered`): the [`Listing::dir`] it
+/// was read from ([`STARTS`] for a starting point) and its index in that
+/// directory's `getdents` order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Place {
+    /// The directory read.
+    pub dir: u64,
+    /// Position in that read, from 0.
+    pub index: u32,
+}
+
+/// The [`Place::dir`] the starting points are listed in, in command-line
+/// order; the one for starting point `i` is `i + 1`.
+pub const STARTS: u64 = 0;
+
+/// One directory's read (`--ordered`), handed to [`Visitor::listed`] once
+/// every entry in it has been visited: how many entries it held, and which
+/// of them were descended into, as what directory. Put together, these give
+/// find's sequential order without waiting for the rest of the walk.
+#[derive(Debug)]
+pub struct Listing {
+    /// The directory's id: [`STARTS`], or one handed out as it was found.
+    pub dir: u64,
+    /// How many entries the read returned.
+    pub len: u32,
+    /// `(index, dir)` of each subdirectory, by index.
+    pub subdirs: Vec<(u32, u64)>,
+}
+
+/// What [`walk_parallel`] calls on each worker thread: any
+/// `FnMut(Entry, &StatAt) -> WalkState` closure, or a type that also follows
+/// `--ordered`'s directory listings.
+pub trait Visitor {
+    /// Visits one entry.
+    fn visit(&mut self, entry: Entry, stat: &StatAt) -> WalkState;
+
+    /// `--ordered`: a directory's read is done, and its entries visited.
+    fn listed(&mut self, _listing: Listing) {}
+
+    /// `--ordered` under `--delete`/`--summarize`: the deferred visit of
+    /// directory `dir` (see [`Listing::dir`]) is done.
+    fn finished(&mut self, _dir: u64) {}
+}
+
+impl<F: FnMut(Entry, &StatAt) -> WalkState> Visitor for F {
+    fn visit(&mut self, entry: Entry, stat: &StatAt) -> WalkState {
+        self(entry, stat)
+    }
 }
 
 impl Entry {
@@ -61,9 +134,51 @@ struct Task {
     follow: bool,
     depth: usize,
     root_dev: u64,
+    // index of the starting point, for Entry::root
+    root: usize,
     // (dev, ino) of every ancestor directory; Some only when following
     // symlinks, so the common path stays allocation-free.
     ancestors: Option<Arc<Vec<(u64, u64)>>>,
+    // this directory's deferred visit; Some only under --delete/--summarize
+    post: Option<Arc<PostDir>>,
+    // this directory's Listing::dir; 0 unless --ordered
+    id: u64,
+}
+
+/// A directory whose own visit waits for its whole subtree — the post-order
+/// `--delete` needs, so a directory is removed only once emptied, and that
+/// `--summarize` needs to know its total. Visited by whichever worker drops
+/// `pending` to zero.
+struct PostDir {
+    // the directory's own read, plus one per subdir task queued from it
+    pending: AtomicUsize,
+    path: PathBuf,
+    file_type: EntryType,
+    depth: usize,
+    // anchor for the visit's StatAt; None for a command-line root
+    parent: Option<Arc<platform::DirFd>>,
+    up: Option<Arc<PostDir>>,
+    order: Option<Place>,
+    // its Listing::dir; 0 unless --ordered
+    id: u64,
+    root: usize,
+    // --summarize: the usage tallied below so far
+    bytes: AtomicU64,
+    blocks: AtomicU64,
+}
+
+impl PostDir {
+    fn tally(&self, u: Usage) {
+        self.bytes.fetch_add(u.bytes, Ordering::Relaxed);
+        self.blocks.fetch_add(u.blocks, Ordering::Relaxed);
+    }
+
+    fn tallied(&self) -> Usage {
+        Usage {
+            bytes: self.bytes.load(Ordering::Relaxed),
+            blocks: self.blocks.load(Ordering::Relaxed),
+        }
+    }
 }
 
 /// A lazy, leaf-relative metadata fetch handed to the visitor with each entry.
@@ -73,6 +188,9 @@ struct Task {
 pub struct StatAt<'a> {
     src: StatSrc<'a>,
     follow: bool,
+    // set by `followed`: no falling back to the link itself
+    strict: bool,
+    stats: &'a Stats,
 }
 
 #[derive(Clone, Copy)]
@@ -86,26 +204,55 @@ impl<'a> StatAt<'a> {
         dir: &'a Arc<platform::DirFd>,
         name: &'a OsStr,
         follow: bool,
+        stats: &'a Stats,
     ) -> Self {
-        StatAt { src: StatSrc::Child { dir, name }, follow }
+        StatAt {
+            src: StatSrc::Child { dir, name },
+            follow,
+            strict: false,
+            stats,
+        }
     }
 
-    fn root(path: &'a Path, follow: bool) -> Self {
-        StatAt { src: StatSrc::Root { path }, follow }
+    fn root(path: &'a Path, follow: bool, stats: &'a Stats) -> Self {
+        StatAt { src: StatSrc::Root { path }, follow, strict: false, stats }
     }
 
     /// Fetches the metadata fields selected by `mask` (see [`crate::meta`]).
+    /// Under `-L` a dangling symlink is described by the link itself, as
+    /// find does.
     pub fn fetch(&self, mask: u32) -> std::io::Result<Meta> {
-        match self.src {
+        self.stats.stat_call();
+        self.fetch_uncounted(mask)
+    }
+
+    /// [`fetch`](Self::fetch) left out of the "stat calls" counter, for
+    /// `--stats`' own lookups, which must not change what it reports.
+    pub(crate) fn fetch_uncounted(&self, mask: u32) -> std::io::Result<Meta> {
+        let stat = |follow| match self.src {
             StatSrc::Child { dir, name } => {
-                platform::stat_at(dir, name, self.follow, mask)
+                platform::stat_at(dir, name, follow, mask)
             }
-            StatSrc::Root { path } => {
-                platform::stat_root(path, self.follow, mask)
+            StatSrc::Root { path } => platform::stat_root(path, follow, mask),
+        };
+        match stat(self.follow) {
+            Err(e)
+                if self.follow
+                    && !self.strict
+                    && e.kind() == std::io::ErrorKind::NotFound =>
+            {
+                stat(false)
             }
+            r => r,
         }
     }
 
+    /// The same entry with a final symlink resolved (for `%Y`); a dangling
+    /// one fails instead of falling back to the link.
+    pub fn followed(self) -> Self {
+        StatAt { follow: true, strict: true, ..self }
+    }
+
     /// `faccessat` for the `meta::access` mode bits (`-readable`/…).
     pub fn access(&self, mode: u8) -> bool {
         match self.src {
@@ -116,8 +263,86 @@ impl<'a> StatAt<'a> {
         }
     }
 
-    /// The symlink target (for `-lname`); `None` if not a symlink / unreadable.
-    pub fn readlink(&self) -> Option<std::ffi::OsString> {
+    /// The entry's [`ExecDir`]: the walker's held parent fd, or for a root
+    /// [`ExecDir::at`] its path.
+    pub fn exec_dir(&self) -> ExecDir {
+        match self.src {
+            StatSrc::Child { dir, name } => ExecDir {
+                dir: ExecCwd::Fd(Arc::clone(dir)),
+                leaf: dot_slash(name),
+            },
+            StatSrc::Root { path } => ExecDir::at(path),
+        }
+    }
+
+    /// Removes the entry (for `--delete`): `unlinkat` on the parent fd, with
+    /// `AT_REMOVEDIR` for a directory.
+    ///
+    /// # Errors
+    ///
+    /// The `unlinkat` failure, e.g. a non-empty directory or `EACCES`.
+    pub fn unlink(&self, is_dir: bool) -> std::io::Result<()> {
+        match self.src {
+            StatSrc::Child { dir, name } => {
+                platform::unlink_at(dir, name, is_dir)
+            }
+            StatSrc::Root { path } => platform::unlink_root(path, is_dir),
+        }
+    }
+
+    /// Opens the entry for reading (for `--hash`): `openat` on the parent
+    /// fd, so the path is not resolved again.
+    ///
+    /// # Errors
+    ///
+    /// The `openat` failure, e.g. `EACCES`, or `ELOOP` for an entry swapped
+    /// for a symlink.
+    pub fn open(&self) -> std::io::Result<std::fs::File> {
+        match self.src {
+            StatSrc::Child { dir, name } => {
+                platform::open_file_at(dir, name, self.follow)
+            }
+            StatSrc::Root { path } => {
+                platform::open_file_root(path, self.follow)
+            }
+        }
+    }
+
+    /// A handle that opens the entry again after the visit (for
+    /// `--duplicates`), keeping the parent fd alive until it is dropped.
+    pub fn anchored(&self) -> Anchored {
+        let (dir, name) = match self.src {
+            StatSrc::Child { dir, name } => (Some(Arc::clone(dir)), name),
+            StatSrc::Root { path } => (None, path.as_os_str()),
+        };
+        Anchored { dir, name: PathBuf::from(name), follow: self.follow }
+    }
+
+    /// Whether the entry is empty (for `--empty`): a directory with no
+    /// children, else a zero-byte file. Checked through the parent fd, so it
+    /// holds whatever form the printed path takes.
+    pub fn is_empty(&self, is_dir: bool) -> bool {
+        if !is_dir {
+            return self.fetch(meta::mask::SIZE).is_ok_and(|m| m.size == 0);
+        }
+        match self.src {
+            StatSrc::Child { dir, name } => {
+                platform::empty_dir_at(dir, name, self.follow)
+            }
+            StatSrc::Root { path } => {
+                platform::empty_dir_root(path, self.follow)
+            }
+        }
+        .unwrap_or(false)
+    }
+
+    /// The symlink target (for `-lname`).
+    ///
+    /// # Errors
+    ///
+    /// The `readlinkat` failure: `EINVAL` for a non-symlink, `ENOENT` if the
+    /// entry vanished.
+    pub fn readlink(&self) -> std::io::Result<std::ffi::OsString> {
         match self.src {
             StatSrc::Child { dir, name } => platform::readlink_at(dir, name),
             StatSrc::Root { path } => platform::readlink_root(path),
@@ -125,6 +350,113 @@ impl<'a> StatAt<'a> {
     }
 }
 
+/// An entry kept for reading after the walk: the walker's held parent fd and
+/// the name in it, or a starting point's path, so a path component swapped
+/// since the visit cannot redirect the open.
+#[derive(Clone)]
+pub struct Anchored {
+    dir: Option<Arc<platform::DirFd>>,
+    name: PathBuf,
+    follow: bool,
+}
+
+impl Anchored {
+    /// The entry at `path`, opened by path with no fd held.
+    pub fn at(path: &Path) -> Anchored {
+        Anchored { dir: None, name: path.to_path_buf(), follow: false }
+    }
+
+    /// Opens the entry for reading, as [`StatAt::open`] does.
+    ///
+    /// # Errors
+    ///
+    /// The `openat` failure, e.g. `ENOENT` once the entry is gone.
+    pub fn open(&self) -> std::io::Result<std::fs::File> {
+        match &self.dir {
+            Some(dir) => {
+                platform::open_file_at(dir, self.name.as_os_str(), self.follow)
+            }
+            None => platform::open_file_root(&self.name, self.follow),
+        }
+    }
+}
+
+/// Where an `-execdir` command runs for one entry: the entry's parent
+/// directory — the walker's own held fd for children — and the `./leaf` that
+/// stands in for `{}` there.
+#[derive(Clone)]
+pub struct ExecDir {
+    dir: ExecCwd,
+    leaf: PathBuf,
+}
+
+#[derive(Clone)]
+enum ExecCwd {
+    Fd(Arc<platform::DirFd>),
+    // roots, and matches held for a sorted output, keep no fd; the parent is
+    // looked up by path
+    Path(PathBuf),
+}
+
+impl ExecDir {
+    /// The [`ExecDir`] of the entry at `path`, holding no fd: the parent is
+    /// entered by path when the command starts (the current directory for a
+    /// bare name; `/` runs in itself).
+    pub fn at(path: &Path) -> ExecDir {
+        match path.components().next_back() {
+            Some(Component::RootDir | Component::Prefix(_)) | None => {
+                ExecDir {
+                    dir: ExecCwd::Path(path.to_path_buf()),
+                    leaf: path.to_path_buf(),
+                }
+            }
+            Some(last) => {
+                let parent = path.parent().unwrap_or(Path::new(""));
+                let parent = if parent.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    parent
+                };
+                ExecDir {
+                    dir: ExecCwd::Path(parent.to_path_buf()),
+                    leaf: dot_slash(last.as_os_str()),
+                }
+            }
+        }
+    }
+
+    /// The entry as seen from its parent directory (`./leaf`).
+    pub fn leaf(&self) -> &Path {
+        &self.leaf
+    }
+
+    /// Whether both entries share a parent directory.
+    pub fn same_dir(&self, other: &ExecDir) -> bool {
+        match (&self.dir, &other.dir) {
+            (ExecCwd::Fd(a), ExecCwd::Fd(b)) => Arc::ptr_eq(a, b),
+            (ExecCwd::Path(a), ExecCwd::Path(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    /// Sets `cmd` to start in the directory.
+    pub fn enter(&self, cmd: &mut std::process::Command) {
+        match &self.dir {
+            ExecCwd::Fd(dir) => platform::enter_dir(cmd, dir),
+            ExecCwd::Path(dir) => {
+                cmd.current_dir(dir);
+            }
+        }
+    }
+}
+
+/// `./name`, so a leaf starting with `-` is never taken for an option.
+fn dot_slash(name: &OsStr) -> PathBuf {
+    let mut leaf = std::ffi::OsString::from("./");
+    leaf.push(name);
+    PathBuf::from(leaf)
+}
+
 /// Immutable shared state for one `walk_parallel` run, bundled so the
 /// recursive worker functions keep small signatures.
 struct WalkCtx<'a> {
@@ -134,19 +466,55 @@ struct WalkCtx<'a> {
     limiter: Option<&'a Limiter>,
     // matched against each child's file name; a matched dir is pruned
     exclude: Option<&'a GlobSet>,
+    errors: &'a Errors,
+    stats: &'a Stats,
+    // see `bare_leaves`
+    bare_leaves: bool,
+    // --ordered: the next Listing::dir to hand out, and the starting
+    // points' own listing, delivered by whichever worker starts first
+    next_dir: AtomicU64,
+    starts: std::sync::Mutex<Option<Listing>>,
+}
+
+/// Whether entries that are not descended into can go without a path: under
+/// `--count`, when no filter reads a name or path (a type, depth or
+/// `--exclude` test needs only the leaf), so counting a leaf allocates
+/// nothing.
+fn bare_leaves(args: &Args) -> bool {
+    args.count.is_some()
+        && [
+            &args.name,
+            &args.iname,
+            &args.regex,
+            &args.iregex,
+            &args.path_glob,
+            &args.ipath_glob,
+            &args.lname,
+            &args.ilname,
+        ]
+        .iter()
+        .all(|p| p.is_none())
+        && args.expr.is_none()
+        && !args.meta.is_active()
+        && args.contains.is_none()
+        && args.mime.is_none()
+        && args.class.is_none()
 }
 
 /// Walks `roots` in parallel, invoking a fresh per-thread visitor (from
-/// `make_visitor`) for every entry. Directory read/open errors are skipped.
+/// `make_visitor`) for every entry. Directory read/open errors are reported
+/// to `errors` and the walk carries on past them; `stats` counts the work.
 pub fn walk_parallel<F, V>(
     args: &Args,
     roots: &[&Path],
     limiter: Option<&Limiter>,
     exclude: Option<&GlobSet>,
+    errors: &Errors,
+    stats: &Stats,
     make_visitor: F,
 ) where
     F: Fn() -> V + Sync,
-    V: FnMut(Entry, &StatAt) -> WalkState + Send,
+    V: Visitor,
 {
     let n_workers = (args.threads - 1).max(1);
     let injector = Injector::new();
@@ -154,14 +522,44 @@ pub fn walk_parallel<F, V>(
     // the termination signal. `quit` is the early-stop signal (Quit visitor).
     let pending = AtomicUsize::new(0);
     let quit = AtomicBool::new(false);
+    let mut starts = Listing {
+        dir: STARTS,
+        len: u32::try_from(roots.len()).unwrap_or(u32::MAX),
+        subdirs: Vec::new(),
+    };
 
-    for root in roots {
-        let Ok((dev, _ino)) = platform::path_id(root) else {
-            continue;
+    for (i, root) in roots.iter().enumerate() {
+        let dev = match platform::path_id(root) {
+            Ok((dev, _ino)) => dev,
+            Err(e) => {
+                errors.start_io(root, &e);
+                continue;
+            }
         };
         // Ancestors hold the parent chain only; `descend` appends each
         // directory's own id before recursing, so a root starts empty.
         let ancestors = args.follow_symlinks.then(|| Arc::new(Vec::new()));
+        let index = u32::try_from(i).unwrap_or(u32::MAX);
+        let order = args.ordered.then_some(Place { dir: STARTS, index });
+        let id = if args.ordered { i as u64 + 1 } else { 0 };
+        if args.ordered {
+            starts.subdirs.push((index, id));
+        }
+        let post = (args.delete || args.summarize).then(|| {
+            Arc::new(PostDir {
+                pending: AtomicUsize::new(1),
+                path: root.to_path_buf(),
+                file_type: EntryType::Dir,
+                depth: 0,
+                parent: None,
+                up: None,
+                order,
+                id,
+                root: i,
+                bytes: AtomicU64::new(0),
+                blocks: AtomicU64::new(0),
+            })
+        });
         pending.fetch_add(1, Ordering::SeqCst);
         injector.push(Task {
             path: root.to_path_buf(),
@@ -170,7 +568,10 @@ pub fn walk_parallel<F, V>(
             follow: true,
             depth: 0,
             root_dev: dev,
+            root: i,
             ancestors,
+            post,
+            id,
         });
     }
 
@@ -179,8 +580,18 @@ pub fn walk_parallel<F, V>(
     let stealers: Vec<Stealer<Task>> =
         workers.iter().map(Worker::stealer).collect();
 
-    let ctx =
-        WalkCtx { args, pending: &pending, quit: &quit, limiter, exclude };
+    let ctx = WalkCtx {
+        args,
+        pending: &pending,
+        quit: &quit,
+        limiter,
+        exclude,
+        errors,
+        stats,
+        bare_leaves: bare_leaves(args),
+        next_dir: AtomicU64::new(roots.len() as u64 + 1),
+        starts: std::sync::Mutex::new(args.ordered.then_some(starts)),
+    };
 
     thread::scope(|scope| {
         for worker in workers {
@@ -196,13 +607,17 @@ pub fn walk_parallel<F, V>(
     });
 }
 
-fn run_worker<V: FnMut(Entry, &StatAt) -> WalkState>(
+fn run_worker<V: Visitor>(
     ctx: &WalkCtx,
     local: &Worker<Task>,
     injector: &Injector<Task>,
     stealers: &[Stealer<Task>],
     visitor: &mut V,
 ) {
+    let starts = ctx.starts.lock().map(|mut s| s.take());
+    if let Ok(Some(listing)) = starts {
+        visitor.listed(listing);
+    }
     let backoff = Backoff::new();
     loop {
         if ctx.quit.load(Ordering::Relaxed) {
@@ -250,21 +665,33 @@ fn descends_into(ty: EntryType, args: &Args) -> bool {
     }
 }
 
-fn process<V: FnMut(Entry, &StatAt) -> WalkState>(
+fn process<V: Visitor>(
     ctx: &WalkCtx,
     task: Task,
     local: &Worker<Task>,
     visitor: &mut V,
 ) {
     // children are emitted by their parent's read loop; a root has none, so it
-    // self-emits here (as Dir — a non-dir root just fails to open below)
+    // self-emits here (as Dir — a non-dir root just fails to open below).
+    // Under --delete every directory is instead visited after its subtree.
     if task.parent.is_none() {
-        let stat = StatAt::root(&task.path, ctx.args.follow_symlinks);
-        if let WalkState::Quit = visitor(
+        ctx.stats.entry_seen();
+    }
+    if task.parent.is_none() && task.post.is_none() {
+        let stat =
+            StatAt::root(&task.path, ctx.args.follow_symlinks, ctx.stats);
+        let index = u32::try_from(task.root).unwrap_or(u32::MAX);
+        if let WalkState::Quit = visitor.visit(
             Entry {
                 path: task.path.clone(),
                 file_type: EntryType::Dir,
                 depth: task.depth,
+                order: ctx
+                    .args
+                    .ordered
+                    .then_some(Place { dir: STARTS, index }),
+                subtree: None,
+                root: task.root,
             },
             &stat,
         ) {
@@ -272,14 +699,76 @@ fn process<V: FnMut(Entry, &StatAt) -> WalkState>(
             return;
         }
     }
-    descend(ctx, &task, local, visitor);
+    // --ordered: every task lists its directory, even one that could not
+    // be read, so the output never waits on it
+    let mut listing = ctx.args.ordered.then(|| Listing {
+        dir: task.id,
+        len: 0,
+        subdirs: Vec::new(),
+    });
+    descend(ctx, &task, local, visitor, listing.as_mut());
+    if let Some(l) = listing {
+        visitor.listed(l);
+    }
+    if let Some(post) = &task.post {
+        finish_post(ctx, post, visitor);
+    }
 }
 
-fn descend<V: FnMut(Entry, &StatAt) -> WalkState>(
+/// Counts one finished piece of `post`'s subtree; the last one visits the
+/// directory and then, in turn, finishes its parent.
+fn finish_post<V: Visitor>(
+    ctx: &WalkCtx,
+    post: &Arc<PostDir>,
+    visitor: &mut V,
+) {
+    let mut cur = Some(post);
+    while let Some(node) = cur {
+        if node.pending.fetch_sub(1, Ordering::AcqRel) != 1
+            || ctx.quit.load(Ordering::Relaxed)
+        {
+            return;
+        }
+        let follow = ctx.args.follow_symlinks;
+        let leaf = node.path.file_name().unwrap_or(node.path.as_os_str());
+        let stat = match &node.parent {
+            Some(dir) => StatAt::child(dir, leaf, follow, ctx.stats),
+            None => StatAt::root(&node.path, follow, ctx.stats),
+        };
+        let below = node.tallied();
+        let entry = Entry {
+            path: node.path.clone(),
+            file_type: node.file_type,
+            depth: node.depth,
+            order: node.order,
+            subtree: Some(below),
+            root: node.root,
+        };
+        let own = match visitor.visit(entry, &stat) {
+            WalkState::Quit => {
+                ctx.quit.store(true, Ordering::Relaxed);
+                return;
+            }
+            WalkState::Tally(u) => u,
+            WalkState::Continue => Usage::default(),
+        };
+        if ctx.args.ordered {
+            visitor.finished(node.id);
+        }
+        // the subtree counts toward the parent even when this dir is no match
+        if let Some(up) = &node.up {
+            up.tally(below.plus(own));
+        }
+        cur = node.up.as_ref();
+    }
+}
+
+fn descend<V: Visitor>(
     ctx: &WalkCtx,
     task: &Task,
     local: &Worker<Task>,
     visitor: &mut V,
+    mut listing: Option<&mut Listing>,
 ) {
     // At depth == max we still emit (the parent did) but never read children.
     if let Some(max) = ctx.args.max_depth {
@@ -304,21 +793,38 @@ fn descend<V: FnMut(Entry, &StatAt) -> WalkState>(
             platform::open_child(parent, leaf, &task.path, task.follow)
         }
     };
-    let Ok(fd) = opened else {
-        return;
+    let fd = match opened {
+        Ok(fd) => fd,
+        Err(e) => {
+            match task.parent {
+                _ if quiet_open_error(task, &e) => {}
+                None => ctx.errors.start_io(&task.path, &e),
+                Some(_) => ctx.errors.io(&task.path, &e),
+            }
+            return;
+        }
     };
+    ctx.stats.dir_opened();
     // anchors this dir's children; refcounting frees the fd once its last
     // still-queued subdir is opened
     let dir = Arc::new(fd);
-    let Ok((dev, ino)) = platform::dir_id(&dir) else {
-        return;
+    let (dev, ino) = match platform::dir_id(&dir) {
+        Ok(id) => id,
+        Err(e) => {
+            ctx.errors.io(&task.path, &e);
+            return;
+        }
     };
     if ctx.args.one_filesystem && dev != task.root_dev {
         return;
     }
     if let Some(anc) = &task.ancestors {
         if anc.contains(&(dev, ino)) {
-            return; // symlink cycle
+            ctx.errors.error(format_args!(
+                "{}: file system loop detected",
+                task.path.display()
+            ));
+            return;
         }
     }
     let child_ancestors = task.ancestors.as_ref().map(|a| {
@@ -334,44 +840,139 @@ fn descend<V: FnMut(Entry, &StatAt) -> WalkState>(
     // emit every entry inline; enqueue a descend task only for dirs / followed
     // symlink-dirs
     let follow = ctx.args.follow_symlinks;
-    let _ = platform::for_each_entry(&dir, &task.path, |path, leaf, ty| {
-        // --exclude: skip the entry; a matched dir prunes the subtree (no
-        // task → no opendir). Roots never reach here, so are always kept.
-        if let Some(ex) = ctx.exclude {
-            if ex.is_match(leaf) {
-                return true;
+    let mut index: u32 = 0;
+    // --ordered: subdirs are queued once the read is done, first on top, so
+    // this worker goes on in the order the output needs
+    let mut held = Vec::new();
+    let listed = platform::for_each_entry(
+        &dir,
+        &task.path,
+        ctx.errors,
+        ctx.stats,
+        |leaf, ty| {
+            ctx.stats.entry_seen();
+            // --ordered: this entry's place in a sequential walk
+            let here = index;
+            let order =
+                listing.as_ref().map(|_| Place { dir: task.id, index: here });
+            index = index.saturating_add(1);
+            if let Some(l) = listing.as_deref_mut() {
+                l.len = index;
             }
-        }
-        // compute before `path` moves into Entry; only descenders clone it
-        let descend_path = (enqueue_children && descends_into(ty, ctx.args))
-            .then(|| path.clone());
-        // `leaf` anchors the lazy statx on the parent fd
-        let stat = StatAt::child(&dir, leaf, follow);
-        if let WalkState::Quit =
-            visitor(Entry { path, file_type: ty, depth: child_depth }, &stat)
-        {
-            ctx.quit.store(true, Ordering::Relaxed);
-            return false;
-        }
-        if let Some(child_path) = descend_path {
-            ctx.pending.fetch_add(1, Ordering::SeqCst);
-            local.push(Task {
-                path: child_path,
-                parent: Some(Arc::clone(&dir)),
-                follow: ty == EntryType::Symlink,
-                depth: child_depth,
-                root_dev: task.root_dev,
-                ancestors: child_ancestors.clone(),
-            });
-        }
-        true
-    });
+            // --exclude: skip the entry; a matched dir prunes the subtree (no
+            // task → no opendir). Roots never reach here, so are always kept.
+            if let Some(ex) = ctx.exclude {
+                if ex.is_match(leaf) {
+                    return true;
+                }
+            }
+            let descends = enqueue_children && descends_into(ty, ctx.args);
+            let path = if ctx.bare_leaves && !descends {
+                PathBuf::new()
+            } else {
+                task.path.join(leaf)
+            };
+            // compute before `path` moves into Entry; only descenders clone it
+            let descend_path = descends.then(|| path.clone());
+            // --ordered: a descended entry lists its own directory later
+            let id = match listing.as_deref_mut() {
+                Some(l) if descends => {
+                    let id = ctx.next_dir.fetch_add(1, Ordering::Relaxed);
+                    l.subdirs.push((here, id));
+                    id
+                }
+                _ => 0,
+            };
+            // a descended dir under --delete is visited by its own task, later
+            let post = match (&task.post, &descend_path) {
+                (Some(up), Some(_)) => {
+                    up.pending.fetch_add(1, Ordering::AcqRel);
+                    Some(Arc::new(PostDir {
+                        pending: AtomicUsize::new(1),
+                        path,
+                        file_type: ty,
+                        depth: child_depth,
+                        parent: Some(Arc::clone(&dir)),
+                        up: Some(Arc::clone(up)),
+                        order,
+                        id,
+                        root: task.root,
+                        bytes: AtomicU64::new(0),
+                        blocks: AtomicU64::new(0),
+                    }))
+                }
+                _ => {
+                    // `leaf` anchors the lazy statx on the parent fd
+                    let stat = StatAt::child(&dir, leaf, follow, ctx.stats);
+                    let entry = Entry {
+                        path,
+                        file_type: ty,
+                        depth: child_depth,
+                        order,
+                        subtree: None,
+                        root: task.root,
+                    };
+                    match visitor.visit(entry, &stat) {
+                        WalkState::Quit => {
+                            ctx.quit.store(true, Ordering::Relaxed);
+                            return false;
+                        }
+                        WalkState::Tally(u) => {
+                            if let Some(up) = &task.post {
+                                up.tally(u);
+                            }
+                        }
+                        WalkState::Continue => {}
+                    }
+                    None
+                }
+            };
+            if let Some(child_path) = descend_path {
+                ctx.pending.fetch_add(1, Ordering::SeqCst);
+                let child = Task {
+                    path: child_path,
+                    parent: Some(Arc::clone(&dir)),
+                    follow: ty == EntryType::Symlink,
+                    depth: child_depth,
+                    root_dev: task.root_dev,
+                    root: task.root,
+                    ancestors: child_ancestors.clone(),
+                    post,
+                    id,
+                };
+                if ctx.args.ordered {
+                    held.push(child);
+                } else {
+                    local.push(child);
+                }
+            }
+            true
+        },
+    );
+    for child in held.into_iter().rev() {
+        local.push(child);
+    }
+    if let Err(e) = listed {
+        ctx.errors.io(&task.path, &e);
+    }
+}
+
+/// Open failures that are not errors: a non-directory root (emitted, just
+/// not descended), and under `--follow-symlinks` a link that turns out to
+/// point at a non-directory or nowhere, which find treats as the link itself.
+fn quiet_open_error(task: &Task, err: &std::io::Error) -> bool {
+    match err.kind() {
+        std::io::ErrorKind::NotADirectory => true,
+        std::io::ErrorKind::NotFound => task.follow && task.parent.is_some(),
+        _ => false,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::sync::Mutex;
+    use std::time::Duration;
     use tempfile::TempDir;
 
     // Serializes tests that touch the process-global absolute-fallback seam
@@ -392,25 +993,69 @@ mod tests {
             max_results: None,
             name: None,
             regex: None,
+            iname: None,
+            iregex: None,
             case_insensitive: false,
+            any: false,
             file_type: vec![],
             meta: crate::meta::Predicates::default(),
             path_glob: None,
+            ipath_glob: None,
             lname: None,
+            ilname: None,
             access: 0,
+            contains: None,
+            max_filesize: None,
+            mime: None,
+            class: None,
             exclude: None,
             null: false,
+            printf: None,
+            json: None,
+            ls: false,
+            tree: false,
+            summarize: false,
+            summarize_depth: None,
+            print: true,
+            exec: vec![],
+            jobs: 1,
+            group_output: false,
+            ignore_readdir_race: false,
+            quiet_errors: false,
+            stats: false,
+            delete: false,
+            sort: None,
+            ordered: false,
+            reverse: false,
+            top: None,
+            count: None,
+            histogram: None,
+            duplicates: false,
+            hash: None,
+            by: crate::top::By::Size,
+            path_style: crate::pathstyle::Style::Absolute,
+            quote: None,
+            color: crate::color::When::Never,
+            expr: None,
         }
     }
 
     fn collect(args: &Args, roots: &[&Path]) -> Vec<PathBuf> {
         let sink = Mutex::new(Vec::new());
-        walk_parallel(args, roots, None, None, || {
-            |e: Entry, _: &StatAt| {
-                sink.lock().unwrap().push(e.path);
-                WalkState::Continue
-            }
-        });
+        walk_parallel(
+            args,
+            roots,
+            None,
+            None,
+            &Errors::default(),
+            &Stats::default(),
+            || {
+                |e: Entry, _: &StatAt| {
+                    sink.lock().unwrap().push(e.path);
+                    WalkState::Continue
+                }
+            },
+        );
         sink.into_inner().unwrap()
     }
 
@@ -418,12 +1063,20 @@ mod tests {
     // stamped on it (0 = root, 1 = its children, …).
     fn collect_depths(args: &Args, roots: &[&Path]) -> Vec<(PathBuf, usize)> {
         let sink = Mutex::new(Vec::new());
-        walk_parallel(args, roots, None, None, || {
-            |e: Entry, _: &StatAt| {
-                sink.lock().unwrap().push((e.path, e.depth));
-                WalkState::Continue
-            }
-        });
+        walk_parallel(
+            args,
+            roots,
+            None,
+            None,
+            &Errors::default(),
+            &Stats::default(),
+            || {
+                |e: Entry, _: &StatAt| {
+                    sink.lock().unwrap().push((e.path, e.depth));
+                    WalkState::Continue
+                }
+            },
+        );
         sink.into_inner().unwrap()
     }
 
@@ -544,12 +1197,20 @@ mod tests {
         roots: &[&Path],
     ) -> Vec<(PathBuf, EntryType)> {
         let sink = Mutex::new(Vec::new());
-        walk_parallel(args, roots, None, None, || {
-            |e: Entry, _: &StatAt| {
-                sink.lock().unwrap().push((e.path, e.file_type));
-                WalkState::Continue
-            }
-        });
+        walk_parallel(
+            args,
+            roots,
+            None,
+            None,
+            &Errors::default(),
+            &Stats::default(),
+            || {
+                |e: Entry, _: &StatAt| {
+                    sink.lock().unwrap().push((e.path, e.file_type));
+                    WalkState::Continue
+                }
+            },
+        );
         sink.into_inner().unwrap()
     }
 
@@ -604,6 +1265,233 @@ mod tests {
         assert_eq!(got.len(), 6, "no duplicates");
     }
 
+    #[test]
+    fn delete_visits_directories_after_their_subtree() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp.path().join("a/b/c")).unwrap();
+        std::fs::create_dir_all(tmp.path().join("d")).unwrap();
+        std::fs::write(tmp.path().join("a/b/c/f"), b"x").unwrap();
+        std::fs::write(tmp.path().join("a/g"), b"x").unwrap();
+
+        let mut args = base_args(8);
+        args.delete = true;
+        let got = collect(&args, &[tmp.path()]);
+        assert_eq!(got.len(), 7, "each entry exactly once");
+        let at = |p: &Path| got.iter().position(|g| g == p).unwrap();
+        for p in &got {
+            if let Ok(rest) = p.strip_prefix(tmp.path()) {
+                for anc in rest.ancestors().skip(1) {
+                    assert!(at(&tmp.path().join(anc)) > at(p), "{p:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stats_count_opened_directories_seen_entries_and_stats() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp.path().join("a/b")).unwrap();
+        std::fs::write(tmp.path().join("a/f"), b"x").unwrap();
+        let stats = Stats::new(true);
+        walk_parallel(
+            &base_args(2),
+            &[tmp.path()],
+            None,
+            None,
+            &Errors::default(),
+            &stats,
+            || {
+                |_: Entry, stat: &StatAt| {
+                    let _ = stat.fetch(meta::mask::SIZE);
+                    WalkState::Continue
+                }
+            },
+        );
+        let s = stats.summary(&Errors::default(), 0, Duration::ZERO);
+        assert!(s.contains("directories opened    3\n"), "{s}");
+        assert!(s.contains("entries seen          4\n"), "{s}");
+        assert!(s.contains("stat calls            4\n"), "{s}");
+    }
+
+    #[test]
+    fn stats_bytes_matched_are_not_counted_as_stat_calls() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("f"), b"xyz").unwrap();
+        let stats = Stats::new(true);
+        walk_parallel(
+            &base_args(2),
+            &[tmp.path()],
+            None,
+            None,
+            &Errors::default(),
+            &stats,
+            || {
+                // a name-only search: every entry matches, nothing stats
+                |e: Entry, stat: &StatAt| {
+                    stat.stats.matched_entry(e.file_type, stat, None);
+                    WalkState::Continue
+                }
+            },
+        );
+        let s = stats.summary(&Errors::default(), 0, Duration::ZERO);
+        assert!(s.contains("bytes matched         3\n"), "{s}");
+        assert!(s.contains("stat calls            0\n"), "{s}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn anchored_entries_open_through_their_parent_fd() {
+        use std::io::Read;
+        let tmp = TempDir::new().unwrap();
+        std::fs::create_dir(tmp.path().join("d")).unwrap();
+        std::fs::write(tmp.path().join("d/f"), b"xyz").unwrap();
+        let kept = Mutex::new(Vec::new());
+        walk_parallel(
+            &base_args(2),
+            &[tmp.path()],
+            None,
+            None,
+            &Errors::default(),
+            &Stats::new(false),
+            || {
+                |e: Entry, stat: &StatAt| {
+                    if e.file_type == EntryType::File {
+                        kept.lock().unwrap().push(stat.anchored());
+                    }
+                    WalkState::Continue
+                }
+            },
+        );
+        // the path no longer leads there; the held fd still does
+        std::fs::rename(tmp.path().join("d"), tmp.path().join("e")).unwrap();
+        let kept = kept.into_inner().unwrap();
+        let mut got = String::new();
+        kept[0].open().unwrap().read_to_string(&mut got).unwrap();
+        assert_eq!(got, "xyz");
+    }
+
+    #[test]
+    fn stats_reuse_a_size_fetched_before_the_entry_went() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("f"), b"xyz").unwrap();
+        let stats = Stats::new(true);
+        walk_parallel(
+            &base_args(2),
+            &[tmp.path()],
+            None,
+            None,
+            &Errors::default(),
+            &stats,
+            || {
+                // as --delete --size does: the size, then the unlink
+                |e: Entry, stat: &StatAt| {
+                    if e.file_type == EntryType::File {
+                        let m = stat.fetch(meta::mask::SIZE).unwrap();
+                        stat.unlink(false).unwrap();
+                        stat.stats.matched_entry(
+                            e.file_type,
+                            stat,
+                            Some(m.size),
+                        );
+                    }
+                    WalkState::Continue
+                }
+            },
+        );
+        let s = stats.summary(&Errors::default(), 0, Duration::ZERO);
+        assert!(s.contains("bytes matched         3\n"), "{s}");
+        assert!(s.contains("stat calls            1\n"), "{s}");
+    }
+
+    #[test]
+    fn summarize_hands_each_directory_its_subtree_tally() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp.path().join("a/b")).unwrap();
+        std::fs::write(tmp.path().join("a/b/f"), b"x").unwrap();
+        std::fs::write(tmp.path().join("g"), b"x").unwrap();
+        let mut args = base_args(4);
+        args.summarize = true;
+        let sink = Mutex::new(Vec::new());
+        walk_parallel(
+            &args,
+            &[tmp.path()],
+            None,
+            None,
+            &Errors::default(),
+            &Stats::default(),
+            || {
+                |e: Entry, _: &StatAt| {
+                    if let Some(u) = e.subtree {
+                        sink.lock().unwrap().push((e.path, u.bytes));
+                    }
+                    // every entry counts one byte
+                    WalkState::Tally(Usage { bytes: 1, blocks: 0 })
+                }
+            },
+        );
+        let mut got = sink.into_inner().unwrap();
+        got.sort();
+        let root = tmp.path();
+        assert_eq!(
+            got,
+            [
+                (root.to_path_buf(), 4),
+                (root.join("a"), 2),
+                (root.join("a/b"), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn count_leaves_come_without_paths_unless_a_filter_reads_them() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::create_dir(tmp.path().join("d")).unwrap();
+        std::fs::write(tmp.path().join("d/f"), b"x").unwrap();
+        let mut args = base_args(2);
+        args.count = Some(crate::count::By::Root);
+        let got = collect(&args, &[tmp.path()]);
+        assert!(got.contains(&tmp.path().join("d")));
+        assert!(got.contains(&PathBuf::new()), "{got:?}");
+        args.name = Some(vec!["f".to_string()]);
+        let got = collect(&args, &[tmp.path()]);
+        assert!(got.contains(&tmp.path().join("d/f")), "{got:?}");
+    }
+
+    #[test]
+    fn missing_root_and_symlink_loop_are_reported() {
+        let tmp = TempDir::new().unwrap();
+        let errors = Errors::new(true, false);
+        walk_parallel(
+            &base_args(2),
+            &[&tmp.path().join("gone")],
+            None,
+            None,
+            &errors,
+            &Stats::default(),
+            || |_: Entry, _: &StatAt| WalkState::Continue,
+        );
+        assert_eq!(errors.count(), 1);
+
+        #[cfg(unix)]
+        {
+            std::fs::create_dir(tmp.path().join("a")).unwrap();
+            std::os::unix::fs::symlink("..", tmp.path().join("a/up")).unwrap();
+            let mut args = base_args(2);
+            args.follow_symlinks = true;
+            let errors = Errors::new(true, false);
+            walk_parallel(
+                &args,
+                &[tmp.path()],
+                None,
+                None,
+                &errors,
+                &Stats::default(),
+                || |_: Entry, _: &StatAt| WalkState::Continue,
+            );
+            assert_eq!(errors.count(), 1, "one loop, reported once");
+        }
+    }
+
     #[test]
     fn max_depth_zero_emits_only_root() {
         let tmp = TempDir::new().unwrap();
@@ -692,12 +1580,20 @@ mod tests {
             crate::glob::build_glob_set(Some(&pats), args.case_insensitive)
                 .unwrap();
         let sink = Mutex::new(Vec::new());
-        walk_parallel(args, roots, None, Some(&set), || {
-            |e: Entry, _: &StatAt| {
-                sink.lock().unwrap().push(e.path);
-                WalkState::Continue
-            }
-        });
+        walk_parallel(
+            args,
+            roots,
+            None,
+            Some(&set),
+            &Errors::default(),
+            &Stats::default(),
+            || {
+                |e: Entry, _: &StatAt| {
+                    sink.lock().unwrap().push(e.path);
+                    WalkState::Continue
+                }
+            },
+        );
         sink.into_inner().unwrap()
     }
 
@@ -828,6 +1724,8 @@ mod tests {
             &[tmp.path()],
             Some(&limiter),
             None,
+            &Errors::default(),
+            &Stats::default(),
             || {
                 |e: Entry, _: &StatAt| {
                     sink.lock().unwrap().push(e.path);
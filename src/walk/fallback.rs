@@ -11,10 +11,13 @@
 
 use crate::filetype::EntryType;
 use crate::meta::Meta;
+use crate::report::Errors;
+use crate::stats::Stats;
 use std::ffi::OsStr;
 use std::hash::{Hash, Hasher};
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// The shareable directory anchor. With no fd to carry, it is the path; the
 /// walker still wraps it in `Arc` for a uniform cross-platform signature.
@@ -55,22 +58,30 @@ pub(crate) fn dir_id(d: &DirFd) -> io::Result<(u64, u64)> {
 pub(crate) fn for_each_entry(
     d: &DirFd,
     parent: &Path,
-    mut f: impl FnMut(PathBuf, &OsStr, EntryType) -> bool,
+    errors: &Errors,
+    stats: &Stats,
+    mut f: impl FnMut(&OsStr, EntryType) -> bool,
 ) -> io::Result<()> {
     for entry in std::fs::read_dir(d)? {
         let entry = entry?;
         let name = entry.file_name();
-        let path = parent.join(&name);
         let ty = match entry.file_type() {
             Ok(ft) => map_type(ft),
-            // DT_UNKNOWN equivalent: resolve the entry's own type; skip on
-            // failure.
-            Err(_) => match std::fs::symlink_metadata(&path) {
-                Ok(m) => map_type(m.file_type()),
-                Err(_) => continue,
-            },
+            // DT_UNKNOWN equivalent: resolve the entry's own type; report
+            // and skip on failure.
+            Err(_) => {
+                stats.dt_unknown();
+                let path = parent.join(&name);
+                match std::fs::symlink_metadata(&path) {
+                    Ok(m) => map_type(m.file_type()),
+                    Err(e) => {
+                        errors.io(&path, &e);
+                        continue;
+                    }
+                }
+            }
         };
-        if !f(path, &name, ty) {
+        if !f(&name, ty) {
             break;
         }
     }
@@ -113,13 +124,17 @@ fn meta_of(path: &Path, follow: bool) -> io::Result<Meta> {
         mtime: secs(md.modified()),
         ctime: secs(md.created()),
         atime: secs(md.accessed()),
-        // mode/uid/gid/nlink/ino need Unix metadata; those predicates are
-        // rejected at parse off-Unix, so zero placeholders are never read.
+        // mode/uid/gid/nlink/ino/blocks/rdev need Unix metadata; those
+        // predicates are rejected at parse off-Unix, so zero placeholders are
+        // never read.
         mode: 0,
         uid: 0,
         gid: 0,
         nlink: 0,
         ino: 0,
+        blocks: 0,
+        rdev: (0, 0),
+        dev: 0,
     })
 }
 
@@ -137,12 +152,64 @@ pub(crate) fn access_root(_path: &Path, _mode: u8) -> bool {
 pub(crate) fn readlink_at(
     dir: &DirFd,
     name: &OsStr,
-) -> Option<std::ffi::OsString> {
-    std::fs::read_link(dir.join(name)).ok().map(PathBuf::into_os_string)
+) -> io::Result<std::ffi::OsString> {
+    readlink_root(&dir.join(name))
 }
 
-pub(crate) fn readlink_root(path: &Path) -> Option<std::ffi::OsString> {
-    std::fs::read_link(path).ok().map(PathBuf::into_os_string)
+pub(crate) fn readlink_root(path: &Path) -> io::Result<std::ffi::OsString> {
+    std::fs::read_link(path).map(PathBuf::into_os_string)
+}
+
+/// Opens a child for reading (for `--hash`) by full path.
+pub(crate) fn open_file_at(
+    dir: &DirFd,
+    name: &OsStr,
+    follow: bool,
+) -> io::Result<std::fs::File> {
+    open_file_root(&dir.join(name), follow)
+}
+
+pub(crate) fn open_file_root(
+    path: &Path,
+    _follow: bool,
+) -> io::Result<std::fs::File> {
+    std::fs::File::open(path)
+}
+
+/// Whether a child directory has no entries (for `--empty`), by full path.
+pub(crate) fn empty_dir_at(
+    dir: &DirFd,
+    name: &OsStr,
+    follow: bool,
+) -> io::Result<bool> {
+    empty_dir_root(&dir.join(name), follow)
+}
+
+pub(crate) fn empty_dir_root(path: &Path, _follow: bool) -> io::Result<bool> {
+    Ok(std::fs::read_dir(path)?.next().is_none())
+}
+
+/// Removes a child (for `--delete`) by full path.
+pub(crate) fn unlink_at(
+    dir: &DirFd,
+    name: &OsStr,
+    is_dir: bool,
+) -> io::Result<()> {
+    unlink_root(&dir.join(name), is_dir)
+}
+
+pub(crate) fn unlink_root(path: &Path, is_dir: bool) -> io::Result<()> {
+    if is_dir {
+        std::fs::remove_dir(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// Makes `cmd` start in `dir` (for `-execdir`). Without an fd this is a
+/// path lookup at spawn time, so it is not race-free here.
+pub(crate) fn enter_dir(cmd: &mut std::process::Command, dir: &Arc<DirFd>) {
+    cmd.current_dir(&**dir);
 }
 
 fn map_type(ft: std::fs::FileType) -> EntryType {
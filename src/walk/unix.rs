@@ -11,14 +11,20 @@
 
 use crate::filetype::EntryType;
 use crate::meta::{self, Meta};
+use crate::report::Errors;
+use crate::stats::Stats;
 use rustix::fs::{self, AtFlags, FileType as RFileType, Mode, OFlags, CWD};
 use rustix::io::Errno;
 use std::ffi::{OsStr, OsString};
+use std::fs::File;
 use std::io;
 use std::os::fd::OwnedFd;
 use std::os::unix::ffi::OsStrExt;
-use std::path::{Path, PathBuf};
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::Command;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 /// A shareable open-directory anchor: one fd, refcounted by the walker via
 /// `Arc` so children can `openat`/`statat` relative to it.
@@ -108,17 +114,20 @@ pub(crate) fn dir_id(fd: &DirFd) -> io::Result<(u64, u64)> {
     Ok((st.st_dev as u64, st.st_ino as u64))
 }
 
-/// Invokes `f` for each entry (excluding `.`/`..`) with its full path — built
-/// directly from the raw name, no intermediate `OsString` or collected `Vec` —
-/// and its resolved type, stopping early when `f` returns `false`.
+/// Invokes `f` for each entry (excluding `.`/`..`) with its raw name — no
+/// intermediate `OsString` or collected `Vec`; the caller joins it onto
+/// `parent` only when it needs the path — and its resolved type, stopping
+/// early when `f` returns `false`.
 ///
 /// `DT_UNKNOWN` is resolved with a `statat` relative to the directory fd
 /// (cheaper and TOCTOU-consistent with the anchored open); an entry whose type
-/// cannot be resolved is skipped.
+/// cannot be resolved is reported to `errors` and skipped.
 pub(crate) fn for_each_entry(
     fd: &DirFd,
     parent: &Path,
-    mut f: impl FnMut(PathBuf, &OsStr, EntryType) -> bool,
+    errors: &Errors,
+    stats: &Stats,
+    mut f: impl FnMut(&OsStr, EntryType) -> bool,
 ) -> io::Result<()> {
     let dir = fs::Dir::read_from(fd)?;
     for entry in dir {
@@ -130,14 +139,20 @@ pub(crate) fn for_each_entry(
         let name = OsStr::from_bytes(bytes);
         let ty = match map_type(entry.file_type()) {
             Some(t) => t,
-            None => match statat_type(fd, name) {
-                Ok(t) => t,
-                Err(_) => continue,
-            },
+            None => {
+                stats.dt_unknown();
+                match statat_type(fd, name) {
+                    Ok(t) => t,
+                    Err(e) => {
+                        errors.io(&parent.join(name), &e);
+                        continue;
+                    }
+                }
+            }
         };
         // `name` borrows the dir-stream buffer (valid this call); the caller
         // reuses it for the leaf-relative statx
-        if !f(parent.join(name), name, ty) {
+        if !f(name, ty) {
             break;
         }
     }
@@ -184,6 +199,9 @@ fn do_stat(
         gid: sx.stx_gid,
         nlink: u64::from(sx.stx_nlink),
         ino: sx.stx_ino,
+        blocks: sx.stx_blocks,
+        rdev: (sx.stx_rdev_major, sx.stx_rdev_minor),
+        dev: fs::makedev(sx.stx_dev_major, sx.stx_dev_minor),
     })
 }
 
@@ -200,6 +218,7 @@ fn to_statx_flags(mask: u32) -> rustix::fs::StatxFlags {
         (meta::mask::GID, S::GID),
         (meta::mask::NLINK, S::NLINK),
         (meta::mask::INO, S::INO),
+        (meta::mask::BLOCKS, S::BLOCKS),
     ];
     pairs.iter().fold(S::empty(), |acc, &(bit, flag)| {
         if mask & bit != 0 {
@@ -228,6 +247,9 @@ fn do_stat(
         gid: st.st_gid,
         nlink: st.st_nlink as u64,
         ino: st.st_ino as u64,
+        blocks: st.st_blocks as u64,
+        rdev: (fs::major(st.st_rdev as _), fs::minor(st.st_rdev as _)),
+        dev: st.st_dev as u64,
     })
 }
 
@@ -269,20 +291,109 @@ fn do_access(
 }
 
 /// Reads a symlink's target (for `-lname`), relative to the parent dir fd.
-pub(crate) fn readlink_at(dir: &DirFd, name: &OsStr) -> Option<OsString> {
+pub(crate) fn readlink_at(dir: &DirFd, name: &OsStr) -> io::Result<OsString> {
     do_readlink(dir, name)
 }
 
-pub(crate) fn readlink_root(path: &Path) -> Option<OsString> {
+pub(crate) fn readlink_root(path: &Path) -> io::Result<OsString> {
     do_readlink(CWD, path)
 }
 
 fn do_readlink(
     dirfd: impl rustix::fd::AsFd,
     path: impl rustix::path::Arg,
-) -> Option<OsString> {
-    let target = fs::readlinkat(dirfd, path, Vec::new()).ok()?;
-    Some(OsStr::from_bytes(target.to_bytes()).to_owned())
+) -> io::Result<OsString> {
+    let target = fs::readlinkat(dirfd, path, Vec::new())?;
+    Ok(OsStr::from_bytes(target.to_bytes()).to_owned())
+}
+
+/// Opens a child for reading (for `--hash`) relative to the parent dir fd;
+/// a final symlink is followed only under `follow`.
+pub(crate) fn open_file_at(
+    dir: &DirFd,
+    name: &OsStr,
+    follow: bool,
+) -> io::Result<File> {
+    do_open_file(dir, name, follow)
+}
+
+pub(crate) fn open_file_root(path: &Path, follow: bool) -> io::Result<File> {
+    do_open_file(CWD, path, follow)
+}
+
+fn do_open_file(
+    dirfd: impl rustix::fd::AsFd,
+    path: impl rustix::path::Arg,
+    follow: bool,
+) -> io::Result<File> {
+    // O_NONBLOCK: a file swapped for a FIFO mid-walk cannot hang the worker
+    let mut flags =
+        OFlags::RDONLY | OFlags::CLOEXEC | OFlags::NOCTTY | OFlags::NONBLOCK;
+    if !follow {
+        flags |= OFlags::NOFOLLOW;
+    }
+    Ok(fs::openat(dirfd, path, flags, Mode::empty())?.into())
+}
+
+/// Whether a child directory has no entries besides `.`/`..` (for
+/// `--empty`), opened relative to the parent dir fd.
+pub(crate) fn empty_dir_at(
+    dir: &DirFd,
+    name: &OsStr,
+    follow: bool,
+) -> io::Result<bool> {
+    no_entries(fs::openat(dir, name, dir_flags(follow), Mode::empty())?)
+}
+
+pub(crate) fn empty_dir_root(path: &Path, follow: bool) -> io::Result<bool> {
+    no_entries(open_root(path, follow)?)
+}
+
+fn no_entries(fd: DirFd) -> io::Result<bool> {
+    for entry in fs::Dir::new(fd)? {
+        let entry = entry?;
+        let bytes = entry.file_name().to_bytes();
+        if bytes != b"." && bytes != b".." {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Removes a child (for `--delete`) with `unlinkat` on its parent dir fd;
+/// `dir` selects `AT_REMOVEDIR`. A symlink is removed, never followed.
+pub(crate) fn unlink_at(
+    dir: &DirFd,
+    name: &OsStr,
+    is_dir: bool,
+) -> io::Result<()> {
+    do_unlink(dir, name, is_dir)
+}
+
+pub(crate) fn unlink_root(path: &Path, is_dir: bool) -> io::Result<()> {
+    do_unlink(CWD, path, is_dir)
+}
+
+fn do_unlink(
+    dirfd: impl rustix::fd::AsFd,
+    path: impl rustix::path::Arg,
+    is_dir: bool,
+) -> io::Result<()> {
+    let flags = if is_dir { AtFlags::REMOVEDIR } else { AtFlags::empty() };
+    Ok(fs::unlinkat(dirfd, path, flags)?)
+}
+
+/// Makes `cmd` start in `dir` (for `-execdir`): the child `fchdir`s to the
+/// held fd between fork and exec, so the directory is never looked up again.
+pub(crate) fn enter_dir(cmd: &mut Command, dir: &Arc<DirFd>) {
+    let dir = Arc::clone(dir);
+    // SAFETY: fchdir is one async-signal-safe syscall and allocates nothing;
+    // `dir` stays alive inside the Command until after the spawn.
+    unsafe {
+        cmd.pre_exec(move || {
+            rustix::process::fchdir(&*dir).map_err(io::Error::from)
+        });
+    }
 }
 
 /// Resolves a `DT_UNKNOWN` entry's own type via a `statat` relative to its
@@ -337,10 +448,16 @@ mod tests {
         };
 
         let mut names = Vec::new();
-        for_each_entry(&dir, tmp.path(), |path, _leaf, _ty| {
-            names.push(path.file_name().unwrap().to_owned());
-            true
-        })
+        for_each_entry(
+            &dir,
+            tmp.path(),
+            &Errors::default(),
+            &Stats::default(),
+            |leaf, _ty| {
+                names.push(leaf.to_owned());
+                true
+            },
+        )
         .unwrap();
 
         stop.store(true, Ordering::Relaxed);
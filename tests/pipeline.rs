@@ -45,25 +45,66 @@ fn base_args(paths: Vec<PathBuf>, file_type: Vec<FileType>) -> Args {
         max_results: None,
         name: None,
         regex: None,
+        iname: None,
+        iregex: None,
         case_insensitive: false,
+        any: false,
         file_type,
         meta: minifind::meta::Predicates::default(),
         path_glob: None,
+        ipath_glob: None,
         lname: None,
+        ilname: None,
         access: 0,
+        contains: None,
+        max_filesize: None,
+        mime: None,
+        class: None,
         exclude: None,
         null: false,
+        printf: None,
+        json: None,
+        ls: false,
+        tree: false,
+        summarize: false,
+        summarize_depth: None,
+        print: true,
+        exec: vec![],
+        jobs: 1,
+        group_output: false,
+        ignore_readdir_race: false,
+        quiet_errors: false,
+        stats: false,
+        delete: false,
+        sort: None,
+        ordered: false,
+        reverse: false,
+        top: None,
+        count: None,
+        histogram: None,
+        duplicates: false,
+        hash: None,
+        by: minifind::top::By::Size,
+        path_style: minifind::pathstyle::Style::Absolute,
+        quote: None,
+        color: minifind::color::When::Never,
+        expr: None,
     }
 }
 
 /// Runs the real pipeline and parses its newline-delimited output into paths.
 fn run_capture(args: &Args) -> Vec<PathBuf> {
+    parse_paths(&run_bytes(args))
+}
+
+/// Runs the real pipeline and returns its raw output bytes.
+fn run_bytes(args: &Args) -> Vec<u8> {
     let sink = SharedSink(Arc::new(Mutex::new(Vec::new())));
     let out = sink.clone();
     // run joins its output thread before returning, so all bytes are present.
     minifind::run(args, move || out).unwrap();
     let bytes = sink.0.lock().unwrap();
-    parse_paths(&bytes)
+    bytes.clone()
 }
 
 #[cfg(unix)]
@@ -143,6 +184,53 @@ fn regex_filter_matches_against_full_path() {
     );
 }
 
+/// `2024/a.JPG`, `2024/b.txt`, `2023/c.jpg` under a fresh temp dir.
+fn photo_tree() -> TempDir {
+    let tmp = TempDir::new().unwrap();
+    for (dir, file) in
+        [("2024", "a.JPG"), ("2024", "b.txt"), ("2023", "c.jpg")]
+    {
+        std::fs::create_dir_all(tmp.path().join(dir)).unwrap();
+        std::fs::write(tmp.path().join(dir).join(file), b"x").unwrap();
+    }
+    tmp
+}
+
+#[test]
+fn iname_and_regex_must_both_match() {
+    let tmp = photo_tree();
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::File]);
+    args.iname = Some(vec!["*.jpg".to_string()]);
+    args.regex = Some(vec![".*/2024/.*".to_string()]);
+    let results = run_capture(&args);
+    assert_eq!(results.len(), 1, "got {results:?}");
+    assert!(results[0].ends_with("2024/a.JPG"));
+}
+
+#[test]
+fn any_mode_matches_name_or_regex() {
+    let tmp = photo_tree();
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::File]);
+    args.iname = Some(vec!["*.jpg".to_string()]);
+    args.regex = Some(vec![".*/2024/.*".to_string()]);
+    args.any = true;
+    assert_eq!(run_capture(&args).len(), 3);
+}
+
+#[test]
+fn name_stays_case_sensitive_beside_iregex() {
+    let tmp = photo_tree();
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::File]);
+    args.name = Some(vec!["*.jpg".to_string()]);
+    args.iregex = Some(vec![".*/2023/.*".to_string()]);
+    let results = run_capture(&args);
+    assert_eq!(results.len(), 1, "got {results:?}");
+    assert!(results[0].ends_with("2023/c.jpg"));
+}
+
 #[test]
 fn file_type_filter_still_descends_into_directories() {
     let tmp = TempDir::new().unwrap();
@@ -563,3 +651,934 @@ fn output_unchanged_under_scan_rate_limit() {
 
     assert_eq!(baseline, limited);
 }
+
+/// Builds a `-name` leaf for expression tests.
+fn name_leaf(pattern: &str) -> minifind::expr::Token {
+    use minifind::expr::{Pred, Spelling, Token};
+    Token::Pred(Pred::name(pattern.to_string(), false), Spelling::Find)
+}
+
+#[test]
+fn expression_or_matches_either_name() {
+    use minifind::expr::{self, Token};
+    let tmp = TempDir::new().unwrap();
+    for f in ["a.rs", "b.toml", "c.txt"] {
+        std::fs::write(tmp.path().join(f), b"x").unwrap();
+    }
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::File]);
+    args.expr = Some(
+        expr::parse(vec![name_leaf("*.rs"), Token::Or, name_leaf("*.toml")])
+            .unwrap(),
+    );
+    let results = run_capture(&args);
+    assert!(results.iter().any(|p| p.ends_with("a.rs")));
+    assert!(results.iter().any(|p| p.ends_with("b.toml")));
+    assert!(!results.iter().any(|p| p.ends_with("c.txt")));
+}
+
+#[test]
+fn expression_not_path_excludes_subtree_matches() {
+    use minifind::expr::{self, Pred, Spelling, Token};
+    let tmp = TempDir::new().unwrap();
+    std::fs::create_dir(tmp.path().join("target")).unwrap();
+    std::fs::write(tmp.path().join("target/gen.rs"), b"x").unwrap();
+    std::fs::write(tmp.path().join("lib.rs"), b"x").unwrap();
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::File]);
+    // \( -name '*.rs' \) ! -path '*/target/*'
+    args.expr = Some(
+        expr::parse(vec![
+            Token::Open,
+            name_leaf("*.rs"),
+            Token::Close,
+            Token::Not,
+            Token::Pred(
+                Pred::path("*/target/*".to_string(), false),
+                Spelling::Find,
+            ),
+        ])
+        .unwrap(),
+    );
+    let results = run_capture(&args);
+    assert!(results.iter().any(|p| p.ends_with("lib.rs")));
+    assert!(!results.iter().any(|p| p.ends_with("gen.rs")));
+}
+
+#[test]
+fn expression_or_over_metadata_leaves() {
+    use minifind::expr::{self, Pred, Spelling, Token};
+    use minifind::meta::{Predicates, SizePred};
+    let tmp = TempDir::new().unwrap();
+    std::fs::write(tmp.path().join("big.bin"), vec![0u8; 5000]).unwrap();
+    std::fs::write(tmp.path().join("small.bin"), b"x").unwrap();
+    std::fs::write(tmp.path().join("keep.txt"), b"x").unwrap();
+    let big = Predicates {
+        size: Some(SizePred::parse("+1k").unwrap()),
+        ..Default::default()
+    };
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::File]);
+    // -size +1k -o -name '*.txt'
+    args.expr = Some(
+        expr::parse(vec![
+            Token::Pred(Pred::Meta(Box::new(big)), Spelling::Find),
+            Token::Or,
+            name_leaf("*.txt"),
+        ])
+        .unwrap(),
+    );
+    let results = run_capture(&args);
+    assert!(results.iter().any(|p| p.ends_with("big.bin")));
+    assert!(results.iter().any(|p| p.ends_with("keep.txt")));
+    assert!(!results.iter().any(|p| p.ends_with("small.bin")));
+}
+
+#[test]
+fn printf_renders_directives_per_entry() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::create_dir(tmp.path().join("sub")).unwrap();
+    std::fs::write(tmp.path().join("sub/five.txt"), b"12345").unwrap();
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::File]);
+    args.printf =
+        Some(minifind::printf::Format::parse("%P|%f|%s|%d|%y\\0").unwrap());
+    assert_eq!(run_bytes(&args), b"sub/five.txt|five.txt|5|2|f\0");
+}
+
+#[test]
+fn printf_start_and_relative_follow_relative_to() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::create_dir(tmp.path().join("sub")).unwrap();
+    std::fs::write(tmp.path().join("sub/five.txt"), b"12345").unwrap();
+    let mut args = base_args(
+        vec![tmp.path().to_path_buf()],
+        vec![FileType::File, FileType::Directory],
+    );
+    args.path_style = minifind::pathstyle::Style::Root;
+    args.ordered = true;
+    args.printf =
+        Some(minifind::printf::Format::parse("%p|%P|%H|%f|%h\\n").unwrap());
+    // %H is the starting point as printed (`.`) at every depth
+    let want = ".||.|.|.\n\
+                sub|sub|.|sub|.\n\
+                sub/five.txt|sub/five.txt|.|five.txt|sub\n";
+    assert_eq!(String::from_utf8(run_bytes(&args)).unwrap(), want);
+}
+
+#[cfg(unix)]
+#[test]
+fn printf_symbolic_mode_and_link_target() {
+    let tmp = TempDir::new().unwrap();
+    std::os::unix::fs::symlink("missing", tmp.path().join("dangling"))
+        .unwrap();
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::Symlink]);
+    args.printf =
+        Some(minifind::printf::Format::parse("%M %f -> %l %Y\\n").unwrap());
+    assert_eq!(run_bytes(&args), b"lrwxrwxrwx dangling -> missing N\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn printf_quotes_names_when_asked_to() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::write(tmp.path().join("a\nb"), b"xy").unwrap();
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::File]);
+    let mut format = minifind::printf::Format::parse("%f %s\\n").unwrap();
+    args.printf = Some(format.clone());
+    assert_eq!(run_bytes(&args), b"a\nb 2\n");
+    format.quote_names(minifind::quote::Style::Shell);
+    args.printf = Some(format);
+    assert_eq!(run_bytes(&args), b"'a'$'\\n''b' 2\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn follow_describes_dangling_links_by_the_link_itself() {
+    let tmp = TempDir::new().unwrap();
+    std::os::unix::fs::symlink("missing", tmp.path().join("dangling"))
+        .unwrap();
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::Symlink]);
+    args.follow_symlinks = true;
+    args.max_filesize = Some(1024);
+    args.printf = Some(minifind::printf::Format::parse("%f %s\\n").unwrap());
+    let sink = SharedSink(Arc::new(Mutex::new(Vec::new())));
+    let out = sink.clone();
+    let status = minifind::run(&args, move || out).unwrap();
+    assert!(status.success());
+    assert_eq!(*sink.0.lock().unwrap(), b"dangling 7\n");
+}
+
+#[test]
+fn json_emits_one_object_per_entry_with_selected_fields() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::write(tmp.path().join("q\"x.txt"), b"abc").unwrap();
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::File]);
+    args.json =
+        Some(minifind::json::Format::parse("name,type,depth,size").unwrap());
+    assert_eq!(
+        String::from_utf8(run_bytes(&args)).unwrap(),
+        "{\"name\":\"q\\\"x.txt\",\"type\":\"file\",\"depth\":1,\"size\":3}\n"
+    );
+}
+
+#[cfg(unix)]
+fn exec_command(
+    mode: minifind::exec::Mode,
+    words: &[&str],
+) -> minifind::exec::Command {
+    minifind::exec::Command::parse(mode, words.iter().map(|w| (*w).into()))
+        .unwrap()
+}
+
+#[cfg(unix)]
+#[test]
+fn exec_runs_per_entry_and_batched_without_printing() {
+    use minifind::exec::Mode::Exec;
+    let tmp = TempDir::new().unwrap();
+    let dir = tmp.path().join("d");
+    std::fs::create_dir(&dir).unwrap();
+    for n in ["a", "b", "c"] {
+        std::fs::write(dir.join(n), b"").unwrap();
+    }
+    let each = tmp.path().join("each.log");
+    let batch = tmp.path().join("batch.log");
+    let mut args = base_args(vec![dir], vec![FileType::File]);
+    args.print = false;
+    args.jobs = 2;
+    args.exec = vec![
+        exec_command(
+            Exec,
+            &[
+                "sh",
+                "-c",
+                &format!("echo \"$1\" >> {}", each.display()),
+                "sh",
+                "{}",
+                ";",
+            ],
+        ),
+        exec_command(
+            Exec,
+            &[
+                "sh",
+                "-c",
+                &format!("echo $# >> {}", batch.display()),
+                "sh",
+                "{}",
+                "+",
+            ],
+        ),
+    ];
+    let sink = SharedSink(Arc::new(Mutex::new(Vec::new())));
+    let out = sink.clone();
+    let status = minifind::run(&args, move || out).unwrap();
+    assert!(status.success());
+    assert!(sink.0.lock().unwrap().is_empty());
+
+    let mut lines: Vec<String> = std::fs::read_to_string(&each)
+        .unwrap()
+        .lines()
+        .map(|l| l.rsplit('/').next().unwrap().to_owned())
+        .collect();
+    lines.sort();
+    assert_eq!(lines, ["a", "b", "c"]);
+    assert_eq!(std::fs::read_to_string(&batch).unwrap(), "3\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn exec_failing_batch_fails_the_run_but_semicolon_does_not() {
+    use minifind::exec::Mode::Exec;
+    let tmp = TempDir::new().unwrap();
+    std::fs::write(tmp.path().join("f"), b"").unwrap();
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::File]);
+    args.exec = vec![exec_command(Exec, &["false", "{}", ";"])];
+    let status = minifind::run(&args, io::sink).unwrap();
+    assert!(status.success());
+
+    args.exec = vec![exec_command(Exec, &["false", "{}", "+"])];
+    let status = minifind::run(&args, io::sink).unwrap();
+    assert!(!status.success());
+}
+
+#[cfg(unix)]
+#[test]
+fn exec_semicolon_status_never_unmatches_the_entry() {
+    use minifind::exec::Mode::Exec;
+    let tmp = TempDir::new().unwrap();
+    std::fs::write(tmp.path().join("f"), b"").unwrap();
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::File]);
+    args.print = true;
+    args.exec = vec![exec_command(Exec, &["false", "{}", ";"])];
+    // the command is an action: --print still prints what it ran on
+    let printed = run_capture(&args);
+    assert_eq!(printed, [tmp.path().join("f")]);
+}
+
+#[cfg(unix)]
+#[test]
+fn execdir_runs_in_the_parent_on_dot_slash_leaf() {
+    use minifind::exec::Mode::ExecDir;
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path().canonicalize().unwrap().join("r");
+    for d in ["x", "y"] {
+        std::fs::create_dir_all(root.join(d)).unwrap();
+        for n in ["1", "2"] {
+            std::fs::write(root.join(d).join(n), b"").unwrap();
+        }
+    }
+    let each = tmp.path().join("each.log");
+    let batch = tmp.path().join("batch.log");
+    let mut args = base_args(vec![root.clone()], vec![FileType::File]);
+    args.print = false;
+    args.exec = vec![
+        exec_command(
+            ExecDir,
+            &[
+                "sh",
+                "-c",
+                &format!("echo \"$(pwd -P) $1\" >> {}", each.display()),
+                "sh",
+                "{}",
+                ";",
+            ],
+        ),
+        exec_command(
+            ExecDir,
+            &[
+                "sh",
+                "-c",
+                &format!("echo \"$(pwd -P) $*\" >> {}", batch.display()),
+                "sh",
+                "{}",
+                "+",
+            ],
+        ),
+    ];
+    assert!(minifind::run(&args, io::sink).unwrap().success());
+
+    let sorted = |p: &PathBuf| {
+        let mut v: Vec<String> = std::fs::read_to_string(p)
+            .unwrap()
+            .lines()
+            .map(str::to_owned)
+            .collect();
+        v.sort();
+        v
+    };
+    let (x, y) = (root.join("x"), root.join("y"));
+    let (x, y) = (x.display(), y.display());
+    assert_eq!(
+        sorted(&each),
+        [
+            format!("{x} ./1"),
+            format!("{x} ./2"),
+            format!("{y} ./1"),
+            format!("{y} ./2")
+        ]
+    );
+    // one `+` batch per directory
+    let batches = sorted(&batch);
+    assert_eq!(batches.len(), 2);
+    assert!(batches[0].starts_with(&format!("{x} ./")));
+    assert!(batches[1].starts_with(&format!("{y} ./")));
+}
+
+#[cfg(unix)]
+#[test]
+fn execdir_under_sort_finds_the_parent_by_path() {
+    use minifind::exec::Mode::ExecDir;
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path().canonicalize().unwrap().join("r");
+    for d in ["y", "x"] {
+        std::fs::create_dir_all(root.join(d)).unwrap();
+        for n in ["2", "1"] {
+            std::fs::write(root.join(d).join(n), b"").unwrap();
+        }
+    }
+    let log = tmp.path().join("each.log");
+    let mut args = base_args(vec![root.clone()], vec![FileType::File]);
+    args.print = false;
+    args.threads = 4;
+    // the printed form is root-relative; the command still runs in place
+    args.path_style = minifind::pathstyle::Style::Root;
+    args.sort = Some(minifind::sort::Key::Path);
+    args.exec = vec![exec_command(
+        ExecDir,
+        &[
+            "sh",
+            "-c",
+            &format!("echo \"$(pwd -P) $1\" >> {}", log.display()),
+            "sh",
+            "{}",
+            ";",
+        ],
+    )];
+    assert!(minifind::run(&args, io::sink).unwrap().success());
+    let (x, y) = (root.join("x"), root.join("y"));
+    let (x, y) = (x.display(), y.display());
+    assert_eq!(
+        std::fs::read_to_string(&log).unwrap(),
+        format!("{x} ./1\n{x} ./2\n{y} ./1\n{y} ./2\n")
+    );
+}
+
+#[test]
+fn delete_removes_matches_and_emptied_directories() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path().join("cache");
+    std::fs::create_dir_all(root.join("a/b")).unwrap();
+    std::fs::write(root.join("a/b/x.tmp"), b"").unwrap();
+    std::fs::write(root.join("a/keep.txt"), b"").unwrap();
+    std::fs::write(root.join("y.tmp"), b"").unwrap();
+
+    // only *.tmp: the files go, every directory stays
+    let mut args = base_args(
+        vec![root.clone()],
+        vec![FileType::File, FileType::Directory],
+    );
+    args.name = Some(vec!["*.tmp".to_string()]);
+    args.delete = true;
+    args.print = false;
+    assert!(minifind::run(&args, io::sink).unwrap().success());
+    assert!(!root.join("a/b/x.tmp").exists() && !root.join("y.tmp").exists());
+    assert!(root.join("a/keep.txt").exists() && root.join("a/b").is_dir());
+
+    // everything: a/b is emptied before it is removed, the root last
+    let mut args = base_args(
+        vec![root.clone()],
+        vec![FileType::File, FileType::Directory],
+    );
+    args.delete = true;
+    args.threads = 4;
+    assert!(minifind::run(&args, io::sink).unwrap().success());
+    assert!(!root.exists());
+}
+
+#[test]
+fn delete_stops_at_max_results() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path().join("d");
+    std::fs::create_dir_all(root.join("sub")).unwrap();
+    for name in ["1", "2", "3", "sub/4", "sub/5"] {
+        std::fs::write(root.join(name), b"").unwrap();
+    }
+    let mut args = base_args(
+        vec![root.clone()],
+        vec![FileType::File, FileType::Directory],
+    );
+    args.delete = true;
+    args.print = true;
+    args.max_results = Some(1);
+    args.threads = 4;
+    let printed = run_capture(&args);
+    assert_eq!(printed.len(), 1);
+    assert!(!printed[0].exists());
+    // the one printed match went, nothing past the cap did
+    let left = ["1", "2", "3", "sub/4", "sub/5"]
+        .iter()
+        .filter(|n| root.join(n).exists())
+        .count();
+    assert_eq!(left, 4);
+    assert!(root.join("sub").is_dir());
+}
+
+#[test]
+fn delete_sorts_by_the_size_read_before_removal() {
+    let tmp = TempDir::new().unwrap();
+    for (name, len) in [("big", 9), ("small", 1), ("mid", 4)] {
+        std::fs::write(tmp.path().join(name), vec![0u8; len]).unwrap();
+    }
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::File]);
+    args.delete = true;
+    args.print = true;
+    args.quiet_errors = true;
+    args.sort = Some(minifind::sort::Key::Size);
+    let sink = SharedSink(Arc::new(Mutex::new(Vec::new())));
+    let out = sink.clone();
+    let status = minifind::run(&args, move || out).unwrap();
+    assert!(status.success(), "{status:?}");
+    let names: Vec<_> = parse_paths(&sink.0.lock().unwrap())
+        .iter()
+        .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names, ["small", "mid", "big"]);
+    assert_eq!(std::fs::read_dir(tmp.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn delete_failure_fails_the_run() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::create_dir(tmp.path().join("full")).unwrap();
+    std::fs::write(tmp.path().join("full/keep"), b"").unwrap();
+    // the directory matches, its file does not: rmdir hits ENOTEMPTY
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::Directory]);
+    args.min_depth = Some(1);
+    args.delete = true;
+    let status = minifind::run(&args, io::sink).unwrap();
+    assert!(status.delete_failed && !status.success());
+    assert!(tmp.path().join("full/keep").exists());
+}
+
+#[test]
+fn vanished_root_is_counted_and_fails_the_run() {
+    let tmp = TempDir::new().unwrap();
+    let gone = tmp.path().join("gone");
+    let mut args = base_args(vec![gone], vec![FileType::Directory]);
+    args.quiet_errors = true;
+    let status = minifind::run(&args, io::sink).unwrap();
+    assert_eq!(status.errors, 1);
+    assert!(!status.success());
+
+    let args = base_args(vec![tmp.path().to_path_buf()], vec![FileType::File]);
+    assert!(minifind::run(&args, io::sink).unwrap().success());
+}
+
+#[test]
+fn ignore_readdir_race_still_reports_missing_starting_points() {
+    let tmp = TempDir::new().unwrap();
+    let mut args =
+        base_args(vec![tmp.path().join("gone")], vec![FileType::Directory]);
+    args.ignore_readdir_race = true;
+    args.quiet_errors = true;
+    let status = minifind::run(&args, io::sink).unwrap();
+    assert_eq!(status.errors, 1);
+    assert!(!status.success());
+}
+
+/// A sequential pre-order walk in `read_dir` order, as find prints it.
+fn sequential_order(
+    dir: &std::path::Path,
+    post: bool,
+    out: &mut Vec<PathBuf>,
+) {
+    if !post {
+        out.push(dir.to_path_buf());
+    }
+    for e in std::fs::read_dir(dir).unwrap() {
+        let e = e.unwrap();
+        if e.file_type().unwrap().is_dir() {
+            sequential_order(&e.path(), post, out);
+        } else {
+            out.push(e.path());
+        }
+    }
+    if post {
+        out.push(dir.to_path_buf());
+    }
+}
+
+fn ordered_tree(root: &std::path::Path) {
+    for d in ["a/b/c", "a/d", "e", "f/g"] {
+        std::fs::create_dir_all(root.join(d)).unwrap();
+    }
+    for f in ["a/1", "a/b/2", "a/b/c/3", "e/4", "5", "f/g/6"] {
+        std::fs::write(root.join(f), b"").unwrap();
+    }
+}
+
+#[test]
+fn ordered_reproduces_sequential_preorder() {
+    let tmp = TempDir::new().unwrap();
+    ordered_tree(tmp.path());
+    let mut expected = Vec::new();
+    sequential_order(tmp.path(), false, &mut expected);
+
+    let mut args = base_args(
+        vec![tmp.path().to_path_buf()],
+        vec![FileType::File, FileType::Directory],
+    );
+    args.threads = 4;
+    args.ordered = true;
+    for _ in 0..5 {
+        assert_eq!(run_capture(&args), expected);
+    }
+}
+
+#[test]
+fn ordered_delete_streams_each_directory_after_its_contents() {
+    for _ in 0..5 {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().join("r");
+        ordered_tree(&root);
+        let mut expected = Vec::new();
+        sequential_order(&root, true, &mut expected);
+
+        let mut args = base_args(
+            vec![root.clone()],
+            vec![FileType::File, FileType::Directory],
+        );
+        args.threads = 4;
+        args.ordered = true;
+        args.delete = true;
+        args.print = true;
+        assert_eq!(run_capture(&args), expected);
+        assert!(!root.exists());
+    }
+}
+
+#[test]
+fn sort_by_size_then_path_reversed_and_capped() {
+    let tmp = TempDir::new().unwrap();
+    for (name, len) in [("b", 3), ("a", 3), ("c", 10), ("d", 1)] {
+        std::fs::write(tmp.path().join(name), vec![0u8; len]).unwrap();
+    }
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::File]);
+    args.threads = 4;
+    args.sort = Some(minifind::sort::Key::Size);
+    let names = |args: &Args| -> Vec<String> {
+        run_capture(args)
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    };
+    assert_eq!(names(&args), ["d", "a", "b", "c"]);
+    args.reverse = true;
+    args.max_results = Some(2);
+    assert_eq!(names(&args), ["c", "b"]);
+}
+
+#[test]
+fn relative_to_root_prints_and_matches_below_the_start() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::create_dir(tmp.path().join("sub")).unwrap();
+    std::fs::write(tmp.path().join("sub/f"), b"x").unwrap();
+    let mut args = base_args(
+        vec![tmp.path().to_path_buf()],
+        vec![FileType::File, FileType::Directory],
+    );
+    args.path_style = minifind::pathstyle::Style::Root;
+    let mut got = run_capture(&args);
+    got.sort();
+    assert_eq!(got, [PathBuf::from("."), "sub".into(), "sub/f".into()]);
+    // --path sees the printed form, so an anchored pattern matches it
+    args.path_glob = Some(vec!["sub/*".to_string()]);
+    assert_eq!(run_capture(&args), [PathBuf::from("sub/f")]);
+}
+
+#[cfg(unix)]
+#[test]
+fn canonical_resolves_followed_directory_links() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path().canonicalize().unwrap();
+    std::fs::create_dir_all(root.join("real")).unwrap();
+    std::fs::write(root.join("real/f"), b"x").unwrap();
+    std::fs::create_dir(root.join("walk")).unwrap();
+    std::os::unix::fs::symlink(root.join("real"), root.join("walk/link"))
+        .unwrap();
+    let mut args = base_args(vec![root.join("walk")], vec![FileType::File]);
+    args.follow_symlinks = true;
+    assert_eq!(run_capture(&args), [root.join("walk/link/f")]);
+    args.path_style = minifind::pathstyle::Style::Canonical;
+    assert_eq!(run_capture(&args), [root.join("real/f")]);
+}
+
+#[cfg(unix)]
+#[test]
+fn quote_shell_keeps_hostile_names_on_one_line() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::write(tmp.path().join("a\nb"), b"x").unwrap();
+    std::fs::write(tmp.path().join("plain"), b"x").unwrap();
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::File]);
+    args.path_style = minifind::pathstyle::Style::Root;
+    args.quote = Some(minifind::quote::Style::Shell);
+    args.sort = Some(minifind::sort::Key::Name);
+    assert_eq!(run_bytes(&args), b"'a'$'\\n''b'\nplain\n");
+}
+
+#[test]
+fn color_paints_by_type_and_suffix() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::create_dir(tmp.path().join("d")).unwrap();
+    std::fs::write(tmp.path().join("a.rs"), b"x").unwrap();
+    std::fs::write(tmp.path().join("b"), b"x").unwrap();
+    let mut args = base_args(
+        vec![tmp.path().to_path_buf()],
+        vec![FileType::File, FileType::Directory],
+    );
+    args.min_depth = Some(1);
+    args.path_style = minifind::pathstyle::Style::Root;
+    args.sort = Some(minifind::sort::Key::Name);
+    args.color = minifind::color::When::Always;
+    // no other test in this binary reads LS_COLORS
+    std::env::set_var("LS_COLORS", "di=01;34:*.rs=33");
+    let out = String::from_utf8(run_bytes(&args)).unwrap();
+    assert_eq!(out, "\x1b[33ma.rs\x1b[0m\nb\n\x1b[01;34md\x1b[0m\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn ls_lists_links_with_their_target() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::write(tmp.path().join("f"), b"hello").unwrap();
+    std::os::unix::fs::symlink("f", tmp.path().join("l")).unwrap();
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::Symlink]);
+    args.ls = true;
+    let out = String::from_utf8(run_bytes(&args)).unwrap();
+    let fields: Vec<&str> = out.split_whitespace().collect();
+    assert_eq!(fields[2], "lrwxrwxrwx", "{out}");
+    assert_eq!(fields[6], "1", "{out}");
+    assert!(out.ends_with("/l -> f\n"), "{out}");
+}
+
+#[cfg(unix)]
+#[test]
+fn ls_quotes_the_path_and_the_link_target() {
+    let tmp = TempDir::new().unwrap();
+    std::os::unix::fs::symlink("t\nx", tmp.path().join("a\nl")).unwrap();
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::Symlink]);
+    args.ls = true;
+    args.path_style = minifind::pathstyle::Style::Root;
+    args.quote = Some(minifind::quote::Style::Shell);
+    let out = String::from_utf8(run_bytes(&args)).unwrap();
+    assert!(out.ends_with(" 'a'$'\\n''l' -> 't'$'\\n''x'\n"), "{out}");
+}
+
+#[test]
+fn tree_draws_matches_with_their_ancestors() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::create_dir_all(tmp.path().join("b/deep")).unwrap();
+    std::fs::write(tmp.path().join("a.rs"), b"x").unwrap();
+    std::fs::write(tmp.path().join("b/deep/c.rs"), b"x").unwrap();
+    std::fs::write(tmp.path().join("b/skip.txt"), b"x").unwrap();
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::File]);
+    args.threads = 4;
+    args.name = Some(vec!["*.rs".to_string()]);
+    args.tree = true;
+    let root = tmp.path().display();
+    let expected =
+        format!("{root}\n├── a.rs\n└── b\n    └── deep\n        └── c.rs\n");
+    assert_eq!(String::from_utf8(run_bytes(&args)).unwrap(), expected);
+}
+
+#[test]
+fn stats_leave_stdout_untouched() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::create_dir(tmp.path().join("d")).unwrap();
+    std::fs::write(tmp.path().join("d/f"), b"x").unwrap();
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::File]);
+    args.sort = Some(minifind::sort::Key::Name);
+    let plain = run_bytes(&args);
+    args.stats = true;
+    assert_eq!(run_bytes(&args), plain, "the summary goes to stderr only");
+}
+
+#[test]
+fn summarize_totals_matches_per_directory() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::create_dir_all(tmp.path().join("a/b")).unwrap();
+    std::fs::write(tmp.path().join("a/b/x.rs"), vec![0; 5000]).unwrap();
+    std::fs::write(tmp.path().join("a/y.txt"), vec![0; 100]).unwrap();
+    std::fs::write(tmp.path().join("z.rs"), vec![0; 30]).unwrap();
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::File]);
+    args.name = Some(vec!["*.rs".to_string()]);
+    args.summarize = true;
+    args.summarize_depth = Some(1);
+    args.path_style = minifind::pathstyle::Style::Root;
+    args.sort = Some(minifind::sort::Key::Path);
+    let out = String::from_utf8(run_bytes(&args)).unwrap();
+    let totals: Vec<(&str, &str)> = out
+        .lines()
+        .map(|l| {
+            let mut f = l.split('\t');
+            let (_kib, bytes) = (f.next().unwrap(), f.next().unwrap());
+            (f.next().unwrap(), bytes)
+        })
+        .collect();
+    assert_eq!(totals, [(".", "5030"), ("a", "5000")], "{out}");
+}
+
+#[test]
+fn top_keeps_the_largest_with_their_size() {
+    let tmp = TempDir::new().unwrap();
+    for (name, len) in [("a", 10), ("b", 300), ("c", 20), ("d", 300)] {
+        std::fs::write(tmp.path().join(name), vec![0; len]).unwrap();
+    }
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::File]);
+    args.top = Some(3);
+    args.threads = 4;
+    let out = String::from_utf8(run_bytes(&args)).unwrap();
+    let p = |n: &str| tmp.path().join(n).display().to_string();
+    assert_eq!(
+        out,
+        format!("300\t{}\n300\t{}\n20\t{}\n", p("b"), p("d"), p("c"))
+    );
+    args.reverse = true;
+    args.top = Some(1);
+    let out = String::from_utf8(run_bytes(&args)).unwrap();
+    assert_eq!(out, format!("10\t{}\n", p("a")));
+}
+
+#[test]
+fn count_tallies_matches_per_root_and_type() {
+    let (one, two) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+    std::fs::create_dir(one.path().join("d")).unwrap();
+    std::fs::write(one.path().join("d/f"), b"x").unwrap();
+    std::fs::write(two.path().join("g"), b"x").unwrap();
+    let mut args = base_args(
+        vec![one.path().to_path_buf(), two.path().to_path_buf()],
+        vec![FileType::File],
+    );
+    args.count = Some(minifind::count::By::Root);
+    let (a, b) = (one.path().display(), two.path().display());
+    let out = String::from_utf8(run_bytes(&args)).unwrap();
+    assert_eq!(out, format!("1\t{a}\n1\t{b}\n2\ttotal\n"));
+    args.file_type = vec![FileType::File, FileType::Directory];
+    args.count = Some(minifind::count::By::Type);
+    let out = String::from_utf8(run_bytes(&args)).unwrap();
+    assert_eq!(
+        out,
+        format!(
+            "1\tf\t{a}\n2\td\t{a}\n1\tf\t{b}\n1\td\t{b}\n2\tf\ttotal\n\
+             3\td\ttotal\n"
+        )
+    );
+}
+
+#[test]
+fn histogram_buckets_matches_by_extension() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::create_dir(tmp.path().join("d")).unwrap();
+    std::fs::write(tmp.path().join("a.txt"), b"xx").unwrap();
+    std::fs::write(tmp.path().join("d/b.txt"), b"xxx").unwrap();
+    std::fs::write(tmp.path().join("d/c.log"), b"x").unwrap();
+    std::fs::write(tmp.path().join("README"), b"").unwrap();
+    let mut args =
+        base_args(vec![tmp.path().to_path_buf()], vec![FileType::File]);
+    args.histogram = Some(minifind::histogram::By::Ext);
+    let out = String::from_utf8(run_bytes(&args)).unwrap();
+    assert_eq!(out, "2\t5\ttxt\n1\t1\tlog\n1\t0\t(none)\n");
+    args.histogram = Some(minifind::histogram::By::Size);
+    let out = String::from_utf8(run_bytes(&args)).unwrap();
+    assert_eq!(out, "1\t0\t0\n3\t6\t< 1K\n");
+}
+
+#[test]
+fn duplicates_print_groups_of_identical_files() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::create_dir(tmp.path().join("d")).unwrap();
+    std::fs::write(tmp.path().join("a"), b"same").unwrap();
+    std::fs::write(tmp.path().join("d/b"), b"same").unwrap();
+    std::fs::write(tmp.path().join("c"), b"else").unwrap();
+    std::fs::hard_link(tmp.path().join("a"), tmp.path().join("d/link"))
+        .unwrap();
+    let mut args = base_args(
+        vec![tmp.path().to_path_buf()],
+        vec![FileType::File, FileType::Directory],
+    );
+    args.duplicates = true;
+    let out = String::from_utf8(run_bytes(&args)).unwrap();
+    let root = tmp.path().display();
+    assert_eq!(out, format!("{root}/a\n{root}/d/b\n\n"));
+}
+
+#[test]
+fn hash_prints_sha256sum_lines_for_regular_files() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::create_dir(tmp.path().join("d")).unwrap();
+    std::fs::write(tmp.path().join("d/abc"), b"abc").unwrap();
+    let mut args = base_args(
+        vec![tmp.path().to_path_buf()],
+        vec![FileType::File, FileType::Directory],
+    );
+    args.hash = Some(minifind::hash::Algo::Sha256);
+    let out = String::from_utf8(run_bytes(&args)).unwrap();
+    assert_eq!(
+        out,
+        format!(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  \
+             {}/d/abc\n",
+            tmp.path().display()
+        )
+    );
+}
+
+#[test]
+fn contains_matches_text_files_by_content() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::create_dir(tmp.path().join("d")).unwrap();
+    std::fs::write(tmp.path().join("d/hit.txt"), b"one\nFIXME: two\n")
+        .unwrap();
+    std::fs::write(tmp.path().join("miss.txt"), b"one\ntwo\n").unwrap();
+    std::fs::write(tmp.path().join("bin"), b"FIXME\0\x01").unwrap();
+    let big = format!("FIXME\n{}", "x".repeat(2000));
+    std::fs::write(tmp.path().join("big.txt"), big).unwrap();
+    let mut args = base_args(
+        vec![tmp.path().to_path_buf()],
+        vec![FileType::File, FileType::Directory],
+    );
+    args.contains = Some("^FIXME".into());
+    args.max_filesize = Some(1024);
+    let out = String::from_utf8(run_bytes(&args)).unwrap();
+    assert_eq!(out, format!("{}/d/hit.txt\n", tmp.path().display()));
+}
+
+#[test]
+fn mime_matches_files_by_magic_number() {
+    let tmp = TempDir::new().unwrap();
+    // a PNG under a misleading name, and text under an image one
+    std::fs::write(tmp.path().join("x.txt"), b"\x89PNG\r\n\x1a\n\0\0\0\x0d")
+        .unwrap();
+    std::fs::write(tmp.path().join("y.png"), b"plain text\n").unwrap();
+    let mut args = base_args(
+        vec![tmp.path().to_path_buf()],
+        vec![FileType::File, FileType::Directory],
+    );
+    args.mime = Some(vec!["IMAGE/*".into()]);
+    args.printf = Some(minifind::printf::Format::parse("%f %e\\n").unwrap());
+    assert_eq!(run_bytes(&args), b"x.txt image/png\n");
+    args.mime = None;
+    let out = String::from_utf8(run_bytes(&args)).unwrap();
+    assert!(out.contains("x.txt image/png\n"));
+    assert!(out.contains("y.png text/plain\n"));
+}
+
+#[test]
+fn text_binary_and_encoding_classify_by_head() {
+    let tmp = TempDir::new().unwrap();
+    let write = |n: &str, data: &[u8]| {
+        std::fs::write(tmp.path().join(n), data).unwrap();
+    };
+    write("ascii.txt", b"hello\n");
+    write("latin1.txt", b"caf\xe9\n");
+    write("utf16.txt", b"\xff\xfeh\0i\0");
+    write("prog", b"\x7fELF\x02\x01\x01\0");
+    std::fs::create_dir(tmp.path().join("dir")).unwrap();
+    let mut args = base_args(
+        vec![tmp.path().to_path_buf()],
+        vec![FileType::File, FileType::Directory],
+    );
+    let names = |args: &Args| {
+        let mut got: Vec<String> = run_capture(args)
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into())
+            .collect();
+        got.sort();
+        got
+    };
+    args.class = Some(minifind::content::Class::Text);
+    assert_eq!(names(&args), ["ascii.txt", "latin1.txt", "utf16.txt"]);
+    args.class = Some(minifind::content::Class::Binary);
+    assert_eq!(names(&args), ["prog"]);
+    args.class = Some(minifind::content::Class::Encoding(
+        minifind::content::Encoding::Latin1,
+    ));
+    assert_eq!(names(&args), ["latin1.txt"]);
+}